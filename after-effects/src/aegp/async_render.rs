@@ -0,0 +1,166 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+use crate::aegp::suites::Render;
+use crate::aegp::{FrameReceipt, LayerRenderOptions, RenderOptions};
+use crate::{Error, ae_sys};
+
+#[derive(Default)]
+struct Shared {
+    result: Option<Result<FrameReceipt, Error>>,
+    waker: Option<Waker>,
+    dropped: bool,
+}
+
+enum State {
+    /// Item frame waiting for its turn on the main thread.
+    Item { options: RenderOptions, yielded: bool },
+    /// Layer frame waiting for the render not started yet.
+    Layer { options: LayerRenderOptions },
+    /// Layer frame rendering asynchronously in After Effects.
+    Rendering { request_id: ae_sys::AEGP_AsyncRequestId, shared: Rc<RefCell<Shared>> },
+    Done,
+}
+
+/// A frame being rendered, created by [`render_item_frame_blocking()`] or [`render_layer_frame()`].
+///
+/// Resolves to the checked out [`FrameReceipt`], which is checked in again when dropped.
+/// Dropping the future before it completed cancels the render.
+///
+/// It must be polled on the main thread, e.g. from a future passed to [`MainThreadQueue::spawn`](crate::aegp::MainThreadQueue::spawn).
+#[must_use = "futures do nothing unless polled"]
+pub struct RenderFrame {
+    state: State,
+}
+
+/// Renders a frame of an item, described by [`RenderOptions`], blocking the main thread while it renders.
+///
+/// After Effects doesn't provide an asynchronous render call for items, so the frame is rendered synchronously
+/// the second time the future is polled, which blocks the main thread until the frame is done. The first poll yields,
+/// so all asynchronous renders get started before. Several item frames awaited together, e.g. with
+/// [`join_all()`](crate::aegp::join_all), still render one after the other. For true concurrency, prefer [`render_layer_frame()`].
+///
+/// The render can only be canceled before it starts, i.e. before the second poll. Once it runs, the main thread is blocked
+/// until it's done, so there is nothing that could call [`RenderFrame::cancel()`].
+///
+/// Example usage:
+/// ```ignore
+/// let queue = MainThreadQueue::install(plugin_id)?;
+/// let thumbnails = queue.spawn(async move {
+///     let frames = aegp::join_all(times.into_iter().map(|time| {
+///         let options = RenderOptions::from_item(item, plugin_id)?;
+///         options.set_time(time)?;
+///         Ok(aegp::render_item_frame_blocking(options))
+///     }).collect::<Result<Vec<_>, Error>>()?).await;
+///     frames.into_iter().map(|frame| make_thumbnail(&frame?)).collect::<Result<Vec<_>, Error>>()
+/// });
+/// ```
+pub fn render_item_frame_blocking(options: RenderOptions) -> RenderFrame {
+    RenderFrame {
+        state: State::Item { options, yielded: false },
+    }
+}
+
+/// Renders a layer frame with effects applied, described by [`LayerRenderOptions`].
+///
+/// The render is started the first time the future is polled and runs asynchronously in After Effects,
+/// so any number of these can be awaited concurrently, e.g. with [`join_all()`](crate::aegp::join_all).
+pub fn render_layer_frame(options: LayerRenderOptions) -> RenderFrame {
+    RenderFrame {
+        state: State::Layer { options },
+    }
+}
+
+impl RenderFrame {
+    /// Cancels the render. The future resolves to [`Error::InterruptCancel`] if it didn't complete already.
+    pub fn cancel(&mut self) {
+        // An item render which didn't start yet is simply dropped.
+        if let State::Rendering { request_id, shared } = std::mem::replace(&mut self.state, State::Done) {
+            shared.borrow_mut().dropped = true;
+            if let Ok(render) = Render::new() {
+                let _ = render.cancel_async_request(request_id);
+            }
+        }
+    }
+}
+
+impl Future for RenderFrame {
+    type Output = Result<FrameReceipt, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match &mut self.state {
+            State::Item { options, yielded } => {
+                if !*yielded {
+                    *yielded = true;
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+                let result = Render::new().and_then(|render| {
+                    render.render_and_checkout_frame(options.as_ptr(), None::<fn() -> bool>)
+                });
+                self.state = State::Done;
+                Poll::Ready(result.map(FrameReceipt::from_raw_owned))
+            }
+            State::Layer { options } => {
+                let shared = Rc::new(RefCell::new(Shared {
+                    waker: Some(cx.waker().clone()),
+                    ..Default::default()
+                }));
+                let callback_shared = shared.clone();
+                let request_id = Render::new()?.render_layer_frame_async(options.as_ptr(), move |_, was_canceled, error, receipt| {
+                    let mut shared = callback_shared.borrow_mut();
+                    let receipt = (!receipt.is_null()).then(|| FrameReceipt::from_raw_owned(receipt));
+                    if shared.dropped {
+                        // Nobody's waiting for it anymore, check it in right away.
+                        return;
+                    }
+                    shared.result = Some(match receipt {
+                        _ if was_canceled => Err(Error::InterruptCancel),
+                        _ if error != Error::None => Err(error),
+                        Some(receipt) => Ok(receipt),
+                        None => Err(Error::Generic),
+                    });
+                    if let Some(waker) = shared.waker.take() {
+                        waker.wake();
+                    }
+                })?;
+                // The callback runs on the main thread too, but may have been called synchronously, e.g. for cached frames.
+                let result = shared.borrow_mut().result.take();
+                match result {
+                    Some(result) => {
+                        self.state = State::Done;
+                        Poll::Ready(result)
+                    }
+                    None => {
+                        self.state = State::Rendering { request_id, shared };
+                        Poll::Pending
+                    }
+                }
+            }
+            State::Rendering { shared, .. } => {
+                let mut guard = shared.borrow_mut();
+                match guard.result.take() {
+                    Some(result) => {
+                        drop(guard);
+                        self.state = State::Done;
+                        Poll::Ready(result)
+                    }
+                    None => {
+                        guard.waker = Some(cx.waker().clone());
+                        Poll::Pending
+                    }
+                }
+            }
+            State::Done => Poll::Ready(Err(Error::InterruptCancel)),
+        }
+    }
+}
+
+impl Drop for RenderFrame {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
@@ -0,0 +1,265 @@
+use crate::aegp::suites::Camera as CameraSuite;
+use crate::aegp::{CameraType, Composition, FilmSizeUnits, Layer, LayerStream, StreamValue, TimeMode};
+use crate::{Error, Matrix4, Time};
+
+type Mat = [[f64; 4]; 4];
+
+/// Film size of the default camera, 36mm horizontal in pixels.
+const DEFAULT_FILM_SIZE: f64 = 36.0 * 72.0 / 25.4;
+
+/// Snapshot of a camera's geometry at a given time, for projecting between world (composition) space and screen pixels.
+///
+/// All matrices follow the After Effects convention: row-based, transforming row vectors (`p' = p * M`).
+/// With the `ultraviolet` or `nalgebra` feature enabled, convert them with `.into()`.
+///
+/// Camera space has its origin at the camera, +Z pointing in the viewing direction and +Y pointing down, like the composition.
+/// Screen space is in composition pixels, with the origin in the top left corner.
+#[derive(Debug, Clone, Copy)]
+pub struct CameraModel {
+    pub camera_type: CameraType,
+    /// Distance from the camera to the image plane, in pixels.
+    pub zoom: f64,
+    /// Distance to the plane in focus, in pixels.
+    pub focus_distance: f64,
+    /// In pixels.
+    pub aperture: f64,
+    pub depth_of_field: bool,
+    pub film_size_units: FilmSizeUnits,
+    /// In pixels, see [`focal_length_mm()`](Self::focal_length_mm).
+    pub film_size: f64,
+    pub comp_width: u32,
+    pub comp_height: u32,
+    /// Camera to world transform, including parenting and auto-orientation.
+    pub camera_to_world: Matrix4,
+}
+
+impl CameraModel {
+    /// Gathers the geometry of a camera layer at `time`, in composition time.
+    pub fn from_layer(camera: &Layer, time: Time) -> Result<Self, Error> {
+        let one_d = |stream| match camera.layer_stream_value(stream, TimeMode::CompTime, time, false)? {
+            StreamValue::OneD(value) => Ok(value),
+            _ => Err(Error::Parameter),
+        };
+        let suite = CameraSuite::new()?;
+        let (film_size_units, film_size) = suite.camera_film_size(camera.as_ptr())?;
+        let (comp_width, comp_height) = camera.parent_comp()?.item()?.dimensions()?;
+        Ok(Self {
+            camera_type: suite.camera_type(camera.as_ptr())?,
+            zoom: one_d(LayerStream::Zoom)?,
+            focus_distance: one_d(LayerStream::FocusDistance)?,
+            aperture: one_d(LayerStream::Aperture)?,
+            depth_of_field: one_d(LayerStream::DepthOfField)? != 0.0,
+            film_size_units,
+            film_size,
+            comp_width,
+            comp_height,
+            camera_to_world: camera.to_world_xform(time)?,
+        })
+    }
+
+    /// The camera After Effects renders with when a composition has no active camera.
+    pub fn default_for_comp(comp: &Composition) -> Result<Self, Error> {
+        let (width, height) = comp.item()?.dimensions()?;
+        let distance = CameraSuite::new()?.default_camera_distance_to_image_plane(comp.handle())?;
+        Ok(Self::default_for_size(width, height, distance))
+    }
+
+    /// Builds a camera from [`Effect::camera_matrix()`](crate::pf::Effect::camera_matrix), which is available to effects during render.
+    pub fn from_effect_camera(camera_matrix: Matrix4, dist_to_image_plane: f64, image_plane_width: i16, image_plane_height: i16) -> Self {
+        Self {
+            camera_to_world: camera_matrix,
+            ..Self::default_for_size(image_plane_width.max(0) as u32, image_plane_height.max(0) as u32, dist_to_image_plane)
+        }
+    }
+
+    fn default_for_size(width: u32, height: u32, distance: f64) -> Self {
+        Self {
+            camera_type: CameraType::Perspective,
+            zoom: distance,
+            focus_distance: distance,
+            aperture: 0.0,
+            depth_of_field: false,
+            film_size_units: FilmSizeUnits::Horizontal,
+            film_size: DEFAULT_FILM_SIZE,
+            comp_width: width,
+            comp_height: height,
+            camera_to_world: Matrix4(translation(width as f64 / 2.0, height as f64 / 2.0, -distance)),
+        }
+    }
+
+    pub fn is_orthographic(&self) -> bool {
+        self.camera_type == CameraType::Orthographic
+    }
+
+    /// Vertical field of view, in radians.
+    pub fn field_of_view(&self) -> f64 {
+        2.0 * (self.comp_height as f64 / 2.0).atan2(self.zoom)
+    }
+
+    /// Focal length of the lens in millimeters, derived from zoom and film size.
+    pub fn focal_length_mm(&self) -> f64 {
+        let (width, height) = (self.comp_width as f64, self.comp_height as f64);
+        let film_extent = match self.film_size_units {
+            FilmSizeUnits::Vertical => height,
+            FilmSizeUnits::Diagonal => width.hypot(height),
+            FilmSizeUnits::Horizontal | FilmSizeUnits::None => width,
+        };
+        let film_size_mm = self.film_size * 25.4 / 72.0;
+        self.zoom * film_size_mm / film_extent
+    }
+
+    /// World to camera transform.
+    pub fn view_matrix(&self) -> Matrix4 {
+        Matrix4(invert(&self.camera_to_world.0).unwrap_or(IDENTITY))
+    }
+
+    /// Camera space to screen space, in homogeneous coordinates.
+    ///
+    /// After the perspective divide, X and Y are screen pixels and Z is the inverse depth `1/z`.
+    /// Orthographic cameras keep the depth as is.
+    pub fn projection_matrix(&self) -> Matrix4 {
+        let (cx, cy) = (self.comp_width as f64 / 2.0, self.comp_height as f64 / 2.0);
+        Matrix4(if self.is_orthographic() {
+            let mut m = translation(cx, cy, 0.0);
+            m[2][2] = 1.0;
+            m
+        } else {
+            [
+                [self.zoom, 0.0,       0.0, 0.0],
+                [0.0,       self.zoom, 0.0, 0.0],
+                [cx,        cy,        0.0, 1.0],
+                [0.0,       0.0,       1.0, 0.0],
+            ]
+        })
+    }
+
+    /// World to screen space, the product of [`view_matrix()`](Self::view_matrix) and [`projection_matrix()`](Self::projection_matrix).
+    pub fn view_projection_matrix(&self) -> Matrix4 {
+        Matrix4(multiply(&self.view_matrix().0, &self.projection_matrix().0))
+    }
+
+    /// Projects a point in world space to `[x, y, depth]`, with `x` and `y` in screen pixels and `depth` the distance along the viewing direction.
+    ///
+    /// Returns `None` for points behind a perspective camera.
+    pub fn world_to_screen(&self, point: [f64; 3]) -> Option<[f64; 3]> {
+        let [x, y, z] = transform_point(&self.view_matrix().0, point);
+        let (cx, cy) = (self.comp_width as f64 / 2.0, self.comp_height as f64 / 2.0);
+        if self.is_orthographic() {
+            return Some([x + cx, y + cy, z]);
+        }
+        (z > f64::EPSILON).then(|| [x * self.zoom / z + cx, y * self.zoom / z + cy, z])
+    }
+
+    /// Inverse of [`world_to_screen()`](Self::world_to_screen): the point in world space at screen position `x`, `y`
+    /// and `depth` along the viewing direction.
+    pub fn screen_to_world(&self, [x, y, depth]: [f64; 3]) -> [f64; 3] {
+        let (x, y) = (x - self.comp_width as f64 / 2.0, y - self.comp_height as f64 / 2.0);
+        let camera_point = if self.is_orthographic() {
+            [x, y, depth]
+        } else {
+            [x * depth / self.zoom, y * depth / self.zoom, depth]
+        };
+        transform_point(&self.camera_to_world.0, camera_point)
+    }
+
+    /// Position of the camera in world space.
+    pub fn position(&self) -> [f64; 3] {
+        let m = &self.camera_to_world.0;
+        [m[3][0], m[3][1], m[3][2]]
+    }
+}
+
+const IDENTITY: Mat = [[1.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 1.0]];
+
+fn translation(x: f64, y: f64, z: f64) -> Mat {
+    let mut m = IDENTITY;
+    m[3] = [x, y, z, 1.0];
+    m
+}
+
+fn multiply(a: &Mat, b: &Mat) -> Mat {
+    std::array::from_fn(|row| std::array::from_fn(|col| (0..4).map(|i| a[row][i] * b[i][col]).sum()))
+}
+
+fn transform_point(m: &Mat, [x, y, z]: [f64; 3]) -> [f64; 3] {
+    let v = [x, y, z, 1.0];
+    let [x, y, z, w] = std::array::from_fn(|col| (0..4).map(|i| v[i] * m[i][col]).sum::<f64>());
+    if w != 0.0 && w != 1.0 { [x / w, y / w, z / w] } else { [x, y, z] }
+}
+
+/// Gauss-Jordan elimination with partial pivoting. Returns `None` for singular matrices.
+fn invert(m: &Mat) -> Option<Mat> {
+    let mut a = *m;
+    let mut inv = IDENTITY;
+    for col in 0..4 {
+        let pivot = (col..4).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        inv.swap(col, pivot);
+        let scale = 1.0 / a[col][col];
+        for k in 0..4 {
+            a[col][k] *= scale;
+            inv[col][k] *= scale;
+        }
+        for row in (0..4).filter(|&row| row != col) {
+            let factor = a[row][col];
+            for k in 0..4 {
+                a[row][k] -= factor * a[col][k];
+                inv[row][k] -= factor * inv[col][k];
+            }
+        }
+    }
+    Some(inv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: [f64; 3], b: [f64; 3]) {
+        assert!(a.iter().zip(&b).all(|(a, b)| (a - b).abs() < 1e-9), "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn default_camera_maps_comp_plane_to_pixels() {
+        let camera = CameraModel::default_for_size(1920, 1080, 2666.67);
+        // Layers at z = 0 are rendered at their composition position.
+        assert_close(camera.world_to_screen([100.0, 200.0, 0.0]).unwrap(), [100.0, 200.0, 2666.67]);
+        // Further away, points move towards the center.
+        assert_close(camera.world_to_screen([0.0, 0.0, 2666.67]).unwrap(), [480.0, 270.0, 2.0 * 2666.67]);
+        assert!(camera.world_to_screen([0.0, 0.0, -3000.0]).is_none());
+
+        let [x, y, w] = {
+            let m = camera.view_projection_matrix().0;
+            let v = [100.0, 200.0, 0.0, 1.0];
+            let r: [f64; 4] = std::array::from_fn(|col| (0..4).map(|i| v[i] * m[i][col]).sum());
+            [r[0], r[1], r[3]]
+        };
+        assert_close([x / w, y / w, 0.0], [100.0, 200.0, 0.0]);
+    }
+
+    #[test]
+    fn screen_to_world_inverts_projection() {
+        let mut camera = CameraModel::default_for_size(1920, 1080, 1500.0);
+        // Rotated 30° around Y and moved.
+        let (s, c) = 30f64.to_radians().sin_cos();
+        camera.camera_to_world = Matrix4([[c, 0.0, -s, 0.0], [0.0, 1.0, 0.0, 0.0], [s, 0.0, c, 0.0], [200.0, -50.0, -900.0, 1.0]]);
+        for point in [[0.0, 0.0, 0.0], [960.0, 540.0, 300.0], [-400.0, 1200.0, 50.0]] {
+            let screen = camera.world_to_screen(point).unwrap();
+            assert_close(camera.screen_to_world(screen), point);
+        }
+        camera.camera_type = CameraType::Orthographic;
+        let screen = camera.world_to_screen([10.0, 20.0, 30.0]).unwrap();
+        assert_close(camera.screen_to_world(screen), [10.0, 20.0, 30.0]);
+    }
+
+    #[test]
+    fn lens_properties() {
+        let camera = CameraModel::default_for_size(1920, 1080, 2666.67);
+        assert!((camera.focal_length_mm() - 50.0).abs() < 0.01);
+        assert!((camera.field_of_view().to_degrees() - 22.9).abs() < 0.1);
+        assert_close(camera.position(), [960.0, 540.0, -2666.67]);
+    }
+}
@@ -0,0 +1,441 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Weak, mpsc};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::ThreadId;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+use crate::aegp::PluginId;
+use crate::aegp::suites::{RegisterNonAegp, Utility};
+use crate::{Error, PicaBasicSuite, ae_sys, borrow_pica_basic_as_ptr};
+
+type BoxedFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+type LocalBoxedFuture = Pin<Box<dyn Future<Output = ()>>>;
+type WakeFn = unsafe extern "C" fn() -> ae_sys::A_Err;
+
+/// How long the idle hook asks After Effects to sleep while futures are pending, in 1/60th of a second.
+const PENDING_MAX_SLEEP: i32 = 1;
+
+thread_local! {
+    /// Futures posted with [`MainThreadQueue::spawn_local`], by task id. They live on the main thread only,
+    /// so they're never dropped elsewhere, even if the last reference to their task is.
+    static LOCAL_FUTURES: RefCell<HashMap<u64, LocalBoxedFuture>> = RefCell::default();
+}
+static NEXT_LOCAL_ID: AtomicU64 = AtomicU64::new(0);
+
+enum TaskFuture {
+    Send(BoxedFuture),
+    /// Key into [`LOCAL_FUTURES`].
+    Local(u64),
+}
+
+fn poll_local(id: u64, cx: &mut Context<'_>) -> Poll<()> {
+    // Taken out while polling, so the future may spawn other local futures.
+    let Some(mut future) = LOCAL_FUTURES.with_borrow_mut(|futures| futures.remove(&id)) else {
+        return Poll::Ready(());
+    };
+    let poll = future.as_mut().poll(cx);
+    if poll.is_pending() {
+        LOCAL_FUTURES.with_borrow_mut(|futures| futures.insert(id, future));
+    }
+    poll
+}
+
+struct Task {
+    future: Mutex<Option<TaskFuture>>,
+    ready: AtomicBool,
+    cancelled: Arc<AtomicBool>,
+    queue: Weak<Inner>,
+}
+
+impl Task {
+    /// Drops the future without completing it. Must be called on the main thread.
+    fn discard(&self) {
+        if let Some(TaskFuture::Local(id)) = self.future.lock().take() {
+            let future = LOCAL_FUTURES.with_borrow_mut(|futures| futures.remove(&id));
+            drop(future);
+        }
+    }
+}
+
+impl Wake for Task {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.ready.store(true, Ordering::Release);
+        if let Some(queue) = self.queue.upgrade() {
+            queue.wake_main_thread();
+        }
+    }
+}
+
+struct Inner {
+    incoming: Mutex<VecDeque<Arc<Task>>>,
+    tasks: Mutex<Vec<Arc<Task>>>,
+    main_thread: ThreadId,
+    pica_basic_suite_ptr: usize,
+    // `AEGP_CauseIdleRoutinesToBeCalled` is safe to call from any thread, but acquiring the suite isn't,
+    // so the function pointer is saved off on the main thread.
+    wake_fn: Option<WakeFn>,
+}
+
+impl Inner {
+    fn wake_main_thread(&self) {
+        if let Some(wake_fn) = self.wake_fn {
+            unsafe { wake_fn() };
+        }
+    }
+
+    fn is_main_thread(&self) -> bool {
+        std::thread::current().id() == self.main_thread
+    }
+
+    /// Polls all ready tasks. Must be called on the main thread. Returns `true` if any tasks are still pending.
+    fn drain(&self) -> bool {
+        let _pica = PicaBasicSuite::from_sp_basic_suite_raw(self.pica_basic_suite_ptr as *const _);
+
+        // Tasks may post new tasks, so don't hold the locks while polling.
+        let mut tasks = std::mem::take(&mut *self.tasks.lock());
+        tasks.extend(self.incoming.lock().drain(..));
+
+        tasks.retain(|task| {
+            if task.cancelled.load(Ordering::Acquire) {
+                task.discard();
+                return false;
+            }
+            if !task.ready.swap(false, Ordering::AcqRel) {
+                return true;
+            }
+            let mut future = task.future.lock();
+            let Some(fut) = future.as_mut() else { return false };
+
+            let waker = Waker::from(task.clone());
+            let mut cx = Context::from_waker(&waker);
+            let poll = crate::catch_ffi_panic("a task posted to the main thread queue", || match fut {
+                TaskFuture::Send(fut) => fut.as_mut().poll(&mut cx),
+                TaskFuture::Local(id) => poll_local(*id, &mut cx),
+            });
+            match poll {
+                Some(Poll::Pending) => true,
+                Some(Poll::Ready(())) => {
+                    *future = None;
+                    false
+                }
+                None => {
+                    *future = None;
+                    false
+                }
+            }
+        });
+
+        let mut pending = self.tasks.lock();
+        tasks.append(&mut pending);
+        *pending = tasks;
+        !pending.is_empty() || !self.incoming.lock().is_empty()
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        // Otherwise the local futures are only dropped when the main thread exits.
+        if self.is_main_thread() {
+            for task in self.tasks.get_mut().iter().chain(self.incoming.get_mut().iter()) {
+                task.discard();
+            }
+        }
+    }
+}
+
+/// Runs closures and futures posted from any thread (e.g. MFR render threads) on the main thread.
+///
+/// Many AEGP calls fail with [`Error::WrongThread`] when made outside of the main thread. Post them here instead,
+/// they're executed from an idle hook registered with [`RegisterNonAegp::register_idle_hook`], which is woken up
+/// with [`Utility::cause_idle_routines_to_be_called`].
+///
+/// Install the queue once on the main thread, e.g. in `GlobalSetup` or the AEGP entry point, and keep it in your global data.
+/// The queue can be cloned and shared across threads.
+///
+/// Example usage:
+/// ```ignore
+/// let queue = MainThreadQueue::install(plugin_id)?;
+///
+/// // Later, on a render thread:
+/// let comp_name = queue
+///     .run(move || ae::aegp::suites::Item::new()?.item_name(item, plugin_id))
+///     .wait_timeout(Duration::from_secs(1))?;
+/// ```
+#[derive(Clone)]
+pub struct MainThreadQueue {
+    inner: Arc<Inner>,
+}
+
+impl MainThreadQueue {
+    /// Registers the idle hook which drains the queue. Must be called on the main thread.
+    pub fn install(plugin_id: PluginId) -> Result<Self, Error> {
+        let utility = Utility::new()?;
+
+        let inner = Arc::new(Inner {
+            incoming: Mutex::new(VecDeque::new()),
+            tasks: Mutex::new(Vec::new()),
+            main_thread: std::thread::current().id(),
+            pica_basic_suite_ptr: borrow_pica_basic_as_ptr() as usize,
+            wake_fn: utility.cause_idle_routines_fn(),
+        });
+
+        RegisterNonAegp::new()?.register_idle_hook(
+            plugin_id,
+            Box::new(|inner: &mut Weak<Inner>, max_sleep| {
+                if let Some(inner) = inner.upgrade()
+                    && inner.drain()
+                {
+                    *max_sleep = (*max_sleep).min(PENDING_MAX_SLEEP);
+                }
+                Ok(())
+            }),
+            // The idle hook can't be unregistered, so it must not keep the queue alive.
+            Arc::downgrade(&inner),
+        )?;
+
+        Ok(Self { inner })
+    }
+
+    /// Posts a closure to run on the main thread. The result is delivered through the returned [`MainThreadTask`].
+    pub fn run<R, F>(&self, f: F) -> MainThreadTask<R>
+    where
+        R: Send + 'static,
+        F: FnOnce() -> Result<R, Error> + Send + 'static,
+    {
+        self.spawn(async move { f() })
+    }
+
+    /// Posts a future to be polled on the main thread, between other idle work.
+    ///
+    /// The future is polled again whenever its waker is woken, so it may await other main thread work,
+    /// like asynchronous renders.
+    pub fn spawn<R, F>(&self, future: F) -> MainThreadTask<R>
+    where
+        R: Send + 'static,
+        F: Future<Output = Result<R, Error>> + Send + 'static,
+    {
+        let (future, task) = completion(future);
+        self.push(TaskFuture::Send(Box::pin(future)), task)
+    }
+
+    /// Like [`spawn()`](Self::spawn), but for futures which aren't `Send`, e.g. because they hold AEGP handles
+    /// like [`render_item_frame_blocking()`](crate::aegp::render_item_frame_blocking).
+    ///
+    /// Returns [`Error::WrongThread`] when called from any other thread than the main thread.
+    pub fn spawn_local<R, F>(&self, future: F) -> Result<MainThreadTask<R>, Error>
+    where
+        R: 'static,
+        F: Future<Output = Result<R, Error>> + 'static,
+    {
+        if !self.inner.is_main_thread() {
+            return Err(Error::WrongThread);
+        }
+        let (future, task) = completion(future);
+        let id = NEXT_LOCAL_ID.fetch_add(1, Ordering::Relaxed);
+        LOCAL_FUTURES.with_borrow_mut(|futures| futures.insert(id, Box::pin(future)));
+        Ok(self.push(TaskFuture::Local(id), task))
+    }
+
+    fn push<R>(&self, future: TaskFuture, mut task: MainThreadTask<R>) -> MainThreadTask<R> {
+        task.queue = Arc::downgrade(&self.inner);
+        self.inner.incoming.lock().push_back(Arc::new(Task {
+            future: Mutex::new(Some(future)),
+            ready: AtomicBool::new(true),
+            cancelled: task.cancelled.clone(),
+            queue: Arc::downgrade(&self.inner),
+        }));
+        self.inner.wake_main_thread();
+        task
+    }
+
+    /// Runs all ready tasks right away. Must be called on the main thread.
+    /// Returns [`Error::WrongThread`] when called from any other thread.
+    pub fn run_pending(&self) -> Result<(), Error> {
+        if !self.inner.is_main_thread() {
+            return Err(Error::WrongThread);
+        }
+        self.inner.drain();
+        Ok(())
+    }
+
+    /// Returns `true` if called on the thread the queue was installed on.
+    pub fn is_main_thread(&self) -> bool {
+        self.inner.is_main_thread()
+    }
+
+    /// Number of tasks waiting to run or to complete.
+    pub fn pending(&self) -> usize {
+        self.inner.incoming.lock().len() + self.inner.tasks.lock().len()
+    }
+}
+
+/// Wraps `future` to send its result to the returned [`MainThreadTask`].
+fn completion<R, F>(future: F) -> (impl Future<Output = ()>, MainThreadTask<R>)
+where
+    F: Future<Output = Result<R, Error>>,
+{
+    let (sender, receiver) = mpsc::sync_channel(1);
+    let result_waker: Arc<Mutex<Option<Waker>>> = Arc::default();
+
+    let task_result_waker = result_waker.clone();
+    let future = async move {
+        let result = future.await;
+        let _ = sender.send(result);
+        if let Some(waker) = task_result_waker.lock().take() {
+            waker.wake();
+        }
+    };
+    let task = MainThreadTask {
+        receiver,
+        result_waker,
+        cancelled: Arc::new(AtomicBool::new(false)),
+        queue: Weak::new(),
+    };
+    (future, task)
+}
+
+/// The result of a closure or future posted to a [`MainThreadQueue`].
+///
+/// Dropping it cancels the task if it hasn't started yet. It can also be awaited.
+pub struct MainThreadTask<R> {
+    receiver: mpsc::Receiver<Result<R, Error>>,
+    result_waker: Arc<Mutex<Option<Waker>>>,
+    cancelled: Arc<AtomicBool>,
+    queue: Weak<Inner>,
+}
+
+impl<R> MainThreadTask<R> {
+    /// Blocks until the task completed.
+    ///
+    /// On the main thread, the idle hook can't run while we wait, and the task may wait for anything delivered
+    /// by After Effects' idle routines. So the queue is drained once, and the task is cancelled with
+    /// [`Error::WrongThread`] if it didn't complete. Await the task or use [`wait_timeout()`](Self::wait_timeout) instead.
+    pub fn wait(self) -> Result<R, Error> {
+        self.wait_until(None)
+    }
+
+    /// Blocks until the task completed, or cancels it and returns [`Error::InterruptCancel`] after `timeout`.
+    ///
+    /// On the main thread, the queue is drained here until then.
+    pub fn wait_timeout(self, timeout: Duration) -> Result<R, Error> {
+        self.wait_until(Some(Instant::now() + timeout))
+    }
+
+    /// Returns the result if the task already completed.
+    pub fn try_result(&self) -> Option<Result<R, Error>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => Some(Err(Error::InterruptCancel)),
+        }
+    }
+
+    /// Cancels the task. Has no effect if it's already running on the main thread.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+
+    fn wait_until(self, deadline: Option<Instant>) -> Result<R, Error> {
+        let on_main_thread = self.queue.upgrade().is_some_and(|queue| queue.is_main_thread());
+        loop {
+            let timeout = match deadline {
+                Some(deadline) => deadline.saturating_duration_since(Instant::now()),
+                None => Duration::from_millis(100),
+            };
+            if on_main_thread {
+                if let Some(queue) = self.queue.upgrade() {
+                    queue.drain();
+                }
+                if let Some(result) = self.try_result() {
+                    return result;
+                }
+                if deadline.is_none() {
+                    self.cancel();
+                    return Err(Error::WrongThread);
+                }
+                std::thread::sleep(Duration::from_millis(1));
+            } else {
+                match self.receiver.recv_timeout(timeout.min(Duration::from_millis(100))) {
+                    Ok(result) => return result,
+                    Err(mpsc::RecvTimeoutError::Disconnected) => return Err(Error::InterruptCancel),
+                    Err(mpsc::RecvTimeoutError::Timeout) => {}
+                }
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                self.cancel();
+                return Err(Error::InterruptCancel);
+            }
+        }
+    }
+}
+
+impl<R> Future for MainThreadTask<R> {
+    type Output = Result<R, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // Register first, so a result sent in between isn't missed.
+        *self.result_waker.lock() = Some(cx.waker().clone());
+        match self.try_result() {
+            Some(result) => Poll::Ready(result),
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl<R> Drop for MainThreadTask<R> {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Waits for all `futures` to complete, polling them concurrently. Resolves to their outputs in the same order.
+pub fn join_all<F: Future>(futures: impl IntoIterator<Item = F>) -> JoinAll<F> {
+    JoinAll {
+        futures: futures.into_iter().map(|f| Some(Box::pin(f))).collect(),
+        outputs: Vec::new(),
+    }
+}
+
+/// Future returned by [`join_all()`].
+#[must_use = "futures do nothing unless polled"]
+pub struct JoinAll<F: Future> {
+    futures: Vec<Option<Pin<Box<F>>>>,
+    outputs: Vec<Option<F::Output>>,
+}
+
+impl<F: Future> Unpin for JoinAll<F> {}
+
+impl<F: Future> Future for JoinAll<F> {
+    type Output = Vec<F::Output>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        this.outputs.resize_with(this.futures.len(), || None);
+        for (future, output) in this.futures.iter_mut().zip(this.outputs.iter_mut()) {
+            if let Some(fut) = future
+                && let Poll::Ready(value) = fut.as_mut().poll(cx)
+            {
+                *output = Some(value);
+                *future = None;
+            }
+        }
+        if this.futures.iter().all(Option::is_none) {
+            Poll::Ready(this.outputs.drain(..).map(|x| x.expect("output of a completed future")).collect())
+        } else {
+            Poll::Pending
+        }
+    }
+}
@@ -0,0 +1,629 @@
+use crate::aegp::suites::Stream as StreamSuite;
+use crate::aegp::{Mask, MaskFeatherFalloff, MaskFeatherInterp, MaskFeatherType, MaskMode, MaskOutline, MaskStream, PluginId, StreamValue, TimeMode, WorldType};
+use crate::pf::{MAX_CHANNEL8, MAX_CHANNEL16};
+use crate::{Error, Time, ae_sys, pf};
+
+/// Default flattening tolerance of [`MaskRasterizer`], in pixels.
+const FLATTEN_TOLERANCE: f64 = 0.1;
+
+/// A vertex of a [`BezierPath`]. Tangents are relative to the vertex, like in `AEGP_MaskVertex`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct BezierVertex {
+    pub position: [f64; 2],
+    pub tangent_in: [f64; 2],
+    pub tangent_out: [f64; 2],
+}
+
+impl From<ae_sys::AEGP_MaskVertex> for BezierVertex {
+    fn from(v: ae_sys::AEGP_MaskVertex) -> Self {
+        Self {
+            position: [v.x, v.y],
+            tangent_in: [v.tan_in_x, v.tan_in_y],
+            tangent_out: [v.tan_out_x, v.tan_out_y],
+        }
+    }
+}
+
+impl From<BezierVertex> for ae_sys::AEGP_MaskVertex {
+    fn from(v: BezierVertex) -> Self {
+        Self {
+            x: v.position[0],
+            y: v.position[1],
+            tan_in_x: v.tangent_in[0],
+            tan_in_y: v.tangent_in[1],
+            tan_out_x: v.tangent_out[0],
+            tan_out_y: v.tangent_out[1],
+        }
+    }
+}
+
+/// A variable width feather point, see [`MaskOutline`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeatherPoint {
+    /// Index of the segment the point is on.
+    pub segment: usize,
+    /// Position on the segment, `0.0..=1.0`.
+    pub position: f64,
+    /// Negative for [`MaskFeatherType::Inner`] points.
+    pub radius: f64,
+    pub corner_angle: f32,
+    pub tension: f32,
+    pub interp: MaskFeatherInterp,
+    pub feather_type: MaskFeatherType,
+}
+
+impl From<ae_sys::AEGP_MaskFeather> for FeatherPoint {
+    fn from(f: ae_sys::AEGP_MaskFeather) -> Self {
+        Self {
+            segment: f.segment.max(0) as usize,
+            position: f.segment_sF,
+            radius: f.radiusF,
+            corner_angle: f.ui_corner_angleF,
+            tension: f.tensionF,
+            interp: f.interp.into(),
+            feather_type: f.type_.into(),
+        }
+    }
+}
+
+impl From<FeatherPoint> for ae_sys::AEGP_MaskFeather {
+    fn from(f: FeatherPoint) -> Self {
+        Self {
+            segment: f.segment as _,
+            segment_sF: f.position,
+            radiusF: f.radius,
+            ui_corner_angleF: f.corner_angle,
+            tensionF: f.tension,
+            interp: f.interp.into(),
+            type_: f.feather_type.into(),
+        }
+    }
+}
+
+/// A cubic bezier path owned by Rust, copied out of a [`MaskOutline`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BezierPath {
+    pub vertices: Vec<BezierVertex>,
+    pub closed: bool,
+    pub feathers: Vec<FeatherPoint>,
+}
+
+impl BezierPath {
+    /// Copies vertices and feather points out of a mask outline.
+    pub fn from_outline(outline: &MaskOutline) -> Result<Self, Error> {
+        let closed = !outline.is_open()?;
+        let num_segments = outline.num_segments()?;
+        // Open paths have one more vertex than segments.
+        let num_vertices = if closed { num_segments } else { num_segments + 1 };
+        Ok(Self {
+            vertices: (0..num_vertices).map(|i| outline.vertex_info(i).map(Into::into)).collect::<Result<_, _>>()?,
+            closed,
+            feathers: (0..outline.num_feathers()?).map(|i| outline.feather_info(i).map(Into::into)).collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Replaces vertices and feather points of a mask outline with this path.
+    ///
+    /// The outline has to be written back to its stream afterwards, see [`suites::Stream::modify_stream_value()`](crate::aegp::suites::Stream::modify_stream_value).
+    pub fn write_to_outline(&self, outline: &MaskOutline) -> Result<(), Error> {
+        for i in (0..outline.num_feathers()?).rev() {
+            outline.delete_feather(i)?;
+        }
+        let segments = outline.num_segments()?;
+        let existing = if segments > 0 { segments + outline.is_open()? as i32 } else { 0 };
+        for i in (0..existing).rev() {
+            outline.delete_vertex(i)?;
+        }
+        for (i, vertex) in self.vertices.iter().enumerate() {
+            outline.create_vertex(i as i32)?;
+            outline.set_vertex_info(i as i32, &(*vertex).into())?;
+        }
+        outline.set_open(!self.closed)?;
+        for feather in &self.feathers {
+            outline.create_feather(Some((*feather).into()))?;
+        }
+        Ok(())
+    }
+
+    /// The segments as absolute cubic control points `[start, control 1, control 2, end]`.
+    pub fn segments(&self) -> impl Iterator<Item = [[f64; 2]; 4]> + '_ {
+        let n = self.vertices.len();
+        let count = match n {
+            0 | 1 => 0,
+            _ if self.closed => n,
+            _ => n - 1,
+        };
+        (0..count).map(move |i| {
+            let a = &self.vertices[i];
+            let b = &self.vertices[(i + 1) % n];
+            [
+                a.position,
+                add(a.position, a.tangent_out),
+                add(b.position, b.tangent_in),
+                b.position,
+            ]
+        })
+    }
+
+    /// Approximates the path with line segments, deviating at most `tolerance` from the curve.
+    ///
+    /// Closed paths don't repeat the first point at the end.
+    pub fn flatten(&self, tolerance: f64) -> Vec<[f64; 2]> {
+        let mut points: Vec<[f64; 2]> = self.vertices.first().map(|v| v.position).into_iter().collect();
+        for [p0, p1, p2, p3] in self.segments() {
+            // Wang's formula for the number of subdivisions.
+            let dd = |a: [f64; 2], b: [f64; 2], c: [f64; 2]| (a[0] - 2.0 * b[0] + c[0]).hypot(a[1] - 2.0 * b[1] + c[1]);
+            let max_dd = dd(p0, p1, p2).max(dd(p1, p2, p3));
+            let steps = (0.75 * max_dd / tolerance.max(1e-6)).sqrt().ceil().clamp(1.0, 1000.0) as usize;
+            points.extend((1..=steps).map(|step| cubic_point([p0, p1, p2, p3], step as f64 / steps as f64)));
+        }
+        if self.closed && points.len() > 1 {
+            points.pop();
+        }
+        points
+    }
+}
+
+fn add(a: [f64; 2], b: [f64; 2]) -> [f64; 2] {
+    [a[0] + b[0], a[1] + b[1]]
+}
+
+fn cubic_point([p0, p1, p2, p3]: [[f64; 2]; 4], t: f64) -> [f64; 2] {
+    let mt = 1.0 - t;
+    let (a, b, c, d) = (mt * mt * mt, 3.0 * mt * mt * t, 3.0 * mt * t * t, t * t * t);
+    [
+        a * p0[0] + b * p1[0] + c * p2[0] + d * p3[0],
+        a * p0[1] + b * p1[1] + c * p2[1] + d * p3[1],
+    ]
+}
+
+/// Everything needed to render a mask, sampled at a given time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaskShape {
+    pub path: BezierPath,
+    pub mode: MaskMode,
+    pub inverted: bool,
+    /// `0.0..=1.0`.
+    pub opacity: f64,
+    /// Horizontal and vertical feather, in pixels.
+    pub feather: [f64; 2],
+    pub feather_falloff: MaskFeatherFalloff,
+    /// In pixels.
+    pub expansion: f64,
+}
+
+impl Mask {
+    /// Copies the mask path at `time`, in layer time.
+    pub fn to_bezier_path(&self, plugin_id: PluginId, time: Time) -> Result<BezierPath, Error> {
+        let stream = self.stream(plugin_id, MaskStream::Outline)?;
+        // The outline handle is owned by the stream value, so it has to be read before the value is disposed.
+        StreamSuite::new()?.with_stream_value(stream.as_ptr(), plugin_id, TimeMode::LayerTime, time, false, |value| match value {
+            StreamValue::Mask(outline) => BezierPath::from_outline(&MaskOutline::from_handle(outline, false)),
+            _ => Err(Error::Parameter),
+        })?
+    }
+
+    /// Samples path, mode and feather of the mask at `time`, in layer time, for [`MaskRasterizer::add_mask()`].
+    pub fn shape(&self, plugin_id: PluginId, time: Time) -> Result<MaskShape, Error> {
+        let value = |stream| self.stream(plugin_id, stream)?.new_value(plugin_id, TimeMode::LayerTime, time, false);
+        let one_d = |stream| match value(stream)? {
+            StreamValue::OneD(x) => Ok(x),
+            _ => Err(Error::Parameter),
+        };
+        let feather = match value(MaskStream::Feather)? {
+            StreamValue::TwoD { x, y } | StreamValue::TwoDSpatial { x, y } => [x, y],
+            _ => return Err(Error::Parameter),
+        };
+        Ok(MaskShape {
+            path: self.to_bezier_path(plugin_id, time)?,
+            mode: self.mode()?,
+            inverted: self.is_inverted()?,
+            opacity: (one_d(MaskStream::Opacity)? / 100.0).clamp(0.0, 1.0),
+            feather,
+            feather_falloff: self.feather_falloff()?,
+            expansion: one_d(MaskStream::Expansion)?,
+        })
+    }
+}
+
+/// Renders masks into an anti-aliased alpha matte.
+///
+/// Masks are combined in the order they are added, like After Effects combines the masks of a layer from top to bottom.
+/// Coverage is computed exactly per pixel from the area covered by the path (non-zero winding), so there is no supersampling.
+///
+/// Limitations: open paths don't contribute (like in After Effects), variable width [`FeatherPoint`]s and
+/// [`MaskShape::expansion`] are ignored, and feathering is approximated with box filters,
+/// a single pass for [`MaskFeatherFalloff::Linear`] and three passes for [`MaskFeatherFalloff::Smooth`].
+///
+/// Example usage:
+/// ```ignore
+/// let mut matte = MaskRasterizer::new(out_layer.width(), out_layer.height())
+///     .with_transform([downsample_x, downsample_y], [-origin_x, -origin_y]);
+/// for i in 0..layer.num_masks()? {
+///     matte.add_mask(&layer.mask_by_index(i)?.shape(plugin_id, time)?);
+/// }
+/// matte.multiply_alpha(&mut out_layer)?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct MaskRasterizer {
+    width: usize,
+    height: usize,
+    scale: [f64; 2],
+    offset: [f64; 2],
+    alpha: Vec<f32>,
+    has_masks: bool,
+}
+
+impl MaskRasterizer {
+    /// Creates a fully opaque matte, which is what a layer without active masks looks like.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            scale: [1.0, 1.0],
+            offset: [0.0, 0.0],
+            alpha: vec![1.0; width * height],
+            has_masks: false,
+        }
+    }
+
+    /// Maps mask coordinates to matte pixels with `point * scale + offset`,
+    /// e.g. to account for downsampling or an output origin.
+    pub fn with_transform(mut self, scale: [f64; 2], offset: [f64; 2]) -> Self {
+        self.scale = scale;
+        self.offset = offset;
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The matte, row by row, `0.0..=1.0`.
+    pub fn alpha(&self) -> &[f32] {
+        &self.alpha
+    }
+
+    /// Combines `shape` with the masks added before, according to its [`MaskMode`].
+    pub fn add_mask(&mut self, shape: &MaskShape) {
+        if shape.mode == MaskMode::None {
+            return;
+        }
+        if !self.has_masks {
+            // Like in After Effects, a layer starts transparent unless its first mask takes away from it.
+            let start = if matches!(shape.mode, MaskMode::Subtract | MaskMode::Intersect | MaskMode::Darken) { 1.0 } else { 0.0 };
+            self.alpha.fill(start);
+            self.has_masks = true;
+        }
+
+        let mut coverage = vec![0.0; self.width * self.height];
+        if shape.path.closed {
+            let points: Vec<[f64; 2]> = shape
+                .path
+                .flatten(FLATTEN_TOLERANCE)
+                .into_iter()
+                .map(|[x, y]| [x * self.scale[0] + self.offset[0], y * self.scale[1] + self.offset[1]])
+                .collect();
+            fill_polygon(&points, self.width, self.height, &mut coverage);
+        }
+        let passes = match shape.feather_falloff {
+            MaskFeatherFalloff::Linear => 1,
+            MaskFeatherFalloff::Smooth => 3,
+        };
+        let feather = [shape.feather[0] * self.scale[0].abs(), shape.feather[1] * self.scale[1].abs()];
+        feather_coverage(&mut coverage, self.width, self.height, feather, passes);
+
+        let opacity = shape.opacity as f32;
+        for (a, &m) in self.alpha.iter_mut().zip(&coverage) {
+            let m = if shape.inverted { 1.0 - m } else { m } * opacity;
+            *a = combine(shape.mode, *a, m).clamp(0.0, 1.0);
+        }
+    }
+
+    /// Replaces the alpha channel of `layer` with the matte.
+    pub fn write_alpha(&self, layer: &mut pf::Layer) -> Result<(), Error> {
+        self.apply(layer, |_, m| m)
+    }
+
+    /// Multiplies the alpha channel of `layer` with the matte.
+    pub fn multiply_alpha(&self, layer: &mut pf::Layer) -> Result<(), Error> {
+        self.apply(layer, |a, m| a * m)
+    }
+
+    fn apply(&self, layer: &mut pf::Layer, op: impl Fn(f32, f32) -> f32) -> Result<(), Error> {
+        let world_type = layer.world_type();
+        if world_type == WorldType::None {
+            return Err(Error::BadCallbackParameter);
+        }
+        let (max8, max16) = (MAX_CHANNEL8 as f32, MAX_CHANNEL16 as f32);
+        for y in 0..layer.height().min(self.height) {
+            for x in 0..layer.width().min(self.width) {
+                let m = self.alpha[y * self.width + x];
+                match world_type {
+                    WorldType::U8 => {
+                        let p = layer.as_pixel8_mut(x, y);
+                        p.alpha = (op(p.alpha as f32 / max8, m) * max8).round() as u8;
+                    }
+                    WorldType::U15 => {
+                        let p = layer.as_pixel16_mut(x, y);
+                        p.alpha = (op(p.alpha as f32 / max16, m) * max16).round() as u16;
+                    }
+                    WorldType::F32 => {
+                        let p = layer.as_pixel32_mut(x, y);
+                        p.alpha = op(p.alpha, m);
+                    }
+                    WorldType::None => unreachable!(),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn combine(mode: MaskMode, a: f32, m: f32) -> f32 {
+    match mode {
+        MaskMode::None => a,
+        MaskMode::Add => a + m * (1.0 - a),
+        MaskMode::Accum => a + m,
+        MaskMode::Subtract => a * (1.0 - m),
+        MaskMode::Intersect => a * m,
+        MaskMode::Lighten => a.max(m),
+        MaskMode::Darken => a.min(m),
+        MaskMode::Difference => a + m - 2.0 * a * m,
+    }
+}
+
+/// Accumulates the signed area the closed polygon covers in each pixel and integrates it along the rows.
+fn fill_polygon(points: &[[f64; 2]], width: usize, height: usize, coverage: &mut [f32]) {
+    if points.len() < 3 || width == 0 || height == 0 {
+        return;
+    }
+    // Two extra columns catch the area right of the last pixel.
+    let stride = width + 2;
+    let mut acc = vec![0.0f64; stride * height];
+    for (i, &p0) in points.iter().enumerate() {
+        let p1 = points[(i + 1) % points.len()];
+        add_clipped_line(&mut acc, stride, width, height, p0, p1);
+    }
+    for (row, acc_row) in coverage.chunks_exact_mut(width).zip(acc.chunks_exact(stride)) {
+        let mut sum = 0.0;
+        for (c, a) in row.iter_mut().zip(acc_row) {
+            sum += a;
+            *c = sum.abs().min(1.0) as f32;
+        }
+    }
+}
+
+/// Splits the line where it crosses the left and right edge and clamps the outside parts onto the edge,
+/// which keeps the covered area inside the matte intact.
+fn add_clipped_line(acc: &mut [f64], stride: usize, width: usize, height: usize, p0: [f64; 2], p1: [f64; 2]) {
+    let right = width as f64;
+    let mut ts = [0.0, 1.0, 1.0, 1.0];
+    let mut n = 1;
+    for edge in [0.0, right] {
+        if (p0[0] - edge) * (p1[0] - edge) < 0.0 {
+            ts[n] = (edge - p0[0]) / (p1[0] - p0[0]);
+            n += 1;
+        }
+    }
+    ts[..n].sort_by(f64::total_cmp);
+    ts[n] = 1.0;
+    let at = |t: f64| [(p0[0] + (p1[0] - p0[0]) * t).clamp(0.0, right), p0[1] + (p1[1] - p0[1]) * t];
+    for pair in ts[..=n].windows(2) {
+        add_line(acc, stride, height, at(pair[0]), at(pair[1]));
+    }
+}
+
+/// Adds the exact area the line contributes to the pixels it crosses, and the remainder to the pixel right of it.
+/// `x` must be within `0.0..=width`.
+fn add_line(acc: &mut [f64], stride: usize, height: usize, p0: [f64; 2], p1: [f64; 2]) {
+    if p0[1] == p1[1] {
+        return;
+    }
+    let (dir, [xa, ya], [_, yb]) = if p0[1] < p1[1] { (1.0, p0, p1) } else { (-1.0, p1, p0) };
+    let dxdy = (p1[0] - p0[0]) / (p1[1] - p0[1]);
+    let (y_start, y_end) = (ya.max(0.0), yb.min(height as f64));
+    if y_start >= y_end {
+        return;
+    }
+    let mut x = xa + (y_start - ya) * dxdy;
+    for row in y_start as usize..y_end.ceil() as usize {
+        let dy = ((row + 1) as f64).min(y_end) - (row as f64).max(y_start);
+        let x_next = x + dxdy * dy;
+        let d = dy * dir;
+        let (left, right) = if x < x_next { (x, x_next) } else { (x_next, x) };
+        let line = &mut acc[row * stride..(row + 1) * stride];
+        let left_floor = left.floor();
+        let li = left_floor as usize;
+        let ri = right.ceil() as usize;
+        if ri <= li + 1 {
+            // Within a single pixel, the covered area is a trapezoid.
+            let xm = 0.5 * (x + x_next) - left_floor;
+            line[li] += d * (1.0 - xm);
+            line[li + 1] += d * xm;
+        } else {
+            let s = 1.0 / (right - left);
+            let lf = left - left_floor;
+            let a0 = 0.5 * s * (1.0 - lf) * (1.0 - lf);
+            let rf = right - right.ceil() + 1.0;
+            let am = 0.5 * s * rf * rf;
+            line[li] += d * a0;
+            if ri == li + 2 {
+                line[li + 1] += d * (1.0 - a0 - am);
+            } else {
+                let a1 = s * (1.5 - lf);
+                line[li + 1] += d * (a1 - a0);
+                for a in &mut line[li + 2..ri - 1] {
+                    *a += d * s;
+                }
+                let a2 = a1 + (ri - li - 3) as f64 * s;
+                line[ri - 1] += d * (1.0 - a2 - am);
+            }
+            line[ri] += d * am;
+        }
+        x = x_next;
+    }
+}
+
+/// Blurs the coverage with `passes` box filters per axis, spreading the edge over `feather` pixels.
+fn feather_coverage(coverage: &mut [f32], width: usize, height: usize, feather: [f64; 2], passes: usize) {
+    // Boxes of width `w / sqrt(n)` have the same variance as a single box of width `w`.
+    let box_width = |feather: f64| feather / (passes as f64).sqrt();
+    let (box_x, box_y) = (box_width(feather[0]), box_width(feather[1]));
+    let mut scratch = Vec::new();
+    if box_x > 0.0 {
+        for row in coverage.chunks_exact_mut(width) {
+            for _ in 0..passes {
+                box_filter(row, box_x, &mut scratch);
+            }
+        }
+    }
+    if box_y > 0.0 {
+        let mut column = vec![0.0; height];
+        for x in 0..width {
+            column.iter_mut().enumerate().for_each(|(y, c)| *c = coverage[y * width + x]);
+            for _ in 0..passes {
+                box_filter(&mut column, box_y, &mut scratch);
+            }
+            column.iter().enumerate().for_each(|(y, &c)| coverage[y * width + x] = c);
+        }
+    }
+}
+
+/// Box filter with a fractional `box_width`, extending the edge values beyond the ends.
+fn box_filter(values: &mut [f32], box_width: f64, prefix: &mut Vec<f64>) {
+    let n = values.len();
+    if n == 0 {
+        return;
+    }
+    prefix.clear();
+    prefix.push(0.0);
+    for &v in values.iter() {
+        prefix.push(prefix.last().unwrap() + v as f64);
+    }
+    // Integral of the piecewise constant signal from 0 to `t`.
+    let (first, last) = (values[0] as f64, values[n - 1] as f64);
+    let integral = |t: f64| {
+        if t <= 0.0 {
+            t * first
+        } else if t >= n as f64 {
+            prefix[n] + (t - n as f64) * last
+        } else {
+            let i = t as usize;
+            prefix[i] + (t - i as f64) * values[i] as f64
+        }
+    };
+    let half = box_width / 2.0;
+    let filtered: Vec<f32> = (0..n)
+        .map(|i| {
+            let center = i as f64 + 0.5;
+            ((integral(center + half) - integral(center - half)) / box_width) as f32
+        })
+        .collect();
+    values.copy_from_slice(&filtered);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rectangle(x0: f64, y0: f64, x1: f64, y1: f64) -> BezierPath {
+        BezierPath {
+            vertices: [[x0, y0], [x1, y0], [x1, y1], [x0, y1]]
+                .into_iter()
+                .map(|position| BezierVertex { position, ..Default::default() })
+                .collect(),
+            closed: true,
+            feathers: Vec::new(),
+        }
+    }
+
+    fn shape(path: BezierPath, mode: MaskMode) -> MaskShape {
+        MaskShape {
+            path,
+            mode,
+            inverted: false,
+            opacity: 1.0,
+            feather: [0.0, 0.0],
+            feather_falloff: MaskFeatherFalloff::Smooth,
+            expansion: 0.0,
+        }
+    }
+
+    #[test]
+    fn coverage_is_exact_area() {
+        let mut matte = MaskRasterizer::new(8, 8);
+        matte.add_mask(&shape(rectangle(1.5, 2.0, 5.25, 6.0), MaskMode::Add));
+        let alpha = matte.alpha();
+        let at = |x: usize, y: usize| alpha[y * 8 + x];
+        assert_eq!(at(0, 3), 0.0);
+        assert_eq!(at(1, 3), 0.5);
+        assert_eq!(at(3, 3), 1.0);
+        assert_eq!(at(5, 3), 0.25);
+        assert_eq!(at(3, 1), 0.0);
+        let total: f32 = alpha.iter().sum();
+        assert!((total - 3.75 * 4.0).abs() < 1e-4);
+
+        // Partially outside of the matte, in the other winding direction.
+        let mut matte = MaskRasterizer::new(4, 4);
+        let mut path = rectangle(-3.0, -1.0, 2.5, 9.0);
+        path.vertices.reverse();
+        matte.add_mask(&shape(path, MaskMode::Add));
+        assert_eq!(&matte.alpha()[..4], &[1.0, 1.0, 0.5, 0.0]);
+    }
+
+    #[test]
+    fn modes_and_invert() {
+        let left = rectangle(0.0, 0.0, 2.0, 1.0);
+        let right = rectangle(1.0, 0.0, 3.0, 1.0);
+        let render = |masks: &[MaskShape]| {
+            let mut matte = MaskRasterizer::new(3, 1);
+            masks.iter().for_each(|m| matte.add_mask(m));
+            matte.alpha().to_vec()
+        };
+        assert_eq!(render(&[]), [1.0, 1.0, 1.0]);
+        assert_eq!(render(&[shape(left.clone(), MaskMode::Add), shape(right.clone(), MaskMode::Add)]), [1.0, 1.0, 1.0]);
+        assert_eq!(render(&[shape(left.clone(), MaskMode::Add), shape(right.clone(), MaskMode::Subtract)]), [1.0, 0.0, 0.0]);
+        assert_eq!(render(&[shape(left.clone(), MaskMode::Add), shape(right.clone(), MaskMode::Intersect)]), [0.0, 1.0, 0.0]);
+        assert_eq!(render(&[shape(left.clone(), MaskMode::Add), shape(right.clone(), MaskMode::Difference)]), [1.0, 0.0, 1.0]);
+        // A leading subtract mask cuts out of the full layer.
+        assert_eq!(render(&[shape(left.clone(), MaskMode::Subtract)]), [0.0, 0.0, 1.0]);
+        assert_eq!(render(&[MaskShape { inverted: true, opacity: 0.5, ..shape(left, MaskMode::Add) }]), [0.0, 0.0, 0.5]);
+    }
+
+    #[test]
+    fn feather_is_symmetric_around_the_edge() {
+        for falloff in [MaskFeatherFalloff::Linear, MaskFeatherFalloff::Smooth] {
+            let mut matte = MaskRasterizer::new(40, 1);
+            matte.add_mask(&MaskShape { feather: [10.0, 0.0], feather_falloff: falloff, ..shape(rectangle(-10.0, -1.0, 20.0, 2.0), MaskMode::Add) });
+            let alpha = matte.alpha();
+            assert!(alpha[5] > 0.99 && alpha[35] < 0.01, "{alpha:?}");
+            assert!(alpha.windows(2).all(|w| w[0] >= w[1]));
+            assert!((alpha[19] + alpha[20] - 1.0).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn flatten_stays_on_the_curve() {
+        // A circle approximated by four cubics.
+        let k = 0.5522847498 * 10.0;
+        let path = BezierPath {
+            vertices: vec![
+                BezierVertex { position: [10.0, 0.0], tangent_in: [0.0, -k], tangent_out: [0.0, k] },
+                BezierVertex { position: [0.0, 10.0], tangent_in: [k, 0.0], tangent_out: [-k, 0.0] },
+                BezierVertex { position: [-10.0, 0.0], tangent_in: [0.0, k], tangent_out: [0.0, -k] },
+                BezierVertex { position: [0.0, -10.0], tangent_in: [-k, 0.0], tangent_out: [k, 0.0] },
+            ],
+            closed: true,
+            feathers: Vec::new(),
+        };
+        let points = path.flatten(0.01);
+        assert!(points.len() > 16);
+        assert!(points.iter().all(|p| (p[0].hypot(p[1]) - 10.0).abs() < 0.01));
+        assert_ne!(points.first(), points.last());
+    }
+}
@@ -0,0 +1,187 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::aegp::suites::{Command, RegisterNonAegp};
+use crate::aegp::{CommandHookStatus, HookPriority, MenuId, MenuOrder, PluginId, UndoGroup};
+use crate::{Error, ae_sys};
+
+type Handler = Box<dyn FnMut() -> Result<(), Error>>;
+type Predicate = Box<dyn Fn() -> bool>;
+
+/// A menu command declared with [`Menu::command()`].
+pub struct MenuCommand {
+    name: String,
+    menu: MenuId,
+    order: MenuOrder,
+    undo_name: Option<String>,
+    enabled: Option<Predicate>,
+    checked: Option<Predicate>,
+    handler: Handler,
+}
+
+impl MenuCommand {
+    /// Creates a command named `name`, inserted sorted into `menu`.
+    ///
+    /// The `handler` runs inside an undo group named after the command, see [`undo_name()`](Self::undo_name).
+    pub fn new(name: &str, menu: MenuId, handler: impl FnMut() -> Result<(), Error> + 'static) -> Self {
+        Self {
+            name: name.to_owned(),
+            menu,
+            order: MenuOrder::Sorted,
+            undo_name: Some(name.to_owned()),
+            enabled: None,
+            checked: None,
+            handler: Box::new(handler),
+        }
+    }
+
+    /// Position in the menu, [`MenuOrder::Sorted`] by default.
+    pub fn order(mut self, order: MenuOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Name of the undo group the handler runs in, shown in the Edit menu.
+    pub fn undo_name(mut self, undo_name: &str) -> Self {
+        self.undo_name = Some(undo_name.to_owned());
+        self
+    }
+
+    /// Runs the handler outside of an undo group, for commands which don't modify the project.
+    pub fn without_undo(mut self) -> Self {
+        self.undo_name = None;
+        self
+    }
+
+    /// The command is only enabled while `predicate` returns `true`. Evaluated every time a menu is drawn.
+    pub fn enabled_if(mut self, predicate: impl Fn() -> bool + 'static) -> Self {
+        self.enabled = Some(Box::new(predicate));
+        self
+    }
+
+    /// Draws a check mark next to the command while `predicate` returns `true`.
+    pub fn checked_if(mut self, predicate: impl Fn() -> bool + 'static) -> Self {
+        self.checked = Some(Box::new(predicate));
+        self
+    }
+}
+
+struct Entry {
+    command: ae_sys::AEGP_Command,
+    name: String,
+    undo_name: Option<String>,
+    enabled: Option<Predicate>,
+    checked: Option<Predicate>,
+    handler: RefCell<Handler>,
+}
+
+/// Declares menu commands and routes them to their handlers.
+///
+/// [`register()`](Self::register) allocates the command ids, inserts the commands into their menus,
+/// and registers a single command hook and update menu hook for all of them.
+///
+/// Example usage:
+/// ```ignore
+/// Menu::new(plugin_id)
+///     .command(MenuCommand::new("Grab Frame", MenuId::Export, move || grab_frame(plugin_id))
+///         .without_undo()
+///         .enabled_if(|| matches!(active_item_type(), Some(ItemType::Comp | ItemType::Footage))))
+///     .command(MenuCommand::new("Add Nulls", MenuId::Layer, move || add_nulls(plugin_id)))
+///     .register()?;
+/// ```
+pub struct Menu {
+    plugin_id: PluginId,
+    commands: Vec<MenuCommand>,
+}
+
+impl Menu {
+    pub fn new(plugin_id: PluginId) -> Self {
+        Self { plugin_id, commands: Vec::new() }
+    }
+
+    pub fn command(mut self, command: MenuCommand) -> Self {
+        self.commands.push(command);
+        self
+    }
+
+    /// Adds the commands to After Effects. Must be called on the main thread, usually in [`AegpPlugin::entry_point()`](crate::AegpPlugin::entry_point).
+    ///
+    /// Returns the allocated command ids, in the order the commands were declared.
+    /// The hooks stay registered for the lifetime of the plugin.
+    pub fn register(self) -> Result<Vec<ae_sys::AEGP_Command>, Error> {
+        if self.commands.is_empty() {
+            return Ok(Vec::new());
+        }
+        let command_suite = Command::new()?;
+        let entries = self
+            .commands
+            .into_iter()
+            .map(|command| {
+                let id = command_suite.unique_command()?;
+                command_suite.insert_command(&command.name, id, command.menu, command.order)?;
+                Ok(Entry {
+                    command: id,
+                    name: command.name,
+                    undo_name: command.undo_name,
+                    enabled: command.enabled,
+                    checked: command.checked,
+                    handler: RefCell::new(command.handler),
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let ids = entries.iter().map(|entry| entry.command).collect();
+        let entries: Rc<[Entry]> = entries.into();
+
+        let register = RegisterNonAegp::new()?;
+        register.register_command_hook(
+            self.plugin_id,
+            HookPriority::BeforeAE,
+            ae_sys::AEGP_Command_ALL as _,
+            Box::new(|entries: &mut Rc<[Entry]>, command, _, _| dispatch(entries, command)),
+            entries.clone(),
+        )?;
+        register.register_update_menu_hook(self.plugin_id, Box::new(|entries: &mut Rc<[Entry]>, _| update_menus(entries)), entries)?;
+
+        Ok(ids)
+    }
+}
+
+fn dispatch(entries: &[Entry], command: ae_sys::AEGP_Command) -> Result<CommandHookStatus, Error> {
+    let Some(entry) = entries.iter().find(|entry| entry.command == command) else {
+        return Ok(CommandHookStatus::Unhandled);
+    };
+    let Ok(mut handler) = entry.handler.try_borrow_mut() else {
+        // The command was triggered again from within its own handler.
+        return Ok(CommandHookStatus::Handled);
+    };
+
+    let undo_group = entry.undo_name.as_deref().map(UndoGroup::new).transpose()?;
+    let result = crate::catch_ffi_panic(&format!("menu command \"{}\"", entry.name), &mut *handler);
+    drop(undo_group);
+
+    match result {
+        Some(result) => result.map(|_| CommandHookStatus::Handled),
+        None => Err(Error::Generic),
+    }
+}
+
+fn update_menus(entries: &[Entry]) -> Result<(), Error> {
+    let command_suite = Command::new()?;
+    for entry in entries {
+        let evaluate = |predicate: &Option<Predicate>, kind: &str, default| {
+            predicate.as_ref().map_or(Some(default), |predicate| {
+                crate::catch_ffi_panic(&format!("{kind} predicate of menu command \"{}\"", entry.name), predicate)
+            })
+        };
+        // A panicking predicate disables the command.
+        if evaluate(&entry.enabled, "enable", true).unwrap_or(false) {
+            command_suite.enable_command(entry.command)?;
+        } else {
+            command_suite.disable_command(entry.command)?;
+        }
+        if entry.checked.is_some() {
+            command_suite.check_mark_menu_command(entry.command, evaluate(&entry.checked, "check", false).unwrap_or(false))?;
+        }
+    }
+    Ok(())
+}
@@ -3,6 +3,27 @@ use crate::ae_sys;
 use std::{ convert::TryFrom, ffi::CString, marker::PhantomData };
 use widestring::U16CString;
 
+mod async_render;
+pub use async_render::*;
+mod camera_model;
+pub use camera_model::*;
+mod main_thread;
+pub use main_thread::*;
+mod mask_path;
+pub use mask_path::*;
+mod menu;
+pub use menu::*;
+mod preferences;
+pub use preferences::*;
+mod project_tree;
+pub use project_tree::*;
+mod render_job;
+pub use render_job::*;
+mod script;
+pub use script::*;
+mod svg_path;
+pub use svg_path::*;
+
 #[cfg(feature = "artisan-2-api")]
 mod scene_3d;
 #[cfg(feature = "artisan-2-api")]
@@ -37,6 +58,7 @@ pub mod suites {
     pub(crate) mod output_module;        pub use output_module       ::OutputModuleSuite       as OutputModule;
     pub(crate) mod render_queue;         pub use render_queue        ::RenderQueueSuite        as RenderQueue;
     pub(crate) mod render_queue_item;    pub use render_queue_item   ::RenderQueueItemSuite    as RenderQueueItem;
+    pub(crate) mod render_queue_monitor; pub use render_queue_monitor::RenderQueueMonitorSuite as RenderQueueMonitor;
     pub(crate) mod sound_data;           pub use sound_data          ::SoundDataSuite          as SoundData;
     pub(crate) mod stream;               pub use stream              ::{ StreamSuite           as Stream,
                                                                          DynamicStreamSuite    as DynamicStream };
@@ -83,7 +105,10 @@ pub use suites::comp::{
     CompHandle,
 };
 pub use suites::compute_cache:: {
-    ComputeClassId
+    ComputeCache,
+    ComputeCacheBuilder,
+    ComputeCacheEntry,
+    ComputeClassId,
 };
 pub use suites::effect::{
     Effect,
@@ -148,6 +173,7 @@ pub use suites::persistent_data::{
    PersistentType,
    PersistentBlobHandle
 };
+pub use suites::render::FrameReceipt;
 pub use suites::render_async_manager::AsyncManager;
 pub use suites::render_options::{
     RenderOptions,
@@ -169,6 +195,16 @@ pub use suites::render_queue_item::{
     RenderItemStatus,
     RQItemRefHandle,
 };
+pub use suites::render_queue_monitor::{
+    FrameFinished,
+    FrameThumbnail,
+    RenderFinishedStatus,
+    RenderJobFrameId,
+    RenderJobItemId,
+    RenderQueueListener,
+    RenderQueueMonitor,
+    RenderSessionId,
+};
 pub use suites::sound_data::SoundDataHandle;
 pub use suites::stream::{
     Stream,
@@ -182,7 +218,12 @@ pub use suites::stream::{
     StreamValue,
     TextDocumentHandle,
 };
-pub use suites::utility::GetPathTypes;
+pub use suites::utility::{
+    GetPathTypes,
+    QuietErrors,
+    UndoGroup,
+    with_undo,
+};
 pub use suites::world::{
     PlatformWorldHandle,
     World,
@@ -0,0 +1,681 @@
+use std::collections::BTreeMap;
+use std::ops::{Deref, DerefMut};
+
+use serde::{Serialize, Serializer, de::DeserializeOwned, ser};
+use serde_json::{Map, Value};
+
+use crate::Error;
+use crate::aegp::{PersistentBlobHandle, PersistentType, suites::PersistentData};
+
+/// Separator between a section key and the field name of a nested struct.
+const SUBSECTION_SEPARATOR: char = '/';
+/// Suffix of the value key holding the byte size of a blob entry.
+const BLOB_SIZE_SUFFIX: &str = ".blob_size";
+/// Strings longer than this are stored as blobs, as `AEGP_GetString` needs a fixed size buffer.
+const MAX_STRING_LEN: usize = 1023;
+/// Key of the single entry used for preferences which aren't a struct.
+const VALUE_KEY: &str = "value";
+
+/// A single value as it is stored in the persistent data blob.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PreferenceValue {
+    Long(i32),
+    FpLong(f64),
+    String(String),
+    /// JSON encoded value of a type that has no native representation in the persistent data blob.
+    Blob(Vec<u8>),
+}
+
+/// A single entry that differs between the stored preferences and the in-memory value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreferenceChange {
+    pub section: String,
+    pub key: String,
+    /// Value last loaded from, or saved to the persistent data blob. `None` if the key is new.
+    pub old: Option<PreferenceValue>,
+    /// Current value. `None` if the key will be removed.
+    pub new: Option<PreferenceValue>,
+}
+
+/// Storage kind of an entry, decided from the value in `T::default()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EntryKind {
+    Bool,
+    Long,
+    FpLong,
+    String,
+    Blob,
+}
+
+type EntryMap = BTreeMap<(String, String), (EntryKind, PreferenceValue)>;
+
+/// Typed preferences stored in the application's persistent data.
+///
+/// Every field of `T` is stored as a separate key in `section`, so the preferences stay
+/// readable and editable in the preferences file:
+/// - `bool` and integers which fit into an `i32` are stored as longs,
+/// - other numbers are stored as floating point values (with 6 decimal places of precision),
+/// - strings are stored as strings,
+/// - nested structs are stored in subsections named `section/field`,
+/// - everything else (maps, sequences, options, large integers, ...) is stored as a JSON blob.
+///
+/// The set of keys and their types is taken from `T::default()`, which is also used for every key
+/// that doesn't exist yet. If `T` isn't a struct (e.g. a map), or uses `#[serde(flatten)]`, it is
+/// stored as a single blob named `value`.
+///
+/// This works both from AEGPs and from effects, it doesn't require a [`PluginId`](crate::aegp::PluginId).
+///
+/// Example usage:
+/// ```ignore
+/// #[derive(Default, Serialize, Deserialize)]
+/// struct Settings {
+///     fuzziness: f64,
+///     description: String,
+///     export: ExportSettings,
+/// }
+///
+/// let mut prefs = Preferences::<Settings>::load("My Plugin", PersistentType::MachineIndependent)?;
+/// prefs.fuzziness = 42.0;
+/// prefs.save()?;
+/// ```
+pub struct Preferences<T> {
+    section: String,
+    persistent_type: PersistentType,
+    value: T,
+    shape: Shape,
+    stored: EntryMap,
+}
+
+impl<T: Serialize + DeserializeOwned + Default> Preferences<T> {
+    /// Loads the preferences from `section` of the given application blob.
+    /// Keys which don't exist, or whose stored value can't be read as the current type of the field,
+    /// take their value from `T::default()`.
+    pub fn load(section: &str, persistent_type: PersistentType) -> Result<Self, Error> {
+        let mut prefs = Self {
+            section: section.to_owned(),
+            persistent_type,
+            value: T::default(),
+            shape: Shape::of(&T::default())?,
+            stored: EntryMap::new(),
+        };
+        prefs.reload()?;
+        Ok(prefs)
+    }
+
+    /// Discards all in-memory changes and reads the values from the persistent data blob again.
+    pub fn reload(&mut self) -> Result<(), Error> {
+        let suite = PersistentData::new()?;
+        let blob = suite.application_blob(self.persistent_type)?;
+
+        let default = flatten(&self.section, &self.shape, &to_value(&T::default())?);
+        let stored = load_entries(default.clone(), |section, key, kind| {
+            if suite.does_key_exist(blob, section, key)? {
+                read_entry(&suite, blob, section, key, kind).map(Some)
+            } else {
+                Ok(None)
+            }
+        });
+
+        self.value = restore(&self.section, &self.shape, &default, &stored);
+        self.stored = stored;
+        Ok(())
+    }
+
+    /// Writes all changed entries to the persistent data blob.
+    pub fn save(&mut self) -> Result<(), Error> {
+        let current = flatten(&self.section, &self.shape, &to_value(&self.value)?);
+        let changes = diff(&self.stored, &current);
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        let suite = PersistentData::new()?;
+        let blob = suite.application_blob(self.persistent_type)?;
+        for change in &changes {
+            match &change.new {
+                Some(value) => write_entry(&suite, blob, &change.section, &change.key, value)?,
+                None => delete_entry(&suite, blob, &change.section, &change.key)?,
+            }
+        }
+        self.stored = current;
+        Ok(())
+    }
+
+    /// Returns the entries which differ from the last loaded or saved state.
+    pub fn diff(&self) -> Result<Vec<PreferenceChange>, Error> {
+        Ok(diff(&self.stored, &flatten(&self.section, &self.shape, &to_value(&self.value)?)))
+    }
+
+    /// Returns `true` if [`Self::save`] would write anything.
+    pub fn is_modified(&self) -> bool {
+        self.diff().map(|changes| !changes.is_empty()).unwrap_or(true)
+    }
+
+    /// Resets the in-memory value to `T::default()`. Call [`Self::save`] to store it.
+    pub fn reset_to_default(&mut self) {
+        self.value = T::default();
+    }
+
+    /// Removes all entries of these preferences from the persistent data blob.
+    pub fn delete(self) -> Result<(), Error> {
+        let suite = PersistentData::new()?;
+        let blob = suite.application_blob(self.persistent_type)?;
+        // Keys which couldn't be read aren't in `stored`.
+        let default = flatten(&self.section, &self.shape, &to_value(&T::default())?);
+        for (section, key) in self.stored.keys().chain(default.keys()) {
+            delete_entry(&suite, blob, section, key)?;
+        }
+        Ok(())
+    }
+
+    pub fn section(&self) -> &str {
+        &self.section
+    }
+
+    pub fn persistent_type(&self) -> PersistentType {
+        self.persistent_type
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for Preferences<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+impl<T> DerefMut for Preferences<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+fn to_value<T: Serialize>(value: &T) -> Result<Value, Error> {
+    serde_json::to_value(value).map_err(|_| Error::InternalStructDamaged)
+}
+
+fn from_value<T: DeserializeOwned>(value: Value) -> Result<T, Error> {
+    serde_json::from_value(value).map_err(|_| Error::InternalStructDamaged)
+}
+
+fn read_entry(suite: &PersistentData, blob: PersistentBlobHandle, section: &str, key: &str, kind: EntryKind) -> Result<PreferenceValue, Error> {
+    let size_key = format!("{key}{BLOB_SIZE_SUFFIX}");
+    // Values may have been stored as a blob even if the default fits a native type, e.g. a long string.
+    if kind == EntryKind::Blob || suite.does_key_exist(blob, section, &size_key)? {
+        let size = suite.long(blob, section, &size_key, 0)?.max(0) as usize;
+        return Ok(PreferenceValue::Blob(suite.data_bytes(blob, section, key, size)?));
+    }
+    Ok(match kind {
+        EntryKind::Bool | EntryKind::Long => PreferenceValue::Long(suite.long(blob, section, key, 0)?),
+        EntryKind::FpLong                 => PreferenceValue::FpLong(suite.fp_long(blob, section, key, 0.0)?),
+        EntryKind::String                 => PreferenceValue::String(suite.string(blob, section, key, "", MAX_STRING_LEN)?),
+        EntryKind::Blob                   => unreachable!(),
+    })
+}
+
+fn write_entry(suite: &PersistentData, blob: PersistentBlobHandle, section: &str, key: &str, value: &PreferenceValue) -> Result<(), Error> {
+    let size_key = format!("{key}{BLOB_SIZE_SUFFIX}");
+    if let PreferenceValue::Blob(data) = value {
+        suite.set_data(blob, section, key, data.as_ptr() as _, data.len() as _)?;
+        return suite.set_long(blob, section, &size_key, data.len() as _);
+    }
+    if suite.does_key_exist(blob, section, &size_key)? {
+        suite.delete_entry(blob, section, &size_key)?;
+    }
+    match value {
+        PreferenceValue::Long(v)   => suite.set_long(blob, section, key, *v),
+        PreferenceValue::FpLong(v) => suite.set_fp_long(blob, section, key, *v),
+        PreferenceValue::String(v) => suite.set_string(blob, section, key, v),
+        PreferenceValue::Blob(_)   => unreachable!(),
+    }
+}
+
+fn delete_entry(suite: &PersistentData, blob: PersistentBlobHandle, section: &str, key: &str) -> Result<(), Error> {
+    let size_key = format!("{key}{BLOB_SIZE_SUFFIX}");
+    if suite.does_key_exist(blob, section, &size_key)? {
+        suite.delete_entry(blob, section, &size_key)?;
+    }
+    if suite.does_key_exist(blob, section, key)? {
+        suite.delete_entry(blob, section, key)?;
+    }
+    Ok(())
+}
+
+/// Reads the stored value of every entry in `default`, keeping the default if `read` returns `None`.
+///
+/// Entries which fail to read are left out, so they are written again on the next save.
+fn load_entries(default: EntryMap, mut read: impl FnMut(&str, &str, EntryKind) -> Result<Option<PreferenceValue>, Error>) -> EntryMap {
+    let mut stored = EntryMap::new();
+    for ((section, key), (kind, default_value)) in default {
+        match read(&section, &key, kind) {
+            Ok(value) => { stored.insert((section, key), (kind, value.unwrap_or(default_value))); }
+            Err(e) => log::warn!("Failed to read preference {section}/{key}, using the default: {e:?}"),
+        }
+    }
+    stored
+}
+
+/// Deserializes the stored entries, falling back to the default of every key that doesn't decode
+/// or doesn't match the type of its field anymore, e.g. because a field changed its type between versions.
+fn restore<T: DeserializeOwned + Default>(section: &str, shape: &Shape, default: &EntryMap, stored: &EntryMap) -> T {
+    let deserialize = |entries: &EntryMap| unflatten(section, shape, entries).and_then(from_value::<T>);
+
+    let mut entries = default.clone();
+    entries.extend(stored.iter().map(|(key, entry)| (key.clone(), entry.clone())));
+    if let Ok(value) = deserialize(&entries) {
+        return value;
+    }
+
+    // Add the stored keys one by one, so a single damaged key doesn't discard all others.
+    let mut accepted = default.clone();
+    for (key, entry) in stored {
+        if default.get(key) == Some(entry) {
+            continue;
+        }
+        let mut candidate = accepted.clone();
+        candidate.insert(key.clone(), entry.clone());
+        if deserialize(&candidate).is_ok() {
+            accepted = candidate;
+        } else {
+            log::warn!("Stored preference {}/{} doesn't match its type, using the default", key.0, key.1);
+        }
+    }
+    deserialize(&accepted).unwrap_or_default()
+}
+
+/// Which parts of `T` are structs, and are split into subsections.
+///
+/// Taken from the serializer calls rather than from the JSON value, which looks the same for structs and maps.
+#[derive(Debug, Clone, PartialEq)]
+enum Shape {
+    /// Stored as a single entry.
+    Leaf,
+    Struct(BTreeMap<String, Shape>),
+}
+
+impl Shape {
+    fn of<T: Serialize>(value: &T) -> Result<Self, Error> {
+        value.serialize(ShapeProbe).map_err(|_| Error::InternalStructDamaged)
+    }
+}
+
+struct ShapeProbe;
+
+macro_rules! leaf_shape {
+    ($($method:ident($ty:ty)),*) => {
+        $(fn $method(self, _: $ty) -> Result<Shape, Self::Error> { Ok(Shape::Leaf) })*
+    };
+}
+
+impl Serializer for ShapeProbe {
+    type Ok = Shape;
+    type Error = serde_json::Error;
+    type SerializeSeq = LeafShape;
+    type SerializeTuple = LeafShape;
+    type SerializeTupleStruct = LeafShape;
+    type SerializeTupleVariant = LeafShape;
+    type SerializeMap = LeafShape;
+    type SerializeStruct = StructShape;
+    type SerializeStructVariant = LeafShape;
+
+    leaf_shape!(
+        serialize_bool(bool), serialize_char(char), serialize_str(&str), serialize_bytes(&[u8]),
+        serialize_i8(i8), serialize_i16(i16), serialize_i32(i32), serialize_i64(i64), serialize_i128(i128),
+        serialize_u8(u8), serialize_u16(u16), serialize_u32(u32), serialize_u64(u64), serialize_u128(u128),
+        serialize_f32(f32), serialize_f64(f64), serialize_unit_struct(&'static str)
+    );
+
+    fn serialize_none(self) -> Result<Shape, Self::Error> { Ok(Shape::Leaf) }
+    fn serialize_unit(self) -> Result<Shape, Self::Error> { Ok(Shape::Leaf) }
+    // `None` and `Some` must be stored the same way.
+    fn serialize_some<T: ?Sized + Serialize>(self, _: &T) -> Result<Shape, Self::Error> { Ok(Shape::Leaf) }
+    fn serialize_unit_variant(self, _: &'static str, _: u32, _: &'static str) -> Result<Shape, Self::Error> { Ok(Shape::Leaf) }
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _: &'static str, value: &T) -> Result<Shape, Self::Error> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(self, _: &'static str, _: u32, _: &'static str, _: &T) -> Result<Shape, Self::Error> {
+        Ok(Shape::Leaf)
+    }
+    fn serialize_seq(self, _: Option<usize>) -> Result<LeafShape, Self::Error> { Ok(LeafShape) }
+    fn serialize_tuple(self, _: usize) -> Result<LeafShape, Self::Error> { Ok(LeafShape) }
+    fn serialize_tuple_struct(self, _: &'static str, _: usize) -> Result<LeafShape, Self::Error> { Ok(LeafShape) }
+    fn serialize_tuple_variant(self, _: &'static str, _: u32, _: &'static str, _: usize) -> Result<LeafShape, Self::Error> { Ok(LeafShape) }
+    fn serialize_map(self, _: Option<usize>) -> Result<LeafShape, Self::Error> { Ok(LeafShape) }
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<StructShape, Self::Error> { Ok(StructShape(BTreeMap::new())) }
+    fn serialize_struct_variant(self, _: &'static str, _: u32, _: &'static str, _: usize) -> Result<LeafShape, Self::Error> { Ok(LeafShape) }
+}
+
+/// Ignores the elements of anything that is stored as a single entry.
+struct LeafShape;
+
+macro_rules! ignore_elements {
+    ($($trait:ident::$method:ident($($arg:ident: $ty:ty),*)),*) => {
+        $(impl ser::$trait for LeafShape {
+            type Ok = Shape;
+            type Error = serde_json::Error;
+            fn $method<T: ?Sized + Serialize>(&mut self, $(_: $ty,)* _: &T) -> Result<(), Self::Error> { Ok(()) }
+            fn end(self) -> Result<Shape, Self::Error> { Ok(Shape::Leaf) }
+        })*
+    };
+}
+ignore_elements!(
+    SerializeSeq::serialize_element(),
+    SerializeTuple::serialize_element(),
+    SerializeTupleStruct::serialize_field(),
+    SerializeTupleVariant::serialize_field(),
+    SerializeStructVariant::serialize_field(key: &'static str)
+);
+
+impl ser::SerializeMap for LeafShape {
+    type Ok = Shape;
+    type Error = serde_json::Error;
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, _: &T) -> Result<(), Self::Error> { Ok(()) }
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, _: &T) -> Result<(), Self::Error> { Ok(()) }
+    fn end(self) -> Result<Shape, Self::Error> { Ok(Shape::Leaf) }
+}
+
+struct StructShape(BTreeMap<String, Shape>);
+
+impl ser::SerializeStruct for StructShape {
+    type Ok = Shape;
+    type Error = serde_json::Error;
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error> {
+        self.0.insert(key.to_owned(), value.serialize(ShapeProbe)?);
+        Ok(())
+    }
+    fn skip_field(&mut self, key: &'static str) -> Result<(), Self::Error> {
+        self.0.insert(key.to_owned(), Shape::Leaf);
+        Ok(())
+    }
+    fn end(self) -> Result<Shape, Self::Error> { Ok(Shape::Struct(self.0)) }
+}
+
+fn flatten(section: &str, shape: &Shape, value: &Value) -> EntryMap {
+    let mut entries = EntryMap::new();
+    match (shape, value) {
+        (Shape::Struct(fields), Value::Object(values)) => flatten_into(section, fields, values, &mut entries),
+        _ => { entries.insert((section.to_owned(), VALUE_KEY.to_owned()), encode(value)); }
+    }
+    entries
+}
+
+fn flatten_into(section: &str, fields: &BTreeMap<String, Shape>, values: &Map<String, Value>, entries: &mut EntryMap) {
+    for (key, value) in values {
+        match (fields.get(key), value) {
+            (Some(Shape::Struct(nested_fields)), Value::Object(nested)) => {
+                flatten_into(&format!("{section}{SUBSECTION_SEPARATOR}{key}"), nested_fields, nested, entries);
+            }
+            _ => { entries.insert((section.to_owned(), key.clone()), encode(value)); }
+        }
+    }
+}
+
+fn encode(value: &Value) -> (EntryKind, PreferenceValue) {
+    match value {
+        Value::Bool(b) => (EntryKind::Bool, PreferenceValue::Long(*b as i32)),
+        Value::Number(n) if n.as_i64().is_some_and(|i| i32::try_from(i).is_ok()) => {
+            (EntryKind::Long, PreferenceValue::Long(n.as_i64().unwrap() as i32))
+        }
+        Value::Number(n) if n.is_f64() => (EntryKind::FpLong, PreferenceValue::FpLong(n.as_f64().unwrap())),
+        Value::String(s) if s.len() <= MAX_STRING_LEN && !s.contains('\0') => (EntryKind::String, PreferenceValue::String(s.clone())),
+        other => (EntryKind::Blob, PreferenceValue::Blob(serde_json::to_vec(other).unwrap_or_default())),
+    }
+}
+
+fn decode(kind: EntryKind, value: &PreferenceValue) -> Result<Value, Error> {
+    Ok(match (kind, value) {
+        (EntryKind::Bool, PreferenceValue::Long(v)) => Value::Bool(*v != 0),
+        (_, PreferenceValue::Long(v))               => Value::from(*v),
+        (_, PreferenceValue::FpLong(v))             => Value::from(*v),
+        (_, PreferenceValue::String(v))             => Value::String(v.clone()),
+        (_, PreferenceValue::Blob(v))               => serde_json::from_slice(v).map_err(|_| Error::InternalStructDamaged)?,
+    })
+}
+
+fn unflatten(section: &str, shape: &Shape, entries: &EntryMap) -> Result<Value, Error> {
+    let Shape::Struct(fields) = shape else {
+        return entries
+            .get(&(section.to_owned(), VALUE_KEY.to_owned()))
+            .map(|(kind, value)| decode(*kind, value))
+            .unwrap_or(Ok(Value::Null));
+    };
+    // Start with every subsection, so structs without any stored keys still deserialize.
+    let mut root = skeleton(fields);
+    for ((entry_section, key), (kind, value)) in entries {
+        let mut object = &mut root;
+        let path = if entry_section == section {
+            None
+        } else {
+            let path = entry_section.strip_prefix(section).and_then(|s| s.strip_prefix(SUBSECTION_SEPARATOR));
+            Some(path.ok_or(Error::InternalStructDamaged)?)
+        };
+        for field in path.into_iter().flat_map(|path| path.split(SUBSECTION_SEPARATOR)) {
+            object = match object.get_mut(field) {
+                Some(Value::Object(nested)) => nested,
+                _ => return Err(Error::InternalStructDamaged),
+            };
+        }
+        object.insert(key.clone(), decode(*kind, value)?);
+    }
+    Ok(Value::Object(root))
+}
+
+fn skeleton(fields: &BTreeMap<String, Shape>) -> Map<String, Value> {
+    fields
+        .iter()
+        .filter_map(|(key, shape)| match shape {
+            Shape::Struct(nested) => Some((key.clone(), Value::Object(skeleton(nested)))),
+            Shape::Leaf => None,
+        })
+        .collect()
+}
+
+fn diff(stored: &EntryMap, current: &EntryMap) -> Vec<PreferenceChange> {
+    let mut changes = Vec::new();
+    for ((section, key), (_, value)) in current {
+        let old = stored.get(&(section.clone(), key.clone())).map(|(_, v)| v);
+        if old != Some(value) {
+            changes.push(PreferenceChange {
+                section: section.clone(),
+                key: key.clone(),
+                old: old.cloned(),
+                new: Some(value.clone()),
+            });
+        }
+    }
+    for ((section, key), (_, value)) in stored {
+        if !current.contains_key(&(section.clone(), key.clone())) {
+            changes.push(PreferenceChange {
+                section: section.clone(),
+                key: key.clone(),
+                old: Some(value.clone()),
+                new: None,
+            });
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::collections::HashMap;
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    struct Export {
+        enabled: bool,
+        quality: f32,
+    }
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    struct Settings {
+        count: i32,
+        name: String,
+        tags: Vec<String>,
+        limit: Option<u64>,
+        export: Export,
+    }
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    struct Empty {}
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    struct Presets {
+        names: HashMap<String, Export>,
+        empty: Empty,
+        export: Export,
+    }
+
+    fn flatten_value<T: Serialize + Default>(section: &str, value: &T) -> EntryMap {
+        flatten(section, &Shape::of(&T::default()).unwrap(), &to_value(value).unwrap())
+    }
+
+    fn unflatten_value<T: Serialize + DeserializeOwned + Default>(section: &str, entries: &EntryMap) -> T {
+        from_value(unflatten(section, &Shape::of(&T::default()).unwrap(), entries).unwrap()).unwrap()
+    }
+
+    /// Writes the changes like [`Preferences::save`] and reads them back like [`Preferences::reload`].
+    fn save_and_load<T: Serialize + DeserializeOwned + Default>(store: &mut EntryMap, value: &T) -> T {
+        let stored = load_entries(flatten_value("Prefs", &T::default()), |section, key, _| {
+            Ok(store.get(&(section.to_owned(), key.to_owned())).map(|(_, v)| v.clone()))
+        });
+        for change in diff(&stored, &flatten_value("Prefs", value)) {
+            let key = (change.section, change.key);
+            match change.new {
+                Some(new) => { store.insert(key, (EntryKind::Blob, new)); }
+                None => { store.remove(&key); }
+            }
+        }
+        let loaded = load_entries(flatten_value("Prefs", &T::default()), |section, key, _| {
+            Ok(store.get(&(section.to_owned(), key.to_owned())).map(|(_, v)| v.clone()))
+        });
+        unflatten_value("Prefs", &loaded)
+    }
+
+    #[test]
+    fn nested_structs_become_subsections() {
+        let settings = Settings { count: 3, ..Default::default() };
+        let entries = flatten_value("Prefs", &settings);
+
+        assert_eq!(entries[&("Prefs".into(), "count".into())], (EntryKind::Long, PreferenceValue::Long(3)));
+        assert_eq!(entries[&("Prefs/export".into(), "enabled".into())], (EntryKind::Bool, PreferenceValue::Long(0)));
+        assert_eq!(entries[&("Prefs".into(), "tags".into())].0, EntryKind::Blob);
+        assert_eq!(entries[&("Prefs".into(), "limit".into())].0, EntryKind::Blob);
+    }
+
+    #[test]
+    fn flatten_round_trips() {
+        let settings = Settings {
+            count: -7,
+            name: "utf-8 牛奶".into(),
+            tags: vec!["a".into(), "b".into()],
+            limit: Some(u64::MAX),
+            export: Export { enabled: true, quality: 0.5 },
+        };
+        let entries = flatten_value("Prefs", &settings);
+        let restored: Settings = unflatten_value("Prefs", &entries);
+
+        assert_eq!(restored, settings);
+    }
+
+    #[test]
+    fn diff_reports_changed_keys_only() {
+        let stored = flatten_value("Prefs", &Settings::default());
+        let mut settings = Settings::default();
+        settings.export.quality = 1.0;
+        let current = flatten_value("Prefs", &settings);
+
+        let changes = diff(&stored, &current);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].section, "Prefs/export");
+        assert_eq!(changes[0].key, "quality");
+        assert_eq!(changes[0].old, Some(PreferenceValue::FpLong(0.0)));
+        assert_eq!(changes[0].new, Some(PreferenceValue::FpLong(1.0)));
+    }
+
+    #[test]
+    fn maps_are_stored_as_a_single_entry() {
+        let mut presets = Presets::default();
+        presets.names.insert("draft".into(), Export { enabled: true, quality: 0.25 });
+        let entries = flatten_value("Prefs", &presets);
+
+        // The key exists in the default too, so it is read back on load.
+        assert!(flatten_value("Prefs", &Presets::default()).contains_key(&("Prefs".into(), "names".into())));
+        assert_eq!(entries[&("Prefs".into(), "names".into())].0, EntryKind::Blob);
+        assert_eq!(unflatten_value::<Presets>("Prefs", &entries), presets);
+
+        let map = BTreeMap::from([("a".to_owned(), 1), ("b".to_owned(), 2)]);
+        let entries = flatten_value("Prefs", &map);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[&("Prefs".into(), VALUE_KEY.into())].0, EntryKind::Blob);
+        assert_eq!(unflatten_value::<BTreeMap<String, i32>>("Prefs", &entries), map);
+    }
+
+    #[test]
+    fn empty_structs_deserialize_without_stored_keys() {
+        let entries = flatten_value("Prefs", &Presets::default());
+        assert!(!entries.keys().any(|(section, _)| section == "Prefs/empty"));
+        assert_eq!(unflatten_value::<Presets>("Prefs", &entries), Presets::default());
+    }
+
+    #[test]
+    fn conflicting_entries_are_an_error() {
+        let shape = Shape::of(&Settings::default()).unwrap();
+        let mut entries = flatten_value("Prefs", &Settings::default());
+        entries.insert(("Prefs/count".into(), "value".into()), (EntryKind::Long, PreferenceValue::Long(1)));
+        assert!(unflatten("Prefs", &shape, &entries).is_err());
+    }
+
+    #[test]
+    fn save_load_round_trips() {
+        let mut store = EntryMap::new();
+        let mut presets = Presets::default();
+        presets.names.insert("final".into(), Export { enabled: true, quality: 1.0 });
+        presets.export.quality = 0.5;
+        assert_eq!(save_and_load(&mut store, &presets), presets);
+
+        presets.names.clear();
+        assert_eq!(save_and_load(&mut store, &presets), presets);
+
+        let settings = Settings { count: 2, name: "x".repeat(2000), tags: vec!["a".into()], limit: Some(1), export: Export::default() };
+        assert_eq!(save_and_load(&mut EntryMap::new(), &settings), settings);
+    }
+
+    #[test]
+    fn damaged_keys_fall_back_to_their_default() {
+        let default = flatten_value("Prefs", &Settings::default());
+        let mut store = flatten_value("Prefs", &Settings { count: 5, name: "kept".into(), ..Default::default() });
+        store.get_mut(&("Prefs/export".into(), "quality".into())).unwrap().1 = PreferenceValue::FpLong(0.5);
+        // A blob that isn't JSON anymore.
+        store.get_mut(&("Prefs".into(), "tags".into())).unwrap().1 = PreferenceValue::Blob(b"[\"a\", ".to_vec());
+        // `limit` used to be a string in an older version.
+        store.get_mut(&("Prefs".into(), "limit".into())).unwrap().1 = PreferenceValue::Blob(br#""unlimited""#.to_vec());
+
+        let stored = load_entries(default.clone(), |section, key, _| {
+            Ok(store.get(&(section.to_owned(), key.to_owned())).map(|(_, v)| v.clone()))
+        });
+        let settings: Settings = restore("Prefs", &Shape::of(&Settings::default()).unwrap(), &default, &stored);
+        assert_eq!(settings, Settings { count: 5, name: "kept".into(), export: Export { enabled: false, quality: 0.5 }, ..Default::default() });
+
+        // Saving writes the defaults over the damaged keys.
+        let changes = diff(&stored, &flatten_value("Prefs", &settings));
+        let mut keys: Vec<_> = changes.iter().map(|change| change.key.as_str()).collect();
+        keys.sort();
+        assert_eq!(keys, ["limit", "tags"]);
+    }
+
+    #[test]
+    fn unreadable_keys_are_written_on_save() {
+        let default = flatten_value("Prefs", &Settings::default());
+        let stored = load_entries(default.clone(), |_, key, _| if key == "name" { Err(Error::Generic) } else { Ok(None) });
+        assert!(!stored.contains_key(&("Prefs".into(), "name".into())));
+
+        let settings: Settings = restore("Prefs", &Shape::of(&Settings::default()).unwrap(), &default, &stored);
+        assert_eq!(settings, Settings::default());
+        assert_eq!(diff(&stored, &default).len(), 1);
+    }
+}
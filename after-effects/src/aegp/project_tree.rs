@@ -0,0 +1,538 @@
+use std::collections::HashMap;
+use std::iter::Peekable;
+
+use serde::{Deserialize, Serialize};
+
+use crate::aegp::suites::{Effect as EffectSuite, Item as ItemSuite, Project as ProjectSuite};
+use crate::aegp::{
+    Effect, EffectFlags, FootageSignature, Item, ItemFlags, ItemHandle, ItemId, ItemType, Layer, LayerHandle, LayerId,
+    PluginId, ProjectHandle, StreamValue, TimeMode,
+};
+use crate::{Error, Time};
+
+/// An After Effects project.
+#[derive(Debug, Clone, Copy)]
+pub struct Project {
+    handle: ProjectHandle,
+}
+
+impl Project {
+    pub fn from_handle(handle: ProjectHandle) -> Self {
+        Self { handle }
+    }
+
+    /// Currently, there is only ever one open project.
+    pub fn current() -> Result<Self, Error> {
+        Self::by_index(0)
+    }
+
+    pub fn by_index(index: i32) -> Result<Self, Error> {
+        Ok(Self::from_handle(ProjectSuite::new()?.project_by_index(index)?))
+    }
+
+    pub fn handle(&self) -> ProjectHandle {
+        self.handle
+    }
+
+    pub fn name(&self) -> Result<String, Error> {
+        ProjectSuite::new()?.project_name(self.handle)
+    }
+
+    /// Returns an empty string if the project was never saved.
+    pub fn path(&self) -> Result<String, Error> {
+        ProjectSuite::new()?.project_path(self.handle)
+    }
+
+    pub fn root_folder(&self) -> Result<Item, Error> {
+        Ok(ProjectSuite::new()?.project_root_folder(self.handle)?.into())
+    }
+
+    /// Walks the project depth-first: folders and their items, the layers of each composition and the effects of each layer.
+    ///
+    /// Items are visited in project order, each followed by its children at `depth + 1`.
+    ///
+    /// Example usage:
+    /// ```ignore
+    /// for entry in aegp::Project::current()?.walk(plugin_id)? {
+    ///     if let ProjectEntry::Effect(effect) = entry?.entry {
+    ///         used_plugins.insert(aegp::Effect::match_name_of(effect.installed_key()?)?);
+    ///     }
+    /// }
+    /// ```
+    pub fn walk(&self, plugin_id: PluginId) -> Result<ProjectWalk, Error> {
+        let suite = ItemSuite::new()?;
+        let root_id = suite.item_id(ProjectSuite::new()?.project_root_folder(self.handle)?)?;
+
+        let mut children = HashMap::<ItemId, Vec<ItemHandle>>::new();
+        let mut item = Some(suite.first_proj_item(&self.handle)?).filter(|item| !item.is_null());
+        while let Some(handle) = item {
+            let parent = suite.item_parent_folder(handle)?.map(|parent| suite.item_id(parent)).transpose()?;
+            children.entry(parent.unwrap_or(root_id)).or_default().push(handle);
+            item = suite.next_proj_item(&self.handle, handle)?;
+        }
+
+        let mut walk = ProjectWalk { plugin_id, children, stack: Vec::new() };
+        walk.push_items(root_id, 0);
+        Ok(walk)
+    }
+
+    /// Captures the whole project, down to the effect parameter values at the start of each layer.
+    pub fn snapshot(&self, plugin_id: PluginId) -> Result<ProjectSnapshot, Error> {
+        let entries = self
+            .walk(plugin_id)?
+            .map(|entry| entry.and_then(|entry| Ok((entry.depth, SnapshotEntry::capture(entry.entry, plugin_id)?))))
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(ProjectSnapshot {
+            name: self.name()?,
+            path: self.path()?,
+            items: build_items(&mut entries.into_iter().peekable(), 0),
+        })
+    }
+}
+
+/// An object in the project, yielded by [`Project::walk()`].
+pub enum ProjectEntry {
+    Folder(Item),
+    /// Use [`Item::composition()`] to access the composition.
+    Comp(Item),
+    /// Footage item, including solids.
+    Footage(Item),
+    Layer(Layer),
+    Effect(Effect),
+}
+
+pub struct WalkEntry {
+    /// 0 for items in the root folder.
+    pub depth: usize,
+    pub entry: ProjectEntry,
+}
+
+enum Pending {
+    Item(ItemHandle),
+    Layer(LayerHandle),
+    Effect { layer: LayerHandle, index: i32 },
+}
+
+/// Depth-first iterator over a project, created by [`Project::walk()`].
+pub struct ProjectWalk {
+    plugin_id: PluginId,
+    children: HashMap<ItemId, Vec<ItemHandle>>,
+    stack: Vec<(Pending, usize)>,
+}
+
+impl ProjectWalk {
+    fn push_items(&mut self, folder: ItemId, depth: usize) {
+        if let Some(items) = self.children.remove(&folder) {
+            self.stack.extend(items.into_iter().rev().map(|item| (Pending::Item(item), depth)));
+        }
+    }
+
+    fn advance(&mut self) -> Result<Option<WalkEntry>, Error> {
+        while let Some((pending, depth)) = self.stack.pop() {
+            let entry = match pending {
+                Pending::Item(handle) => {
+                    let item = Item::from_handle(handle, false);
+                    match item.item_type()? {
+                        ItemType::Folder => {
+                            self.push_items(item.id()?, depth + 1);
+                            ProjectEntry::Folder(item)
+                        }
+                        ItemType::Comp => {
+                            let comp = item.composition()?;
+                            for index in (0..comp.num_layers()?).rev() {
+                                self.stack.push((Pending::Layer(*comp.layer_by_index(index)?.handle()), depth + 1));
+                            }
+                            ProjectEntry::Comp(item)
+                        }
+                        ItemType::Footage | ItemType::Solid => ProjectEntry::Footage(item),
+                        ItemType::None => continue,
+                    }
+                }
+                Pending::Layer(handle) => {
+                    let layer = Layer::from_handle(handle, false);
+                    for index in (0..layer.num_effects()?).rev() {
+                        self.stack.push((Pending::Effect { layer: handle, index }, depth + 1));
+                    }
+                    ProjectEntry::Layer(layer)
+                }
+                Pending::Effect { layer, index } => {
+                    // Owned, so the reference is disposed when the entry is dropped.
+                    let effect = EffectSuite::new()?.layer_effect_by_index(layer, self.plugin_id, index)?;
+                    ProjectEntry::Effect(Effect::from_handle(effect, true))
+                }
+            };
+            return Ok(Some(WalkEntry { depth, entry }));
+        }
+        Ok(None)
+    }
+}
+
+impl Iterator for ProjectWalk {
+    type Item = Result<WalkEntry, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self.advance();
+        if next.is_err() {
+            // Don't continue with a partially expanded tree.
+            self.stack.clear();
+        }
+        next.transpose()
+    }
+}
+
+/// Serializable copy of a project, created by [`Project::snapshot()`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectSnapshot {
+    pub name: String,
+    pub path: String,
+    pub items: Vec<ProjectNode>,
+}
+
+impl ProjectSnapshot {
+    /// Match names of all effects used in the project, sorted and deduplicated.
+    pub fn effect_match_names(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.visit(&mut |node| {
+            if let ProjectNode::Comp { layers, .. } = node {
+                names.extend(layers.iter().flat_map(|layer| &layer.effects).map(|effect| effect.match_name.as_str()));
+            }
+        });
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    /// Paths of all footage files used in the project, sorted and deduplicated.
+    pub fn footage_files(&self) -> Vec<&str> {
+        let mut files = Vec::new();
+        self.visit(&mut |node| {
+            if let ProjectNode::Footage { files: footage_files, .. } = node {
+                files.extend(footage_files.iter().map(String::as_str));
+            }
+        });
+        files.sort_unstable();
+        files.dedup();
+        files
+    }
+
+    fn visit<'a>(&'a self, f: &mut impl FnMut(&'a ProjectNode)) {
+        fn visit_nodes<'a>(nodes: &'a [ProjectNode], f: &mut impl FnMut(&'a ProjectNode)) {
+            for node in nodes {
+                f(node);
+                if let ProjectNode::Folder { children, .. } = node {
+                    visit_nodes(children, f);
+                }
+            }
+        }
+        visit_nodes(&self.items, f);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProjectNode {
+    Folder {
+        id: ItemId,
+        name: String,
+        children: Vec<ProjectNode>,
+    },
+    Comp {
+        id: ItemId,
+        name: String,
+        width: u32,
+        height: u32,
+        /// In seconds.
+        duration: f64,
+        frame_rate: f64,
+        layers: Vec<LayerNode>,
+    },
+    Footage {
+        id: ItemId,
+        name: String,
+        width: u32,
+        height: u32,
+        /// In seconds.
+        duration: f64,
+        solid: bool,
+        missing: bool,
+        /// The first frame's files, empty for solids.
+        files: Vec<String>,
+        /// Set if the footage couldn't be read, `solid` and `files` are empty then.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayerNode {
+    pub id: LayerId,
+    pub index: usize,
+    pub name: String,
+    pub source_name: String,
+    /// `None` for layers without a source, like cameras, lights and text layers.
+    pub source_item_id: Option<ItemId>,
+    pub effects: Vec<EffectNode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EffectNode {
+    pub name: String,
+    pub match_name: String,
+    pub enabled: bool,
+    pub params: Vec<ParamNode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParamNode {
+    pub name: String,
+    pub match_name: String,
+    pub value: ParamValue,
+}
+
+/// Value of an effect parameter stream.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum ParamValue {
+    /// Groups, buttons and streams without data.
+    None,
+    Number(f64),
+    Vector(Vec<f64>),
+    Color { red: f64, green: f64, blue: f64, alpha: f64 },
+    /// Layer id, 0 if no layer is selected.
+    Layer(i32),
+    Mask(i32),
+    /// Values not captured in snapshots, like mask outlines, text documents, markers and arbitrary data.
+    Unsupported,
+    /// The parameter couldn't be read, with the error.
+    Unreadable(String),
+}
+
+impl From<StreamValue> for ParamValue {
+    fn from(value: StreamValue) -> Self {
+        match value {
+            StreamValue::None => Self::None,
+            StreamValue::OneD(x) => Self::Number(x),
+            StreamValue::TwoD { x, y } | StreamValue::TwoDSpatial { x, y } => Self::Vector(vec![x, y]),
+            StreamValue::ThreeD { x, y, z } | StreamValue::ThreeDSpatial { x, y, z } => Self::Vector(vec![x, y, z]),
+            StreamValue::FourD(x, y, z, w) => Self::Vector(vec![x, y, z, w]),
+            StreamValue::Color { alpha, red, green, blue } => Self::Color { red, green, blue, alpha },
+            StreamValue::LayerId(id) => Self::Layer(id),
+            StreamValue::MaskId(id) => Self::Mask(id),
+            StreamValue::Mask(_) | StreamValue::TextDocument(_) | StreamValue::ArbBlock(_) | StreamValue::Marker(_) => Self::Unsupported,
+        }
+    }
+}
+
+/// A walk entry's data, before being nested into the tree.
+enum SnapshotEntry {
+    Folder { id: ItemId, name: String },
+    Item(ProjectNode),
+    Layer(LayerNode),
+    Effect(EffectNode),
+}
+
+impl SnapshotEntry {
+    fn capture(entry: ProjectEntry, plugin_id: PluginId) -> Result<Self, Error> {
+        Ok(match entry {
+            ProjectEntry::Folder(item) => Self::Folder { id: item.id()?, name: item.name(plugin_id)? },
+            ProjectEntry::Comp(item) => {
+                let (width, height) = item.dimensions()?;
+                Self::Item(ProjectNode::Comp {
+                    id: item.id()?,
+                    name: item.name(plugin_id)?,
+                    width,
+                    height,
+                    duration: item.duration()?.into(),
+                    frame_rate: item.composition()?.framerate()?,
+                    layers: Vec::new(),
+                })
+            }
+            ProjectEntry::Footage(item) => {
+                let (width, height) = item.dimensions()?;
+                let footage = item.main_footage().and_then(|footage| {
+                    if footage.signature()? == FootageSignature::Solid {
+                        return Ok((true, Vec::new()));
+                    }
+                    let (_, files_per_frame) = footage.num_files()?;
+                    Ok((false, (0..files_per_frame).map(|index| footage.path(0, index)).collect::<Result<_, _>>()?))
+                });
+                let (solid, files, error) = match footage {
+                    Ok((solid, files)) => (solid, files, None),
+                    Err(e) => (false, Vec::new(), Some(e.to_string())),
+                };
+                Self::Item(ProjectNode::Footage {
+                    id: item.id()?,
+                    name: item.name(plugin_id)?,
+                    width,
+                    height,
+                    duration: item.duration()?.into(),
+                    solid,
+                    missing: item.flags()?.contains(ItemFlags::MISSING),
+                    files,
+                    error,
+                })
+            }
+            ProjectEntry::Layer(layer) => {
+                let (name, source_name) = layer.name(plugin_id)?;
+                Self::Layer(LayerNode {
+                    id: layer.id()?,
+                    index: layer.index()?,
+                    name,
+                    source_name,
+                    // Fails for layers without a source.
+                    source_item_id: layer.source_item_id().ok().filter(|id| *id != 0),
+                    effects: Vec::new(),
+                })
+            }
+            ProjectEntry::Effect(effect) => {
+                let key = effect.installed_key()?;
+                // A single unreadable parameter doesn't fail the whole snapshot.
+                let params = (0..effect.num_param_streams()?)
+                    .map(|index| {
+                        let stream = match effect.new_stream_by_index(plugin_id, index) {
+                            Ok(stream) => stream,
+                            Err(e) => return ParamNode { name: String::new(), match_name: String::new(), value: ParamValue::Unreadable(e.to_string()) },
+                        };
+                        ParamNode {
+                            name: stream.name(plugin_id, false).unwrap_or_default(),
+                            match_name: stream.match_name().unwrap_or_default(),
+                            value: stream
+                                .new_value(plugin_id, TimeMode::LayerTime, Time { value: 0, scale: 1 }, false)
+                                .map_or_else(|e| ParamValue::Unreadable(e.to_string()), ParamValue::from),
+                        }
+                    })
+                    .collect();
+                Self::Effect(EffectNode {
+                    name: Effect::name_of(key)?,
+                    match_name: Effect::match_name_of(key)?,
+                    enabled: effect.flags()?.contains(EffectFlags::ACTIVE),
+                    params,
+                })
+            }
+        })
+    }
+}
+
+/// Nests the depth-first `entries` at `depth` and below into a tree.
+fn build_items(entries: &mut Peekable<impl Iterator<Item = (usize, SnapshotEntry)>>, depth: usize) -> Vec<ProjectNode> {
+    let mut items = Vec::new();
+    while let Some((_, entry)) = entries.next_if(|(entry_depth, _)| *entry_depth == depth) {
+        match entry {
+            SnapshotEntry::Folder { id, name } => items.push(ProjectNode::Folder {
+                id,
+                name,
+                children: build_items(entries, depth + 1),
+            }),
+            SnapshotEntry::Item(mut node) => {
+                if let ProjectNode::Comp { layers, .. } = &mut node {
+                    while let Some((_, SnapshotEntry::Layer(mut layer))) = entries.next_if(|(d, e)| *d == depth + 1 && matches!(e, SnapshotEntry::Layer(_))) {
+                        while let Some((_, SnapshotEntry::Effect(effect))) = entries.next_if(|(d, e)| *d == depth + 2 && matches!(e, SnapshotEntry::Effect(_))) {
+                            layer.effects.push(effect);
+                        }
+                        layers.push(layer);
+                    }
+                }
+                items.push(node);
+            }
+            // Layers and effects are consumed by their composition.
+            SnapshotEntry::Layer(_) | SnapshotEntry::Effect(_) => {}
+        }
+    }
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comp(id: ItemId) -> SnapshotEntry {
+        SnapshotEntry::Item(ProjectNode::Comp {
+            id,
+            name: format!("Comp {id}"),
+            width: 1920,
+            height: 1080,
+            duration: 10.0,
+            frame_rate: 24.0,
+            layers: Vec::new(),
+        })
+    }
+
+    fn layer(id: LayerId) -> SnapshotEntry {
+        SnapshotEntry::Layer(LayerNode {
+            id,
+            index: id as usize,
+            name: format!("Layer {id}"),
+            source_name: String::new(),
+            source_item_id: None,
+            effects: Vec::new(),
+        })
+    }
+
+    fn effect(match_name: &str) -> SnapshotEntry {
+        SnapshotEntry::Effect(EffectNode {
+            name: match_name.into(),
+            match_name: match_name.into(),
+            enabled: true,
+            params: vec![ParamNode {
+                name: "Amount".into(),
+                match_name: format!("{match_name}-0001"),
+                value: StreamValue::TwoD { x: 1.0, y: 2.0 }.into(),
+            }],
+        })
+    }
+
+    #[test]
+    fn builds_tree_from_walk_order() {
+        let entries = vec![
+            (0, SnapshotEntry::Folder { id: 1, name: "Shots".into() }),
+            (1, comp(2)),
+            (2, layer(1)),
+            (3, effect("ADBE Gaussian Blur 2")),
+            (3, effect("ADBE Glo2")),
+            (2, layer(2)),
+            (1, SnapshotEntry::Folder { id: 3, name: "Empty".into() }),
+            (0, comp(4)),
+            (1, layer(1)),
+            (2, effect("ADBE Glo2")),
+        ];
+        let snapshot = ProjectSnapshot {
+            name: "test.aep".into(),
+            path: String::new(),
+            items: build_items(&mut entries.into_iter().peekable(), 0),
+        };
+
+        assert_eq!(snapshot.items.len(), 2);
+        let ProjectNode::Folder { children, .. } = &snapshot.items[0] else { panic!() };
+        assert_eq!(children.len(), 2);
+        let ProjectNode::Comp { layers, .. } = &children[0] else { panic!() };
+        assert_eq!(layers.iter().map(|l| l.effects.len()).collect::<Vec<_>>(), vec![2, 0]);
+        assert!(matches!(&children[1], ProjectNode::Folder { children, .. } if children.is_empty()));
+        assert_eq!(snapshot.effect_match_names(), vec!["ADBE Gaussian Blur 2", "ADBE Glo2"]);
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert!(json.contains(r#""value":{"type":"vector","value":[1.0,2.0]}"#));
+        assert_eq!(serde_json::from_str::<ProjectSnapshot>(&json).unwrap(), snapshot);
+    }
+
+    #[test]
+    fn unreadable_values_are_recorded() {
+        assert_eq!(ParamValue::from(StreamValue::ArbBlock(std::ptr::null_mut())), ParamValue::Unsupported);
+        assert_eq!(ParamValue::from(StreamValue::Marker(std::ptr::null_mut())), ParamValue::Unsupported);
+
+        let footage = ProjectNode::Footage {
+            id: 1,
+            name: "missing.mov".into(),
+            width: 0,
+            height: 0,
+            duration: 0.0,
+            solid: false,
+            missing: true,
+            files: Vec::new(),
+            error: Some(Error::Generic.to_string()),
+        };
+        let json = serde_json::to_string(&footage).unwrap();
+        assert_eq!(serde_json::from_str::<ProjectNode>(&json).unwrap(), footage);
+        let param = ParamValue::Unreadable("Generic error.".into());
+        assert_eq!(serde_json::from_str::<ParamValue>(&serde_json::to_string(&param).unwrap()).unwrap(), param);
+    }
+}
@@ -0,0 +1,460 @@
+use crate::aegp::suites::{Comp, Item, OutputModule, RenderQueue, RenderQueueItem};
+use crate::aegp::{
+    CompHandle, EmbeddingType, LogType, OutputModuleRefHandle, OutputTypes, PluginId, PostRenderAction, RQItemRefHandle,
+    RenderItemStatus, Script, StretchQuality, VideoChannels,
+};
+use crate::{AsPtr, Error, Time, ae_sys};
+
+/// Which part of the composition to render.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RenderRange {
+    /// The composition's work area.
+    #[default]
+    WorkArea,
+    /// The whole duration of the composition.
+    Comp,
+    /// A custom range, relative to the start of the composition.
+    Custom { start: Time, duration: Time },
+}
+
+/// Settings of one output module of a [`RenderJob`].
+///
+/// The output path may contain tokens, which are replaced when the job is submitted:
+/// - `[compName]` - name of the composition,
+/// - `[width]`, `[height]` - size of the composition,
+/// - `[frameRate]` - frame rate of the composition,
+/// - `[outputIndex]` - 1-based index of this output in the job.
+///
+/// Frame number placeholders like `[####]` are left for After Effects to fill in when rendering image sequences.
+#[derive(Debug, Clone)]
+pub struct RenderOutput {
+    path: String,
+    template: Option<String>,
+    outputs: Option<OutputTypes>,
+    channels: Option<VideoChannels>,
+    post_render_action: Option<PostRenderAction>,
+    embed: Option<EmbeddingType>,
+    stretch: Option<(bool, StretchQuality)>,
+    crop: Option<ae_sys::A_Rect>,
+    sound_format: Option<(ae_sys::AEGP_SoundDataFormat, bool)>,
+}
+
+impl RenderOutput {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_owned(),
+            template: None,
+            outputs: None,
+            channels: None,
+            post_render_action: None,
+            embed: None,
+            stretch: None,
+            crop: None,
+            sound_format: None,
+        }
+    }
+
+    /// Name of an output module template, e.g. `"Lossless"`. It's applied before all other settings.
+    pub fn template(mut self, name: &str) -> Self {
+        self.template = Some(name.to_owned());
+        self
+    }
+    pub fn outputs(mut self, outputs: OutputTypes) -> Self {
+        self.outputs = Some(outputs);
+        self
+    }
+    pub fn channels(mut self, channels: VideoChannels) -> Self {
+        self.channels = Some(channels);
+        self
+    }
+    pub fn post_render_action(mut self, action: PostRenderAction) -> Self {
+        self.post_render_action = Some(action);
+        self
+    }
+    pub fn embed(mut self, embed: EmbeddingType) -> Self {
+        self.embed = Some(embed);
+        self
+    }
+    pub fn stretch(mut self, enabled: bool, quality: StretchQuality) -> Self {
+        self.stretch = Some((enabled, quality));
+        self
+    }
+    pub fn crop(mut self, rect: ae_sys::A_Rect) -> Self {
+        self.crop = Some(rect);
+        self
+    }
+    pub fn sound_format(mut self, format: ae_sys::AEGP_SoundDataFormat, audio_enabled: bool) -> Self {
+        self.sound_format = Some((format, audio_enabled));
+        self
+    }
+}
+
+/// Builds a render queue item for a composition and applies all its settings in one go.
+///
+/// Everything that can be checked upfront is validated before the item is added to the render queue.
+/// If applying any setting fails, the item is removed again.
+///
+/// Example usage:
+/// ```ignore
+/// let job = RenderJob::new(comp)
+///     .range(RenderRange::Comp)
+///     .render_settings_template("Best Settings")
+///     .output(RenderOutput::new("/renders/[compName]/[compName]_[#####].png").template("PNG Sequence"))
+///     .output(RenderOutput::new("/renders/[compName].wav").outputs(OutputTypes::AUDIO))
+///     .submit(plugin_id)?;
+/// println!("{:?}", job.status()?);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RenderJob {
+    comp: CompHandle,
+    range: RenderRange,
+    render_settings_template: Option<String>,
+    outputs: Vec<RenderOutput>,
+    comment: Option<String>,
+    log_type: Option<LogType>,
+    queued: bool,
+}
+
+impl RenderJob {
+    pub fn new(comp: CompHandle) -> Self {
+        Self {
+            comp,
+            range: RenderRange::default(),
+            render_settings_template: None,
+            outputs: Vec::new(),
+            comment: None,
+            log_type: None,
+            queued: true,
+        }
+    }
+
+    pub fn range(mut self, range: RenderRange) -> Self {
+        self.range = range;
+        self
+    }
+    /// Name of a render settings template, e.g. `"Best Settings"`.
+    pub fn render_settings_template(mut self, name: &str) -> Self {
+        self.render_settings_template = Some(name.to_owned());
+        self
+    }
+    /// Adds an output module. At least one is required.
+    pub fn output(mut self, output: RenderOutput) -> Self {
+        self.outputs.push(output);
+        self
+    }
+    pub fn comment(mut self, comment: &str) -> Self {
+        self.comment = Some(comment.to_owned());
+        self
+    }
+    pub fn log_type(mut self, log_type: LogType) -> Self {
+        self.log_type = Some(log_type);
+        self
+    }
+    /// Whether the item is queued for the next render, `true` by default.
+    pub fn queued(mut self, queued: bool) -> Self {
+        self.queued = queued;
+        self
+    }
+
+    /// Validates the job, adds it to the render queue and applies all settings.
+    pub fn submit(self, plugin_id: PluginId) -> Result<QueuedRenderJob, Error> {
+        let tokens = self.path_tokens(plugin_id)?;
+        let paths = self.validate(&tokens)?;
+
+        let rq_items = RenderQueueItem::new()?;
+        let before = queued_comps(&rq_items)?;
+        RenderQueue::new()?.add_comp_to_render_queue(self.comp, &paths[0])?;
+        let after = queued_comps(&rq_items)?;
+        let Some(added) = added_index(&before, &after, &self.comp.as_ptr()) else {
+            log::error!("Couldn't find the item added to the render queue");
+            return Err(Error::Generic);
+        };
+        let index = added as i32;
+        let rq_item = rq_items.item_by_index(index)?;
+
+        let job = QueuedRenderJob { comp: self.comp, index, queued_comps: after[..=added].to_vec() };
+        if let Err(e) = self.apply(plugin_id, rq_item, index, &paths) {
+            let _ = rq_items.delete_item(rq_item);
+            return Err(e);
+        }
+        Ok(job)
+    }
+
+    fn path_tokens(&self, plugin_id: PluginId) -> Result<PathTokens, Error> {
+        let comp_suite = Comp::new()?;
+        let item_suite = Item::new()?;
+        let item = comp_suite.item_from_comp(self.comp)?;
+        let (width, height) = item_suite.item_dimensions(item)?;
+        Ok(PathTokens {
+            comp_name: item_suite.item_name(item, plugin_id)?,
+            width,
+            height,
+            frame_rate: comp_suite.comp_framerate(self.comp)?,
+        })
+    }
+
+    fn validate(&self, tokens: &PathTokens) -> Result<Vec<String>, Error> {
+        if self.outputs.is_empty() {
+            log::error!("RenderJob needs at least one output");
+            return Err(Error::InvalidParms);
+        }
+        if let RenderRange::Custom { start, duration } = self.range
+            && (start.scale == 0 || duration.scale == 0 || start.value < 0 || duration.value <= 0)
+        {
+            log::error!("Invalid render range: {:?}", self.range);
+            return Err(Error::InvalidParms);
+        }
+        let paths = self
+            .outputs
+            .iter()
+            .enumerate()
+            .map(|(i, output)| expand_path_tokens(&output.path, tokens, i + 1))
+            .collect::<Result<Vec<_>, _>>()?;
+        for (i, path) in paths.iter().enumerate() {
+            if paths[..i].contains(path) {
+                log::error!("Multiple outputs write to {path}");
+                return Err(Error::InvalidParms);
+            }
+        }
+        Ok(paths)
+    }
+
+    fn apply(&self, plugin_id: PluginId, rq_item: RQItemRefHandle, index: i32, paths: &[String]) -> Result<(), Error> {
+        let rq_items = RenderQueueItem::new()?;
+        let om_suite = OutputModule::new()?;
+
+        // The item is created with one default output module.
+        for _ in 1..self.outputs.len() {
+            om_suite.add_default_output_module(rq_item)?;
+        }
+
+        // Templates and the time span are only accessible from scripting. Apply them first, so the rest can override them.
+        self.apply_scripted_settings(plugin_id, index)?;
+
+        for (i, (output, path)) in self.outputs.iter().zip(paths).enumerate() {
+            let om: OutputModuleRefHandle = om_suite.output_module_by_index(rq_item, i as i32)?;
+            om_suite.set_output_file_path(rq_item, om, path)?;
+            if let Some(outputs) = output.outputs {
+                om_suite.set_enabled_outputs(rq_item, om, outputs)?;
+            }
+            if let Some(channels) = output.channels {
+                om_suite.set_output_channels(rq_item, om, channels)?;
+            }
+            if let Some(action) = output.post_render_action {
+                om_suite.set_post_render_action(rq_item, om, action)?;
+            }
+            if let Some(embed) = output.embed {
+                om_suite.set_embed_options(rq_item, om, embed)?;
+            }
+            if let Some((enabled, quality)) = output.stretch {
+                om_suite.set_stretch_info(rq_item, om, enabled, quality)?;
+            }
+            if let Some(rect) = output.crop {
+                om_suite.set_crop_info(rq_item, om, true, rect)?;
+            }
+            if let Some((format, audio_enabled)) = output.sound_format {
+                om_suite.set_sound_format_info(rq_item, om, format, audio_enabled)?;
+            }
+        }
+
+        if let Some(comment) = &self.comment {
+            rq_items.set_comment(rq_item, comment)?;
+        }
+        if let Some(log_type) = self.log_type {
+            rq_items.set_log_type(rq_item, log_type)?;
+        }
+        rq_items.set_render_state(rq_item, if self.queued { RenderItemStatus::Queued } else { RenderItemStatus::Unqueued })?;
+        Ok(())
+    }
+
+    fn apply_scripted_settings(&self, plugin_id: PluginId, index: i32) -> Result<(), Error> {
+        let mut script = format!("var rqItem = app.project.renderQueue.item({});\n", index + 1);
+        if let Some(template) = &self.render_settings_template {
+            script += &format!("rqItem.applyTemplate({});\n", Script::string_literal(template));
+        }
+        for (i, output) in self.outputs.iter().enumerate() {
+            if let Some(template) = &output.template {
+                script += &format!("rqItem.outputModule({}).applyTemplate({});\n", i + 1, Script::string_literal(template));
+            }
+        }
+        script += &match self.range {
+            RenderRange::WorkArea => "rqItem.timeSpanStart = rqItem.comp.workAreaStart;\nrqItem.timeSpanDuration = rqItem.comp.workAreaDuration;\n".to_owned(),
+            RenderRange::Comp => "rqItem.timeSpanStart = 0;\nrqItem.timeSpanDuration = rqItem.comp.duration;\n".to_owned(),
+            RenderRange::Custom { start, duration } => format!(
+                "rqItem.timeSpanStart = {};\nrqItem.timeSpanDuration = {};\n",
+                f64::from(start),
+                f64::from(duration)
+            ),
+        };
+
+        Script::new(&script).execute(plugin_id).map_err(|e| {
+            log::error!("Failed to apply render job settings: {e}");
+            e.into()
+        })
+    }
+}
+
+/// Comps of all items in the render queue, in order.
+fn queued_comps(rq_items: &RenderQueueItem) -> Result<Vec<ae_sys::AEGP_CompH>, Error> {
+    (0..rq_items.num_items()?)
+        .map(|index| Ok(rq_items.comp(rq_items.item_by_index(index)?)?.as_ptr()))
+        .collect()
+}
+
+/// Finds the item added to the render queue by comparing the comps of all items before and after adding it.
+/// Returns `None` if anything else changed too.
+///
+/// Items of the same comp next to each other can't be told apart, the last one of them is taken then,
+/// as After Effects appends new items.
+fn added_index<T: PartialEq>(before: &[T], after: &[T], comp: &T) -> Option<usize> {
+    if after.len() != before.len() + 1 {
+        return None;
+    }
+    let index = before.iter().zip(after).position(|(a, b)| a != b).unwrap_or(before.len());
+    if after[index] != *comp || after[index + 1..] != before[index..] {
+        return None;
+    }
+    Some(index + after[index + 1..].iter().take_while(|c| *c == comp).count())
+}
+
+/// A render queue item created by [`RenderJob::submit()`].
+///
+/// Render queue item handles are invalidated whenever the queue changes, so the item is looked up again on every call.
+#[derive(Debug, Clone)]
+pub struct QueuedRenderJob {
+    comp: CompHandle,
+    index: i32,
+    /// Comps of the items up to and including this one, when it was added.
+    queued_comps: Vec<ae_sys::AEGP_CompH>,
+}
+
+impl QueuedRenderJob {
+    /// Returns the current handle of the render queue item.
+    ///
+    /// Returns [`Error::InvalidIndex`] if the item, or any item before it, was removed or moved since, as the item
+    /// can't be identified reliably anymore.
+    pub fn rq_item(&self) -> Result<RQItemRefHandle, Error> {
+        let rq_items = RenderQueueItem::new()?;
+        let comps = queued_comps(&rq_items)?;
+        if comps.get(..self.queued_comps.len()) != Some(&self.queued_comps[..]) {
+            return Err(Error::InvalidIndex);
+        }
+        rq_items.item_by_index(self.index)
+    }
+
+    pub fn comp(&self) -> CompHandle {
+        self.comp
+    }
+
+    pub fn status(&self) -> Result<RenderItemStatus, Error> {
+        RenderQueueItem::new()?.render_state(self.rq_item()?)
+    }
+
+    pub fn elapsed_time(&self) -> Result<Time, Error> {
+        RenderQueueItem::new()?.elapsed_time(self.rq_item()?)
+    }
+
+    /// Returns the output file paths of all output modules.
+    pub fn output_paths(&self) -> Result<Vec<String>, Error> {
+        let rq_items = RenderQueueItem::new()?;
+        let om_suite = OutputModule::new()?;
+        let rq_item = self.rq_item()?;
+        (0..rq_items.num_output_modules(rq_item)?)
+            .map(|i| om_suite.output_file_path(rq_item, om_suite.output_module_by_index(rq_item, i)?))
+            .collect()
+    }
+
+    /// Removes the item from the render queue.
+    pub fn remove(self) -> Result<(), Error> {
+        RenderQueueItem::new()?.delete_item(self.rq_item()?)
+    }
+}
+
+struct PathTokens {
+    comp_name: String,
+    width: u32,
+    height: u32,
+    frame_rate: f64,
+}
+
+/// Replaces the tokens in an output path. Unknown tokens are an error, frame number placeholders (`[###]`) are kept.
+fn expand_path_tokens(pattern: &str, tokens: &PathTokens, output_index: usize) -> Result<String, Error> {
+    if pattern.trim().is_empty() {
+        log::error!("Empty output path");
+        return Err(Error::InvalidParms);
+    }
+    let mut result = String::with_capacity(pattern.len());
+    let mut rest = pattern;
+    while let Some(start) = rest.find('[') {
+        result += &rest[..start];
+        let Some(len) = rest[start..].find(']') else {
+            log::error!("Unterminated token in output path {pattern}");
+            return Err(Error::InvalidParms);
+        };
+        let token = &rest[start + 1..start + len];
+        match token {
+            "compName" => result += &sanitize_file_name(&tokens.comp_name),
+            "width" => result += &tokens.width.to_string(),
+            "height" => result += &tokens.height.to_string(),
+            "frameRate" => result += &format!("{}", (tokens.frame_rate * 1000.0).round() / 1000.0),
+            "outputIndex" => result += &output_index.to_string(),
+            _ if !token.is_empty() && token.chars().all(|c| c == '#') => result += &rest[start..=start + len],
+            _ => {
+                log::error!("Unknown token [{token}] in output path {pattern}");
+                return Err(Error::InvalidParms);
+            }
+        }
+        rest = &rest[start + len + 1..];
+    }
+    result += rest;
+    Ok(result)
+}
+
+/// Replaces characters that can't be used in file names on any platform.
+fn sanitize_file_name(name: &str) -> String {
+    name.chars().map(|c| if matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') { '_' } else { c }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens() -> PathTokens {
+        PathTokens { comp_name: "Main/Final".into(), width: 1920, height: 1080, frame_rate: 29.97002997 }
+    }
+
+    #[test]
+    fn expands_path_tokens() {
+        assert_eq!(
+            expand_path_tokens("/out/[compName]_[width]x[height]@[frameRate]_[outputIndex]_[####].png", &tokens(), 2).unwrap(),
+            "/out/Main_Final_1920x1080@29.97_2_[####].png"
+        );
+        assert_eq!(expand_path_tokens("C:\\out\\a.mov", &tokens(), 1).unwrap(), "C:\\out\\a.mov");
+    }
+
+    #[test]
+    fn finds_the_added_item() {
+        assert_eq!(added_index(&[1, 2], &[1, 2, 3], &3), Some(2));
+        assert_eq!(added_index(&[1, 2], &[1, 3, 2], &3), Some(1));
+        assert_eq!(added_index(&[], &[3], &3), Some(0));
+        // Same comps next to each other can't be told apart.
+        assert_eq!(added_index(&[3, 1, 3], &[3, 1, 3, 3], &3), Some(3));
+        assert_eq!(added_index(&[3, 3, 1], &[3, 3, 3, 1], &3), Some(2));
+    }
+
+    #[test]
+    fn rejects_ambiguous_queue_changes() {
+        assert_eq!(added_index(&[1, 2], &[1, 2], &3), None);
+        assert_eq!(added_index(&[1, 2], &[1, 2, 4], &3), None);
+        assert_eq!(added_index(&[1, 2], &[2, 3, 1], &3), None);
+        assert_eq!(added_index(&[1, 2], &[1, 2, 3, 3], &3), None);
+    }
+
+    #[test]
+    fn rejects_invalid_paths() {
+        assert!(expand_path_tokens("", &tokens(), 1).is_err());
+        assert!(expand_path_tokens("/out/[compName", &tokens(), 1).is_err());
+        assert!(expand_path_tokens("/out/[layerName].png", &tokens(), 1).is_err());
+        assert!(expand_path_tokens("/out/[].png", &tokens(), 1).is_err());
+    }
+}
@@ -0,0 +1,270 @@
+use std::fmt::{self, Display};
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::Error;
+use crate::aegp::PluginId;
+use crate::aegp::suites::Utility;
+
+/// ExtendScript is ES3 and has no `JSON` object, so the wrapper brings its own serializer.
+const PRELUDE: &str = r#"(function () {
+function __rs_json(v) {
+    if (v === null || v === undefined || typeof v === "function") return "null";
+    if (typeof v === "boolean") return v ? "true" : "false";
+    if (typeof v === "number") return isFinite(v) ? String(v) : "null";
+    if (typeof v === "string") {
+        var s = '"';
+        for (var i = 0; i < v.length; i++) {
+            var c = v.charAt(i), n = v.charCodeAt(i);
+            if (c === '"' || c === "\\") s += "\\" + c;
+            else if (n < 32 || n === 0x2028 || n === 0x2029) s += "\\u" + ("000" + n.toString(16)).slice(-4);
+            else s += c;
+        }
+        return s + '"';
+    }
+    if (v instanceof Array) {
+        var a = [];
+        for (var j = 0; j < v.length; j++) a.push(__rs_json(v[j]));
+        return "[" + a.join(",") + "]";
+    }
+    if (v instanceof Date) return __rs_json(v.toString());
+    var o = [];
+    for (var k in v) if (v.hasOwnProperty(k) && typeof v[k] !== "function") o.push(__rs_json(k) + ":" + __rs_json(v[k]));
+    return "{" + o.join(",") + "}";
+}
+try {
+    var __rs_result = (function () {
+"#;
+
+const EPILOGUE: &str = r#"
+    })();
+    return __rs_json({ ok: __rs_result === undefined ? null : __rs_result });
+} catch (e) {
+    return __rs_json({ error: { name: String(e.name || "Error"), message: String(e.message !== undefined ? e.message : e), line: typeof e.line === "number" ? e.line : null } });
+}
+})();"#;
+
+/// An exception thrown by a script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptException {
+    /// Error type, e.g. `"ReferenceError"`.
+    pub name: String,
+    pub message: String,
+    /// 1-based line in the script source passed to [`Script::new()`], if known.
+    pub line: Option<u32>,
+    /// The offending line of the script source.
+    pub source_line: Option<String>,
+}
+
+impl Display for ScriptException {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.message)?;
+        if let Some(line) = self.line {
+            write!(f, " (line {line}")?;
+            if let Some(source_line) = &self.source_line {
+                write!(f, ": `{}`", source_line.trim())?;
+            }
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+/// Error returned by [`Script::run()`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptError {
+    /// Calling into After Effects failed.
+    Host(Error),
+    /// The script didn't compile, or threw an exception.
+    Exception(ScriptException),
+    /// The script returned a value which couldn't be deserialized into the requested type.
+    InvalidResult { result: String, message: String },
+}
+
+impl Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Host(e) => write!(f, "{e}"),
+            Self::Exception(e) => write!(f, "{e}"),
+            Self::InvalidResult { result, message } => write!(f, "Invalid script result {result}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+impl From<Error> for ScriptError {
+    fn from(e: Error) -> Self {
+        Self::Host(e)
+    }
+}
+
+impl From<ScriptError> for Error {
+    fn from(e: ScriptError) -> Self {
+        match e {
+            ScriptError::Host(e) => e,
+            ScriptError::Exception(_) => Error::Generic,
+            ScriptError::InvalidResult { .. } => Error::InternalStructDamaged,
+        }
+    }
+}
+
+/// Runs ExtendScript code and deserializes its result.
+///
+/// The source is the body of a function, so use `return` to pass a value back. The value is serialized to JSON
+/// in the script and deserialized into any `T: DeserializeOwned` on the Rust side.
+/// Rust values are passed in with [`arg()`](Self::arg), which declares them as variables at the top of the function,
+/// or embedded into the source with [`string_literal()`](Self::string_literal) and [`json_literal()`](Self::json_literal).
+///
+/// Exceptions thrown by the script are returned as [`ScriptError::Exception`], with line numbers relative to the source.
+///
+/// Example usage:
+/// ```ignore
+/// let names: Vec<String> = Script::new(r#"
+///     var names = [];
+///     for (var i = 1; i <= app.project.numItems; i++) {
+///         if (app.project.item(i).name.indexOf(prefix) === 0) names.push(app.project.item(i).name);
+///     }
+///     return names;
+/// "#)
+/// .arg("prefix", "Shot_")?
+/// .run(plugin_id)?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Script {
+    args: String,
+    source: String,
+}
+
+impl Script {
+    pub fn new(source: &str) -> Self {
+        Self {
+            args: String::new(),
+            source: source.to_owned(),
+        }
+    }
+
+    /// Declares `var name = value;` at the top of the script, with `value` serialized as a JSON literal.
+    ///
+    /// Returns [`Error::InvalidParms`] if `name` isn't a valid identifier.
+    pub fn arg<T: Serialize + ?Sized>(mut self, name: &str, value: &T) -> Result<Self, Error> {
+        let valid_identifier = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+        if !valid_identifier {
+            return Err(Error::InvalidParms);
+        }
+        self.args += &format!("var {name} = {};", Self::json_literal(value)?);
+        Ok(self)
+    }
+
+    /// Appends a line of source.
+    pub fn line(mut self, source: &str) -> Self {
+        if !self.source.is_empty() && !self.source.ends_with('\n') {
+            self.source.push('\n');
+        }
+        self.source += source;
+        self
+    }
+
+    /// Returns a quoted and escaped string literal, safe to embed into script source.
+    pub fn string_literal(s: &str) -> String {
+        // Serializing a `&str` can't fail.
+        Self::json_literal(s).unwrap_or_default()
+    }
+
+    /// Returns `value` serialized as a JSON literal, safe to embed into script source.
+    pub fn json_literal<T: Serialize + ?Sized>(value: &T) -> Result<String, Error> {
+        let json = serde_json::to_string(value).map_err(|_| Error::InvalidParms)?;
+        // Valid in JSON, but line terminators in ES3 string literals.
+        Ok(json.replace('\u{2028}', "\\u2028").replace('\u{2029}', "\\u2029"))
+    }
+
+    /// Returns the full script passed to After Effects.
+    pub fn wrapped_source(&self) -> String {
+        // Arguments are all on the first line, so line numbers only shift by the prelude.
+        format!("{PRELUDE}{}\n{}{EPILOGUE}", self.args, self.source)
+    }
+
+    /// Runs the script and deserializes its return value.
+    pub fn run<T: DeserializeOwned>(&self, plugin_id: PluginId) -> Result<T, ScriptError> {
+        let (result, error) = Utility::new()?.execute_script(plugin_id, &self.wrapped_source(), false)?;
+        self.parse_result(&result, &error)
+    }
+
+    /// Runs the script, ignoring its return value.
+    pub fn execute(&self, plugin_id: PluginId) -> Result<(), ScriptError> {
+        self.run::<serde::de::IgnoredAny>(plugin_id).map(|_| ())
+    }
+
+    fn parse_result<T: DeserializeOwned>(&self, result: &str, error: &str) -> Result<T, ScriptError> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "lowercase")]
+        enum Response<T> {
+            Ok(T),
+            Error { name: String, message: String, line: Option<u32> },
+        }
+
+        // Errors not caught by the wrapper, i.e. syntax errors.
+        if !error.is_empty() {
+            return Err(ScriptError::Exception(ScriptException {
+                name: "SyntaxError".into(),
+                message: error.to_owned(),
+                line: None,
+                source_line: None,
+            }));
+        }
+
+        let invalid = |e: serde_json::Error| ScriptError::InvalidResult {
+            result: result.to_owned(),
+            message: e.to_string(),
+        };
+        match serde_json::from_str::<Response<serde_json::Value>>(result).map_err(invalid)? {
+            Response::Ok(value) => serde_json::from_value(value).map_err(invalid),
+            Response::Error { name, message, line } => {
+                let prelude_lines = PRELUDE.lines().count() as u32 + 1;
+                let line = line.and_then(|line| line.checked_sub(prelude_lines)).filter(|line| *line > 0);
+                Err(ScriptError::Exception(ScriptException {
+                    name,
+                    message,
+                    line,
+                    source_line: line.and_then(|line| self.source.lines().nth(line as usize - 1)).map(str::to_owned),
+                }))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_literals() {
+        assert_eq!(Script::string_literal("a\"b\\c\n"), r#""a\"b\\c\n""#);
+        assert_eq!(Script::string_literal("\u{2028}"), r#""\u2028""#);
+        assert_eq!(Script::json_literal(&[1, 2]).unwrap(), "[1,2]");
+        assert!(Script::new("").arg("1abc", &1).is_err());
+        assert!(Script::new("").arg("a b", &1).is_err());
+        assert!(Script::new("").arg("$ok_1", &1).is_ok());
+    }
+
+    #[test]
+    fn parses_results() {
+        let script = Script::new("return [1, 2];");
+        assert_eq!(script.parse_result::<Vec<i32>>(r#"{"ok":[1,2]}"#, "").unwrap(), vec![1, 2]);
+        assert!(matches!(script.parse_result::<Vec<i32>>(r#"{"ok":"x"}"#, ""), Err(ScriptError::InvalidResult { .. })));
+        assert!(matches!(script.parse_result::<()>("", "Syntax error"), Err(ScriptError::Exception(_))));
+    }
+
+    #[test]
+    fn maps_exception_lines_to_source() {
+        let script = Script::new("var a = 1;\nfoo();\nreturn a;");
+        // Line 2 of the source, after the prelude and the arguments line.
+        let wrapped_line = script.wrapped_source().lines().position(|l| l == "foo();").unwrap() as u32 + 1;
+        let result = format!(r#"{{"error":{{"name":"ReferenceError","message":"foo is undefined","line":{wrapped_line}}}}}"#);
+        let ScriptError::Exception(e) = script.parse_result::<()>(&result, "").unwrap_err() else { panic!() };
+        assert_eq!(e.line, Some(2));
+        assert_eq!(e.source_line.as_deref(), Some("foo();"));
+        assert_eq!(e.to_string(), "ReferenceError: foo is undefined (line 2: `foo();`)");
+    }
+}
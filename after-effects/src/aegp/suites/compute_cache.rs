@@ -1,11 +1,11 @@
-use std::{ffi::CString, marker::PhantomData, str::FromStr};
+use std::{ffi::CString, hash::Hash, marker::PhantomData, str::FromStr, sync::Arc};
 
 use after_effects_sys::{
     AEGP_CCCheckoutReceiptP, AEGP_CCComputeKeyP, AEGP_CCComputeOptionsRefconP,
     AEGP_CCComputeValueRefconP, AEGP_ComputeCacheCallbacks,
 };
 
-use crate::{aegp::Guid, *};
+use crate::{aegp::{Guid, suites::Hash as HashSuite}, *};
 
 #[inline(always)]
 fn conjure<F>() -> F {
@@ -13,6 +13,11 @@ fn conjure<F>() -> F {
     unsafe { std::mem::zeroed() }
 }
 
+/// Runs a compute cache callback, which must never unwind into After Effects.
+fn catch_panic<R>(on_panic: R, f: impl FnOnce() -> R) -> R {
+    crate::catch_ffi_panic("compute cache callback", f).unwrap_or(on_panic)
+}
+
 define_suite!(
     ComputeCacheSuite,
     AEGP_ComputeCacheSuite1,
@@ -87,13 +92,13 @@ impl ComputeCacheSuite {
             GenKey: Fn(&O) -> Result<Guid, Error>,
         {
             let opts = unsafe { &*(options_p as *const O) };
-            match conjure::<GenKey>()(opts) {
+            catch_panic(Error::Generic.into(), || match conjure::<GenKey>()(opts) {
                 Ok(guid) => {
                     unsafe { *out_key_p = guid.0 };
                     ae_sys::A_Err_NONE as _
                 }
                 Err(e) => e.into(),
-            }
+            })
         }
 
         unsafe extern "C" fn compute_trampoline<O, V, Compute>(
@@ -104,13 +109,13 @@ impl ComputeCacheSuite {
             Compute: Fn(&O) -> Result<V, Error>,
         {
             let opts = unsafe { &*(options_p as *const O) };
-            match conjure::<Compute>()(opts) {
+            catch_panic(Error::Generic.into(), || match conjure::<Compute>()(opts) {
                 Ok(value) => {
                     unsafe { *out_value_pp = Box::into_raw(Box::new(value)) as _ };
                     ae_sys::A_Err_NONE as _
                 }
                 Err(e) => e.into(),
-            }
+            })
         }
 
         unsafe extern "C" fn approx_size_trampoline<V, ApproxSize>(
@@ -119,14 +124,14 @@ impl ComputeCacheSuite {
         where
             ApproxSize: Fn(&V) -> usize,
         {
-            conjure::<ApproxSize>()(unsafe { &*(value_p as *const V) })
+            catch_panic(0, || conjure::<ApproxSize>()(unsafe { &*(value_p as *const V) }))
         }
 
         unsafe extern "C" fn delete_trampoline<V, Delete>(value_p: AEGP_CCComputeValueRefconP)
         where
             Delete: Fn(V),
         {
-            conjure::<Delete>()(unsafe { *Box::from_raw(value_p as *mut V) })
+            catch_panic((), || conjure::<Delete>()(unsafe { *Box::from_raw(value_p as *mut V) }))
         }
 
         let c_str = CString::from_str(compute_class_id.id()).map_err(|_| Error::InvalidParms)?;
@@ -233,3 +238,235 @@ impl ComputeCacheSuite {
         unsafe { (value_ptr as *const V).as_ref() }.ok_or(Error::Generic)
     }
 }
+
+type ComputeFn<O, V> = Box<dyn Fn(&O) -> Result<V, Error> + Send + Sync>;
+type ApproxSizeFn<V> = Box<dyn Fn(&V) -> usize + Send + Sync>;
+type DeleteFn<V> = Box<dyn Fn(V) + Send + Sync>;
+
+/// Callbacks of a [`ComputeCache`] class. Shared between the handle and all values computed by it,
+/// so values purged by After Effects after the handle is gone can still be deleted.
+struct ComputeCacheCallbacks<O, V> {
+    compute: ComputeFn<O, V>,
+    approx_size: ApproxSizeFn<V>,
+    delete: Option<DeleteFn<V>>,
+}
+
+/// What we pass to After Effects as the opaque options pointer.
+struct ComputeRequest<'a, O, V> {
+    callbacks: &'a Arc<ComputeCacheCallbacks<O, V>>,
+    options: &'a O,
+}
+
+/// What we return to After Effects as the opaque value pointer.
+struct ComputedValue<O, V> {
+    value: Option<V>,
+    size: usize,
+    callbacks: Arc<ComputeCacheCallbacks<O, V>>,
+}
+
+/// A compute cache class with a typed, safe interface.
+///
+/// Unlike [`ComputeCacheSuite::register_class`], the callbacks can be closures capturing
+/// configuration, and the cache key is derived from the options with [`Hash`] using
+/// [`HashSuite`](crate::aegp::suites::Hash), so there's no need to compute a [`Guid`] by hand.
+///
+/// The class is unregistered when the handle is dropped, typically in `GlobalSetdown`.
+///
+/// Example usage:
+/// ```ignore
+/// let cache = ComputeCache::<SimParams, SimStep>::builder("particle_cache", move |params| simulate(params, quality))
+///     .approx_size(|step| step.0.len() * std::mem::size_of::<Particle>())
+///     .register()?;
+///
+/// let step = cache.compute(&params)?;
+/// ```
+pub struct ComputeCache<O, V> {
+    id: CString,
+    callbacks: Arc<ComputeCacheCallbacks<O, V>>,
+    suite: ComputeCacheSuite,
+}
+
+/// Builder for a [`ComputeCache`], created with [`ComputeCache::builder`].
+pub struct ComputeCacheBuilder<O, V> {
+    id: String,
+    callbacks: ComputeCacheCallbacks<O, V>,
+}
+
+impl<O: Hash + 'static, V: 'static> ComputeCacheBuilder<O, V> {
+    /// Sets the callback returning the approximate size of a value in bytes, used by After Effects
+    /// to decide when to purge the cache. Defaults to `size_of::<V>()`.
+    pub fn approx_size(mut self, approx_size: impl Fn(&V) -> usize + Send + Sync + 'static) -> Self {
+        self.callbacks.approx_size = Box::new(approx_size);
+        self
+    }
+
+    /// Sets the callback invoked with every value purged from the cache. By default the value is just dropped.
+    pub fn on_delete(mut self, delete: impl Fn(V) + Send + Sync + 'static) -> Self {
+        self.callbacks.delete = Some(Box::new(delete));
+        self
+    }
+
+    /// Registers the class with After Effects.
+    pub fn register(self) -> Result<ComputeCache<O, V>, Error> {
+        let cache = ComputeCache {
+            id: CString::new(self.id).map_err(|_| Error::InvalidParms)?,
+            callbacks: Arc::new(self.callbacks),
+            suite: ComputeCacheSuite::new()?,
+        };
+
+        let callbacks = AEGP_ComputeCacheCallbacks {
+            generate_key: Some(ComputeCache::<O, V>::generate_key_trampoline),
+            compute: Some(ComputeCache::<O, V>::compute_trampoline),
+            approx_size_value: Some(ComputeCache::<O, V>::approx_size_trampoline),
+            delete_compute_value: Some(ComputeCache::<O, V>::delete_trampoline),
+        };
+        call_suite_fn!(cache.suite, AEGP_ClassRegister, cache.id.as_ptr(), &callbacks as *const _)?;
+
+        Ok(cache)
+    }
+}
+
+impl<O: Hash + 'static, V: 'static> ComputeCache<O, V> {
+    /// Starts building a compute cache class. `id` must be unique across all plug-ins, so prefix it with your match name.
+    pub fn builder(id: &str, compute: impl Fn(&O) -> Result<V, Error> + Send + Sync + 'static) -> ComputeCacheBuilder<O, V> {
+        ComputeCacheBuilder {
+            id: id.to_owned(),
+            callbacks: ComputeCacheCallbacks {
+                compute: Box::new(compute),
+                approx_size: Box::new(|_| std::mem::size_of::<V>()),
+                delete: None,
+            },
+        }
+    }
+
+    /// Registers a compute cache class with default size estimation and deletion.
+    pub fn register(id: &str, compute: impl Fn(&O) -> Result<V, Error> + Send + Sync + 'static) -> Result<Self, Error> {
+        Self::builder(id, compute).register()
+    }
+
+    /// Returns the cached value for `options`, computing it if needed.
+    /// If another thread is already computing the same value, this waits for it.
+    pub fn compute(&self, options: &O) -> Result<ComputeCacheEntry<'_, V>, Error> {
+        self.checkout(options, true)
+    }
+
+    /// Returns the cached value for `options`, computing it if needed.
+    /// Returns `None` instead of waiting if another thread is already computing the same value.
+    pub fn try_compute(&self, options: &O) -> Result<Option<ComputeCacheEntry<'_, V>>, Error> {
+        match self.checkout(options, false) {
+            Ok(entry) => Ok(Some(entry)),
+            Err(Error::NotInComputeCache) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns the cached value for `options` if it has already been computed, without triggering computation.
+    pub fn cached(&self, options: &O) -> Result<Option<ComputeCacheEntry<'_, V>>, Error> {
+        let mut request = ComputeRequest { callbacks: &self.callbacks, options };
+        let result = call_suite_fn_single!(self.suite, AEGP_CheckoutCached -> AEGP_CCCheckoutReceiptP, self.id.as_ptr(), &mut request as *mut _ as *mut _);
+        match result {
+            Ok(receipt_ptr) => self.entry(receipt_ptr).map(Some),
+            Err(Error::NotInComputeCache) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// The id this class was registered with.
+    pub fn id(&self) -> &str {
+        self.id.to_str().unwrap_or_default()
+    }
+
+    fn checkout(&self, options: &O, wait_for_other_thread: bool) -> Result<ComputeCacheEntry<'_, V>, Error> {
+        let mut request = ComputeRequest { callbacks: &self.callbacks, options };
+        let receipt_ptr = call_suite_fn_single!(
+            self.suite,
+            AEGP_ComputeIfNeededAndCheckout -> AEGP_CCCheckoutReceiptP,
+            self.id.as_ptr(),
+            &mut request as *mut _ as *mut _,
+            wait_for_other_thread
+        )?;
+        self.entry(receipt_ptr)
+    }
+
+    fn entry(&self, receipt_ptr: AEGP_CCCheckoutReceiptP) -> Result<ComputeCacheEntry<'_, V>, Error> {
+        // Created first, so the receipt is checked in on error as well.
+        let mut entry = ComputeCacheEntry { suite: &self.suite, receipt_ptr, value: std::ptr::null() };
+        let value_ptr = call_suite_fn_single!(self.suite, AEGP_GetReceiptComputeValue -> AEGP_CCComputeValueRefconP, receipt_ptr)?;
+        let computed = unsafe { (value_ptr as *const ComputedValue<O, V>).as_ref() }.ok_or(Error::Generic)?;
+        entry.value = computed.value.as_ref().ok_or(Error::Generic)?;
+        Ok(entry)
+    }
+
+    unsafe extern "C" fn generate_key_trampoline(options_p: AEGP_CCComputeOptionsRefconP, out_key_p: AEGP_CCComputeKeyP) -> ae_sys::A_Err {
+        let request = unsafe { &*(options_p as *const ComputeRequest<O, V>) };
+        // `Hash` implementations are user code too.
+        catch_panic(Error::Generic.into(), || match HashSuite::new().and_then(|hash| hash.hash_value(request.options)) {
+            Ok(guid) => {
+                unsafe { *out_key_p = guid.0 };
+                ae_sys::A_Err_NONE as _
+            }
+            Err(e) => e.into(),
+        })
+    }
+
+    unsafe extern "C" fn compute_trampoline(options_p: AEGP_CCComputeOptionsRefconP, out_value_pp: *mut AEGP_CCComputeValueRefconP) -> ae_sys::A_Err {
+        let request = unsafe { &*(options_p as *const ComputeRequest<O, V>) };
+        catch_panic(Error::Generic.into(), || match (request.callbacks.compute)(request.options) {
+            Ok(value) => {
+                // Values are immutable once computed, so the size can be computed right away.
+                let size = (request.callbacks.approx_size)(&value);
+                let computed = ComputedValue { value: Some(value), size, callbacks: request.callbacks.clone() };
+                unsafe { *out_value_pp = Box::into_raw(Box::new(computed)) as _ };
+                ae_sys::A_Err_NONE as _
+            }
+            Err(e) => e.into(),
+        })
+    }
+
+    unsafe extern "C" fn approx_size_trampoline(value_p: AEGP_CCComputeValueRefconP) -> usize {
+        unsafe { &*(value_p as *const ComputedValue<O, V>) }.size
+    }
+
+    unsafe extern "C" fn delete_trampoline(value_p: AEGP_CCComputeValueRefconP) {
+        // Dropping the value runs user code as well.
+        catch_panic((), || {
+            let mut computed = unsafe { Box::from_raw(value_p as *mut ComputedValue<O, V>) };
+            if let (Some(delete), Some(value)) = (&computed.callbacks.delete, computed.value.take()) {
+                delete(value);
+            }
+        });
+    }
+}
+
+impl<O, V> Drop for ComputeCache<O, V> {
+    fn drop(&mut self) {
+        // All cached values are purged through `delete_trampoline` here.
+        if let Err(e) = call_suite_fn!(self.suite, AEGP_ClassUnregister, self.id.as_ptr()) {
+            log::error!("Failed to unregister compute cache class {:?}: {e:?}", self.id);
+        }
+    }
+}
+
+unsafe impl<O, V: Send + Sync> Send for ComputeCache<O, V> {}
+unsafe impl<O, V: Send + Sync> Sync for ComputeCache<O, V> {}
+
+/// A checked out value of a [`ComputeCache`]. The receipt is checked in when this is dropped,
+/// which must happen before returning to After Effects.
+pub struct ComputeCacheEntry<'a, V> {
+    suite: &'a ComputeCacheSuite,
+    receipt_ptr: AEGP_CCCheckoutReceiptP,
+    value: *const V,
+}
+
+impl<V> std::ops::Deref for ComputeCacheEntry<'_, V> {
+    type Target = V;
+    fn deref(&self) -> &V {
+        unsafe { &*self.value }
+    }
+}
+
+impl<V> Drop for ComputeCacheEntry<'_, V> {
+    fn drop(&mut self) {
+        let _ = call_suite_fn!(self.suite, AEGP_CheckinComputeReceipt, self.receipt_ptr);
+    }
+}
@@ -1,4 +1,4 @@
-use std::hash::Hash;
+use std::hash::{Hash, Hasher};
 
 use crate::*;
 
@@ -64,4 +64,27 @@ impl HashSuite {
             hash.as_mut_ptr()
         )
     }
+
+    /// Computes the hash of any value implementing [`Hash`], e.g. the options struct of a compute cache class.
+    ///
+    /// All the bytes fed to the [`Hasher`] are collected and hashed at once with [`Self::create_hash_from_ptr`].
+    pub fn hash_value<T: Hash + ?Sized>(&self, value: &T) -> Result<Guid, Error> {
+        let mut hasher = ByteCollector(Vec::new());
+        value.hash(&mut hasher);
+        self.create_hash_from_ptr(&hasher.0)
+    }
+}
+
+/// A [`Hasher`] which doesn't hash anything, but collects the bytes to be hashed by After Effects.
+struct ByteCollector(Vec<u8>);
+
+impl Hasher for ByteCollector {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+
+    // Not used for the key itself, FNV-1a is good enough for `Hash` implementations which call it.
+    fn finish(&self) -> u64 {
+        self.0.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+    }
 }
@@ -192,6 +192,36 @@ impl PersistentDataSuite {
         Ok(ptr)
     }
 
+    /// Reads `size` bytes of unstructured data stored at a given section's value.
+    ///
+    /// If the key doesn't exist, a zeroed buffer of `size` bytes is written to the blob and returned.
+    pub fn data_bytes(
+        &self,
+        blob_handle: PersistentBlobHandle,
+        section_key: &str,
+        value_key: &str,
+        size: usize,
+    ) -> Result<Vec<u8>, Error> {
+        let section_key = CString::new(section_key).map_err(|_| Error::InvalidParms)?;
+        let value_key = CString::new(value_key).map_err(|_| Error::InvalidParms)?;
+
+        let default = vec![0u8; size];
+        let mut data = vec![0u8; size];
+
+        call_suite_fn!(
+            self,
+            AEGP_GetData,
+            blob_handle.as_ptr(),
+            section_key.as_ptr(),
+            value_key.as_ptr(),
+            size as _,
+            default.as_ptr() as _,
+            data.as_mut_ptr() as _
+        )?;
+
+        Ok(data)
+    }
+
     /// Obtains the data located at a given section's value.
     #[deprecated(since = "0.5.0", note = "renamed to `data`")]
     pub fn get_data(
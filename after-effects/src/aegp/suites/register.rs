@@ -106,13 +106,10 @@ impl RegisterSuite {
 
             let hook_priority_enum = HookPriority::from(hook_priority);
 
-            let res = callback(
-                global,
-                refcon,
-                command,
-                hook_priority_enum,
-                already_handled_bool,
-            );
+            let res = crate::catch_ffi_panic("command hook", || {
+                callback(global, refcon, command, hook_priority_enum, already_handled_bool)
+            })
+            .unwrap_or(Err(Error::Generic));
 
             match res {
                 Ok(CommandHookStatus::Handled) => {
@@ -167,7 +164,9 @@ impl RegisterSuite {
                 return Error::Generic.into();
             };
 
-            match callback(global, refcon, window_type.into()) {
+            let res = crate::catch_ffi_panic("update menu hook", || callback(global, refcon, window_type.into()))
+                .unwrap_or(Err(Error::Generic));
+            match res {
                 Ok(_) => Error::None,
                 Err(e) => e,
             }
@@ -203,7 +202,9 @@ impl RegisterSuite {
             };
 
             let (cb, refcon) = unsafe { &mut *(refcon as *mut (DeathHook<P, T>, T)) };
-            match cb(global, refcon) {
+            let res = crate::catch_ffi_panic("death hook", || cb(global, refcon))
+                .unwrap_or(Err(Error::Generic));
+            match res {
                 Ok(_) => Error::None,
                 Err(e) => e,
             }
@@ -1,7 +1,24 @@
 use crate::*;
 use ae_sys::*;
+use std::cell::RefCell;
+use std::collections::HashMap;
 //use ae_sys::{ AEGP_FrameReceiptH, AEGP_WorldH };
 
+type FrameReadyCallback = Box<dyn FnOnce(AEGP_AsyncRequestId, bool, Error, AEGP_FrameReceiptH)>;
+
+struct PendingRequest {
+    /// `None` while After Effects hasn't returned the id yet.
+    request_id: Option<AEGP_AsyncRequestId>,
+    callback: FrameReadyCallback,
+}
+
+thread_local! {
+    /// Callbacks of async layer renders, by the refcon passed to After Effects.
+    /// Kept here rather than owned by After Effects, so they're freed when a request is canceled, even if it never calls back.
+    static PENDING_REQUESTS: RefCell<HashMap<usize, PendingRequest>> = RefCell::default();
+}
+static NEXT_REQUEST_REFCON: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(1);
+
 define_suite!(
     /// Since we introduced the AEGP API, we've been asked to provide functions for retrieving rendered frames.
     ///
@@ -29,23 +46,24 @@ impl RenderSuite {
     /// Create the [`aegp::RenderOptionsHandle`] using the [`aegp::suites::RenderOptions`].
     ///
     /// Optionally, the AEGP can pass a function to be called by After Effects if the user cancels the current render.
-    pub fn render_and_checkout_frame<F: Fn() -> bool>(&self, options: impl AsPtr<AEGP_RenderOptionsH>, cancel_function: Option<F>) -> Result<AEGP_FrameReceiptH, Error> {
+    pub fn render_and_checkout_frame<F: FnMut() -> bool>(&self, options: impl AsPtr<AEGP_RenderOptionsH>, cancel_function: Option<F>) -> Result<AEGP_FrameReceiptH, Error> {
         unsafe extern "C" fn cancel_fn(refcon: *mut std::ffi::c_void, cancel: *mut ae_sys::A_Boolean) -> A_Err {
             unsafe {
-                let cb = Box::<Box<dyn Fn() -> bool + Send + Sync + 'static>>::from_raw(refcon as *mut _);
+                // Called repeatedly during the render, the closure is owned by the calling function.
+                let cb = &mut *(refcon as *mut Box<dyn FnMut() -> bool>);
                 *cancel = cb() as _;
             }
             ae_sys::PF_Err_NONE as ae_sys::PF_Err
         }
 
-        let cancel_function = cancel_function.map(|x| Box::new(Box::new(x)));
+        let mut cancel_function = cancel_function.map(|x| Box::new(x) as Box<dyn FnMut() -> bool + '_>);
 
         call_suite_fn_single!(
             self,
             AEGP_RenderAndCheckoutFrame -> AEGP_FrameReceiptH,
             options.as_ptr(),
             if cancel_function.is_some() { Some(cancel_fn) } else { None },
-            cancel_function.map_or(std::ptr::null_mut(), |f| Box::into_raw(f) as *mut _)
+            cancel_function.as_mut().map_or(std::ptr::null_mut(), |f| f as *mut _ as *mut _)
         )
     }
 
@@ -67,39 +85,90 @@ impl RenderSuite {
     pub fn render_and_checkout_layer_frame<F: FnMut() -> bool>(&self, options: impl AsPtr<AEGP_LayerRenderOptionsH>, cancel_function: Option<F>) -> Result<AEGP_FrameReceiptH, Error> {
         unsafe extern "C" fn cancel_fn(refcon: *mut std::ffi::c_void, cancel: *mut ae_sys::A_Boolean) -> A_Err {
             unsafe {
-                let cb = Box::<Box<dyn Fn() -> bool + Send + Sync + 'static>>::from_raw(refcon as *mut _);
+                // Called repeatedly during the render, the closure is owned by the calling function.
+                let cb = &mut *(refcon as *mut Box<dyn FnMut() -> bool>);
                 *cancel = cb() as _;
             }
             ae_sys::PF_Err_NONE as ae_sys::PF_Err
         }
 
-        let cancel_function = cancel_function.map(|x| Box::new(Box::new(x)));
+        let mut cancel_function = cancel_function.map(|x| Box::new(x) as Box<dyn FnMut() -> bool + '_>);
 
         call_suite_fn_single!(
             self,
             AEGP_RenderAndCheckoutLayerFrame -> AEGP_FrameReceiptH,
             options.as_ptr(),
             if cancel_function.is_some() { Some(cancel_fn) } else { None },
-            cancel_function.map_or(std::ptr::null_mut(), |f| Box::into_raw(f) as *mut _)
+            cancel_function.as_mut().map_or(std::ptr::null_mut(), |f| f as *mut _ as *mut _)
         )
     }
 
-    pub fn render_and_checkout_layer_frame_async<R: FnMut(AEGP_AsyncRequestId, bool, Error, AEGP_FrameReceiptH)>(&self, options: impl AsPtr<AEGP_LayerRenderOptionsH>, callback: R) -> Result<AEGP_AsyncRequestId, Error> {
+    /// Starts rendering the layer frame without blocking, see [`render_layer_frame_async()`](Self::render_layer_frame_async).
+    ///
+    /// # Safety
+    /// `callback` is called on the main thread once the frame is ready, after this function returned.
+    /// Everything it borrows must stay alive until then, or until the request is canceled with
+    /// [`cancel_async_request()`](Self::cancel_async_request).
+    #[deprecated(note = "the callback may outlive its captures, use `render_layer_frame_async()` which requires a `'static` callback")]
+    pub unsafe fn render_and_checkout_layer_frame_async<R: FnMut(AEGP_AsyncRequestId, bool, Error, AEGP_FrameReceiptH)>(&self, options: impl AsPtr<AEGP_LayerRenderOptionsH>, callback: R) -> Result<AEGP_AsyncRequestId, Error> {
+        let callback: Box<dyn FnOnce(AEGP_AsyncRequestId, bool, Error, AEGP_FrameReceiptH) + '_> = Box::new(callback);
+        // Keeping the captures alive is up to the caller, see the safety section.
+        let callback: FrameReadyCallback = unsafe { std::mem::transmute(callback) };
+        self.render_layer_frame_async(options, callback)
+    }
+
+    /// New in CC 2015. Starts rendering the layer frame without blocking, `callback` is called on the main thread once the frame is ready or the request was canceled.
+    ///
+    /// The receipt passed to the callback must be checked in with [`checkin_frame()`](Self::checkin_frame).
+    ///
+    /// See [`aegp::render_layer_frame()`] for a `Future` based version.
+    pub fn render_layer_frame_async<R: FnOnce(AEGP_AsyncRequestId, bool, Error, AEGP_FrameReceiptH) + 'static>(&self, options: impl AsPtr<AEGP_LayerRenderOptionsH>, callback: R) -> Result<AEGP_AsyncRequestId, Error> {
         unsafe extern "C" fn frame_ready_cb(request_id: AEGP_AsyncRequestId, was_canceled: A_Boolean, error: A_Err, receipt: AEGP_FrameReceiptH, refcon: AEGP_AsyncFrameRequestRefcon) -> A_Err {
-            let cb = unsafe { Box::<Box<dyn Fn(AEGP_AsyncRequestId, bool, Error, AEGP_FrameReceiptH)>>::from_raw(refcon as *mut _) };
-            cb(request_id, was_canceled != 0, Error::from(error), receipt);
+            let Some(pending) = PENDING_REQUESTS.with_borrow_mut(|pending| pending.remove(&(refcon as usize))) else {
+                // Canceled, the callback is gone already.
+                if !receipt.is_null() && let Ok(suite) = RenderSuite::new() {
+                    let _ = suite.checkin_frame(receipt);
+                }
+                return ae_sys::PF_Err_NONE as ae_sys::PF_Err;
+            };
+            crate::catch_ffi_panic("async layer render callback", || (pending.callback)(request_id, was_canceled != 0, Error::from(error), receipt));
             ae_sys::PF_Err_NONE as ae_sys::PF_Err
         }
 
-        let callback = Box::new(Box::new(callback));
+        let refcon = NEXT_REQUEST_REFCON.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        PENDING_REQUESTS.with_borrow_mut(|pending| pending.insert(refcon, PendingRequest { request_id: None, callback: Box::new(callback) }));
 
-        call_suite_fn_single!(
+        let result = call_suite_fn_single!(
             self,
             AEGP_RenderAndCheckoutLayerFrame_Async -> AEGP_AsyncRequestId,
             options.as_ptr(),
             Some(frame_ready_cb),
-            Box::into_raw(callback) as *mut _
-        )
+            refcon as *mut _
+        );
+        // The callback may have been called synchronously already, e.g. for cached frames.
+        let pending = PENDING_REQUESTS.with_borrow_mut(|pending| match &result {
+            Ok(request_id) => {
+                if let Some(request) = pending.get_mut(&refcon) {
+                    request.request_id = Some(*request_id);
+                }
+                None
+            }
+            Err(_) => pending.remove(&refcon),
+        });
+        drop(pending);
+        result
+    }
+
+    /// Cancels a request started with [`render_layer_frame_async()`](Self::render_layer_frame_async).
+    /// Its callback is dropped without being called if it is still pending.
+    pub fn cancel_async_request(&self, request_id: AEGP_AsyncRequestId) -> Result<(), Error> {
+        call_suite_fn!(self, AEGP_CancelAsyncRequest, request_id)?;
+        let canceled = PENDING_REQUESTS.with_borrow_mut(|pending| {
+            let refcon = pending.iter().find(|(_, request)| request.request_id == Some(request_id)).map(|(refcon, _)| *refcon)?;
+            pending.remove(&refcon)
+        });
+        drop(canceled);
+        Ok(())
     }
 
     /// Call this function as soon as your AEGP is done accessing the frame.
@@ -134,12 +203,13 @@ impl RenderSuite {
     pub fn render_new_item_sound_data<F: FnMut() -> bool>(&self, item: impl AsPtr<AEGP_ItemH>, start_time: Time, duration: Time, sound_format: &AEGP_SoundDataFormat, cancel_function: Option<F>) -> Result<aegp::SoundDataHandle, Error> {
         unsafe extern "C" fn cancel_fn(refcon: *mut std::ffi::c_void, cancel: *mut ae_sys::A_Boolean) -> A_Err {
             unsafe {
-                let cb = Box::<Box<dyn Fn() -> bool + Send + Sync + 'static>>::from_raw(refcon as *mut _);
+                // Called repeatedly during the render, the closure is owned by the calling function.
+                let cb = &mut *(refcon as *mut Box<dyn FnMut() -> bool>);
                 *cancel = cb() as _;
             }
             ae_sys::PF_Err_NONE as ae_sys::PF_Err
         }
-        let cancel_function = cancel_function.map(|x| Box::new(Box::new(x)));
+        let mut cancel_function = cancel_function.map(|x| Box::new(x) as Box<dyn FnMut() -> bool + '_>);
 
         Ok(aegp::SoundDataHandle::from_raw(
             call_suite_fn_single!(self,
@@ -149,7 +219,7 @@ impl RenderSuite {
                 &duration.into(),
                 sound_format,
                 if cancel_function.is_some() { Some(cancel_fn) } else { None },
-                cancel_function.map_or(std::ptr::null_mut(), |f| Box::into_raw(f) as *mut _)
+                cancel_function.as_mut().map_or(std::ptr::null_mut(), |f| f as *mut _ as *mut _)
             )?
         ))
     }
@@ -186,3 +256,26 @@ impl RenderSuite {
         Ok(call_suite_fn_single!(self, AEGP_GetReceiptGuid -> AEGP_MemHandle, receipt.as_ptr())?)
     }
 }
+
+// ――――――――――――――――――――――――――――――――――――――― Types ――――――――――――――――――――――――――――――――――――――――
+
+define_owned_handle_wrapper!(FrameReceipt, AEGP_FrameReceiptH);
+impl Drop for FrameReceipt {
+    fn drop(&mut self) {
+        if self.is_owned() && let Ok(suite) = RenderSuite::new() {
+            let _ = suite.checkin_frame(self.as_ptr());
+        }
+    }
+}
+
+impl FrameReceipt {
+    /// Retrieves the pixels associated with this receipt. The world is only valid as long as the receipt is alive.
+    pub fn world(&self) -> Result<aegp::WorldHandle, Error> {
+        RenderSuite::new()?.receipt_world(self.as_ptr())
+    }
+
+    /// Retrieves the region of the world that has already been rendered.
+    pub fn rendered_region(&self) -> Result<Rect, Error> {
+        RenderSuite::new()?.rendered_region(self.as_ptr())
+    }
+}
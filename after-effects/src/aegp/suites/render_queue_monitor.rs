@@ -0,0 +1,311 @@
+use crate::*;
+use crate::aegp::*;
+use ae_sys::{ AEGP_MemHandle, AEGP_RQM_BasicData, AEGP_RQM_FrameId, AEGP_RQM_ItemId, AEGP_RQM_SessionId };
+use std::collections::HashMap;
+use std::time::{ Duration, Instant };
+
+define_suite!(
+    /// Lets an AEGP follow renders from the render queue, e.g. to report progress to a render farm dashboard.
+    ///
+    /// Register a [`RenderQueueListener`] with [`RenderQueueMonitor::register()`] to get notified when jobs, items and frames start and finish.
+    /// The other functions query details about the job currently rendering, using the ids passed to the listener.
+    RenderQueueMonitorSuite,
+    AEGP_RenderQueueMonitorSuite1,
+    kAEGPRenderQueueMonitorSuite,
+    kAEGPRenderQueueMonitorSuiteVersion1
+);
+
+impl RenderQueueMonitorSuite {
+    /// Acquire this suite from the host. Returns error if the suite is not available.
+    /// Suite is released on drop.
+    pub fn new() -> Result<Self, Error> {
+        crate::Suite::new()
+    }
+
+    /// Registers a set of callbacks. `refcon` is passed back to every callback in `AEGP_RQM_BasicData`.
+    ///
+    /// Prefer [`RenderQueueMonitor::register()`] which wraps the callbacks in a [`RenderQueueListener`].
+    pub fn register_listener(&self, plugin_id: PluginId, refcon: ae_sys::AEGP_RQM_Refcon, function_block: &'static ae_sys::AEGP_RQM_FunctionBlock1) -> Result<(), Error> {
+        call_suite_fn!(self, AEGP_RegisterListener, plugin_id, refcon, function_block)
+    }
+
+    /// Deregisters the callbacks registered with `refcon`.
+    pub fn deregister_listener(&self, plugin_id: PluginId, refcon: ae_sys::AEGP_RQM_Refcon) -> Result<(), Error> {
+        call_suite_fn!(self, AEGP_DeregisterListener, plugin_id, refcon)
+    }
+
+    /// Retrieves the name of the project being rendered.
+    pub fn project_name(&self, session: RenderSessionId) -> Result<String, Error> {
+        utf16_string(call_suite_fn_single!(self, AEGP_GetProjectName -> AEGP_MemHandle, session)?)
+    }
+
+    /// Retrieves the version of the application doing the render.
+    pub fn app_version(&self, session: RenderSessionId) -> Result<String, Error> {
+        utf16_string(call_suite_fn_single!(self, AEGP_GetAppVersion -> AEGP_MemHandle, session)?)
+    }
+
+    /// Retrieves the number of items in the render job.
+    pub fn num_job_items(&self, session: RenderSessionId) -> Result<i32, Error> {
+        call_suite_fn_single!(self, AEGP_GetNumJobItems -> ae_sys::A_long, session)
+    }
+
+    /// Retrieves the id of the job item at `index`.
+    pub fn job_item_id(&self, session: RenderSessionId, index: i32) -> Result<RenderJobItemId, Error> {
+        call_suite_fn_single!(self, AEGP_GetJobItemID -> AEGP_RQM_ItemId, session, index)
+    }
+
+    /// Retrieves the render settings of a job item as name/value pairs.
+    pub fn job_item_render_settings(&self, session: RenderSessionId, item: RenderJobItemId) -> Result<Vec<(String, String)>, Error> {
+        let count = call_suite_fn_single!(self, AEGP_GetNumJobItemRenderSettings -> ae_sys::A_long, session, item)?;
+        (0..count).map(|i| {
+            let (name, value) = call_suite_fn_double!(self, AEGP_GetJobItemRenderSetting -> AEGP_MemHandle, AEGP_MemHandle, session, item, i)?;
+            Ok((utf16_string(name)?, utf16_string(value)?))
+        }).collect()
+    }
+
+    /// Retrieves the number of output modules of a job item.
+    pub fn num_job_item_output_modules(&self, session: RenderSessionId, item: RenderJobItemId) -> Result<i32, Error> {
+        call_suite_fn_single!(self, AEGP_GetNumJobItemOutputModules -> ae_sys::A_long, session, item)
+    }
+
+    /// Retrieves the settings of an output module as name/value pairs.
+    pub fn job_item_output_module_settings(&self, session: RenderSessionId, item: RenderJobItemId, output_module: i32) -> Result<Vec<(String, String)>, Error> {
+        let count = call_suite_fn_single!(self, AEGP_GetNumJobItemOutputModuleSettings -> ae_sys::A_long, session, item, output_module)?;
+        (0..count).map(|i| {
+            let (name, value) = call_suite_fn_double!(self, AEGP_GetJobItemOutputModuleSetting -> AEGP_MemHandle, AEGP_MemHandle, session, item, output_module, i)?;
+            Ok((utf16_string(name)?, utf16_string(value)?))
+        }).collect()
+    }
+
+    /// Retrieves the warnings of an output module.
+    pub fn job_item_output_module_warnings(&self, session: RenderSessionId, item: RenderJobItemId, output_module: i32) -> Result<Vec<String>, Error> {
+        let count = call_suite_fn_single!(self, AEGP_GetNumJobItemOutputModuleWarnings -> ae_sys::A_long, session, item, output_module)?;
+        (0..count).map(|i| {
+            utf16_string(call_suite_fn_single!(self, AEGP_GetJobItemOutputModuleWarning -> AEGP_MemHandle, session, item, output_module, i)?)
+        }).collect()
+    }
+
+    /// Retrieves the properties of a rendered frame as name/value pairs.
+    pub fn job_item_frame_properties(&self, session: RenderSessionId, item: RenderJobItemId, frame: RenderJobFrameId) -> Result<Vec<(String, String)>, Error> {
+        let count = call_suite_fn_single!(self, AEGP_GetNumJobItemFrameProperties -> ae_sys::A_long, session, item, frame)?;
+        (0..count).map(|i| {
+            let (name, value) = call_suite_fn_double!(self, AEGP_GetJobItemFrameProperty -> AEGP_MemHandle, AEGP_MemHandle, session, item, frame, i)?;
+            Ok((utf16_string(name)?, utf16_string(value)?))
+        }).collect()
+    }
+
+    /// Retrieves the properties of an output module as name/value pairs.
+    pub fn job_item_output_module_properties(&self, session: RenderSessionId, item: RenderJobItemId, output_module: i32) -> Result<Vec<(String, String)>, Error> {
+        let count = call_suite_fn_single!(self, AEGP_GetNumJobItemOutputModuleProperties -> ae_sys::A_long, session, item, output_module)?;
+        (0..count).map(|i| {
+            let (name, value) = call_suite_fn_double!(self, AEGP_GetJobItemOutputModuleProperty -> AEGP_MemHandle, AEGP_MemHandle, session, item, output_module, i)?;
+            Ok((utf16_string(name)?, utf16_string(value)?))
+        }).collect()
+    }
+
+    /// Retrieves a JPEG encoded thumbnail of a rendered frame, fitting into `max_width` x `max_height`.
+    pub fn job_item_frame_thumbnail(&self, session: RenderSessionId, item: RenderJobItemId, frame: RenderJobFrameId, max_width: i32, max_height: i32) -> Result<FrameThumbnail, Error> {
+        let mut width = max_width;
+        let mut height = max_height;
+        let handle = call_suite_fn_single!(self, AEGP_GetJobItemFrameThumbnail -> AEGP_MemHandle, session, item, frame, &mut width, &mut height)?;
+        let jpeg = if handle.is_null() {
+            Vec::new()
+        } else {
+            MemHandle::<u8>::from_raw(handle)?.to_bytes()?
+        };
+        Ok(FrameThumbnail { width, height, jpeg })
+    }
+}
+
+// ――――――――――――――――――――――――――――――――――――――― Types ――――――――――――――――――――――――――――――――――――――――
+
+/// Identifies a render job, i.e. one press of the render button.
+pub type RenderSessionId = AEGP_RQM_SessionId;
+/// Identifies an item in a render job.
+pub type RenderJobItemId = AEGP_RQM_ItemId;
+/// Identifies a frame of a job item.
+pub type RenderJobFrameId = AEGP_RQM_FrameId;
+
+define_enum! {
+    ae_sys::AEGP_RQM_FinishedStatus,
+    RenderFinishedStatus {
+        Unknown   = ae_sys::AEGP_RQM_FinishedStatus_AEGP_RQM_FinishedStatus_UNKNOWN,
+        Succeeded = ae_sys::AEGP_RQM_FinishedStatus_AEGP_RQM_FinishedStatus_SUCCEEDED,
+        Aborted   = ae_sys::AEGP_RQM_FinishedStatus_AEGP_RQM_FinishedStatus_ABORTED,
+        Erred     = ae_sys::AEGP_RQM_FinishedStatus_AEGP_RQM_FinishedStatus_ERRED,
+    }
+}
+
+/// A JPEG encoded frame thumbnail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameThumbnail {
+    pub width: i32,
+    pub height: i32,
+    pub jpeg: Vec<u8>,
+}
+
+/// A frame finished rendering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameFinished {
+    pub frame: RenderJobFrameId,
+    /// Number of frames finished so far for this item, including this one.
+    pub frames_done: u32,
+    /// Wall clock time since the previous frame finished, or since the item started for the first frame.
+    pub render_time: Duration,
+}
+
+/// Callbacks for renders from the render queue, registered with [`RenderQueueMonitor::register()`].
+///
+/// All functions are called on the main thread and have empty default implementations.
+/// Use [`RenderQueueMonitorSuite`] to query more details about the job, e.g. thumbnails of finished frames.
+pub trait RenderQueueListener: 'static {
+    fn job_started(&mut self, _session: RenderSessionId) -> Result<(), Error> { Ok(()) }
+    fn job_ended(&mut self, _session: RenderSessionId) -> Result<(), Error> { Ok(()) }
+    fn item_started(&mut self, _session: RenderSessionId, _item: RenderJobItemId) -> Result<(), Error> { Ok(()) }
+    fn frame_finished(&mut self, _session: RenderSessionId, _item: RenderJobItemId, _frame: FrameFinished) -> Result<(), Error> { Ok(()) }
+    fn item_ended(&mut self, _session: RenderSessionId, _item: RenderJobItemId, _status: RenderFinishedStatus) -> Result<(), Error> { Ok(()) }
+    fn log(&mut self, _session: RenderSessionId, _item: RenderJobItemId, _is_error: bool, _message: &str) -> Result<(), Error> { Ok(()) }
+}
+
+struct ItemProgress {
+    last_frame: Instant,
+    frames_done: u32,
+}
+
+struct ListenerState {
+    listener: Box<dyn RenderQueueListener>,
+    items: HashMap<(RenderSessionId, RenderJobItemId), ItemProgress>,
+}
+
+/// A registered [`RenderQueueListener`]. The listener is deregistered on drop.
+///
+/// Example usage:
+/// ```ignore
+/// struct Dashboard;
+/// impl RenderQueueListener for Dashboard {
+///     fn frame_finished(&mut self, session: RenderSessionId, item: RenderJobItemId, frame: FrameFinished) -> Result<(), Error> {
+///         log::info!("Frame {} took {:?}", frame.frames_done, frame.render_time);
+///         Ok(())
+///     }
+/// }
+/// let monitor = RenderQueueMonitor::register(plugin_id, Dashboard)?;
+/// ```
+pub struct RenderQueueMonitor {
+    plugin_id: PluginId,
+    state: *mut ListenerState,
+}
+
+impl RenderQueueMonitor {
+    pub fn register(plugin_id: PluginId, listener: impl RenderQueueListener) -> Result<Self, Error> {
+        let state = Box::into_raw(Box::new(ListenerState {
+            listener: Box::new(listener),
+            items: HashMap::new(),
+        }));
+        if let Err(e) = RenderQueueMonitorSuite::new()?.register_listener(plugin_id, state as _, &FUNCTION_BLOCK) {
+            drop(unsafe { Box::from_raw(state) });
+            return Err(e);
+        }
+        Ok(Self { plugin_id, state })
+    }
+}
+
+impl Drop for RenderQueueMonitor {
+    fn drop(&mut self) {
+        if let Ok(suite) = RenderQueueMonitorSuite::new() {
+            let _ = suite.deregister_listener(self.plugin_id, self.state as _);
+        }
+        drop(unsafe { Box::from_raw(self.state) });
+    }
+}
+
+static FUNCTION_BLOCK: ae_sys::AEGP_RQM_FunctionBlock1 = ae_sys::AEGP_RQM_FunctionBlock1 {
+    AEGP_RQM_RenderJobStarted: Some(job_started),
+    AEGP_RQM_RenderJobEnded: Some(job_ended),
+    AEGP_RQM_RenderJobItemStarted: Some(item_started),
+    AEGP_RQM_RenderJobItemUpdated: Some(item_updated),
+    AEGP_RQM_RenderJobItemEnded: Some(item_ended),
+    AEGP_RQM_RenderJobItemReportLog: Some(item_report_log),
+};
+
+fn with_listener(basic_data: *mut AEGP_RQM_BasicData, f: impl FnOnce(&mut ListenerState) -> Result<(), Error>) -> ae_sys::A_Err {
+    let Some(basic_data) = (unsafe { basic_data.as_ref() }) else {
+        return Error::BadCallbackParameter.into();
+    };
+    let Some(state) = (unsafe { (basic_data.aegp_refconPV as *mut ListenerState).as_mut() }) else {
+        return Error::BadCallbackParameter.into();
+    };
+    let _pica = crate::PicaBasicSuite::from_sp_basic_suite_raw(basic_data.pica_basicP);
+    match crate::catch_ffi_panic("RenderQueueListener", || f(state)) {
+        Some(Ok(())) => Error::None.into(),
+        Some(Err(e)) => e.into(),
+        None => Error::Generic.into(),
+    }
+}
+
+unsafe extern "C" fn job_started(basic_data: *mut AEGP_RQM_BasicData, session: RenderSessionId) -> ae_sys::A_Err {
+    with_listener(basic_data, |state| state.listener.job_started(session))
+}
+
+unsafe extern "C" fn job_ended(basic_data: *mut AEGP_RQM_BasicData, session: RenderSessionId) -> ae_sys::A_Err {
+    with_listener(basic_data, |state| {
+        state.items.retain(|(s, _), _| *s != session);
+        state.listener.job_ended(session)
+    })
+}
+
+unsafe extern "C" fn item_started(basic_data: *mut AEGP_RQM_BasicData, session: RenderSessionId, item: RenderJobItemId) -> ae_sys::A_Err {
+    with_listener(basic_data, |state| {
+        state.items.insert((session, item), ItemProgress { last_frame: Instant::now(), frames_done: 0 });
+        state.listener.item_started(session, item)
+    })
+}
+
+unsafe extern "C" fn item_updated(basic_data: *mut AEGP_RQM_BasicData, session: RenderSessionId, item: RenderJobItemId, frame: RenderJobFrameId) -> ae_sys::A_Err {
+    with_listener(basic_data, |state| {
+        let now = Instant::now();
+        let progress = state.items.entry((session, item)).or_insert(ItemProgress { last_frame: now, frames_done: 0 });
+        progress.frames_done += 1;
+        let finished = FrameFinished {
+            frame,
+            frames_done: progress.frames_done,
+            render_time: now - progress.last_frame,
+        };
+        progress.last_frame = now;
+        state.listener.frame_finished(session, item, finished)
+    })
+}
+
+unsafe extern "C" fn item_ended(basic_data: *mut AEGP_RQM_BasicData, session: RenderSessionId, item: RenderJobItemId, status: ae_sys::AEGP_RQM_FinishedStatus) -> ae_sys::A_Err {
+    with_listener(basic_data, |state| {
+        state.items.remove(&(session, item));
+        state.listener.item_ended(session, item, status.into())
+    })
+}
+
+unsafe extern "C" fn item_report_log(basic_data: *mut AEGP_RQM_BasicData, session: RenderSessionId, item: RenderJobItemId, is_error: ae_sys::A_Boolean, log: AEGP_MemHandle) -> ae_sys::A_Err {
+    with_listener(basic_data, |state| {
+        // The log buffer is owned by After Effects, so only lock it.
+        let message = if log.is_null() {
+            String::new()
+        } else {
+            let memory = aegp::suites::Memory::new()?;
+            let ptr = memory.lock_mem_handle(log)? as *const u16;
+            let message = unsafe { widestring::U16CStr::from_ptr_str(ptr) }.to_string_lossy();
+            memory.unlock_mem_handle(log)?;
+            message
+        };
+        state.listener.log(session, item, is_error != 0, &message)
+    })
+}
+
+/// Reads a null terminated UTF-16 string from a mem handle and disposes it.
+fn utf16_string(handle: AEGP_MemHandle) -> Result<String, Error> {
+    if handle.is_null() {
+        return Ok(String::new());
+    }
+    Ok(unsafe {
+        widestring::U16CString::from_ptr_str(
+            MemHandle::<u16>::from_raw(handle)?.lock()?.as_ptr(),
+        ).to_string_lossy()
+    })
+}
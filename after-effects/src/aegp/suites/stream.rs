@@ -128,6 +128,13 @@ impl StreamSuite {
     /// Get value, at a time you specify, of stream. `value` must be disposed by the plug-in.
     /// The `time_mode` indicates whether the time is in compositions or layer time.
     pub fn new_stream_value(&self, stream_ref: impl AsPtr<AEGP_StreamRefH>, plugin_id: PluginId, time_mode: TimeMode, time: Time, sample_stream_pre_expression: bool) -> Result<StreamValue, Error> {
+        self.with_stream_value(stream_ref, plugin_id, time_mode, time, sample_stream_pre_expression, |value| value)
+    }
+
+    /// Like [`new_stream_value()`](Self::new_stream_value), but the value is only disposed after `f` returns.
+    ///
+    /// Use this for values holding handles owned by the stream value, like [`StreamValue::Mask`] or [`StreamValue::TextDocument`].
+    pub fn with_stream_value<R>(&self, stream_ref: impl AsPtr<AEGP_StreamRefH>, plugin_id: PluginId, time_mode: TimeMode, time: Time, sample_stream_pre_expression: bool, f: impl FnOnce(StreamValue) -> R) -> Result<R, Error> {
         let type_ = self.stream_type(stream_ref.as_ptr())?;
 
         let mut stream_value2 = call_suite_fn_single!(self,
@@ -138,7 +145,7 @@ impl StreamSuite {
             &time.into() as *const _,
             sample_stream_pre_expression as u8
         )?;
-        let value = StreamValue::from_sys(type_, stream_value2.val);
+        let value = f(StreamValue::from_sys(type_, stream_value2.val));
 
         self.dispose_stream_value(&mut stream_value2)?;
 
@@ -150,6 +157,29 @@ impl StreamSuite {
         call_suite_fn!(self, AEGP_DisposeStreamValue, stream_value)
     }
 
+    /// Gets the value of the stream at `time`, lets `f` edit the data behind its handles and sets it as the new value.
+    ///
+    /// Use this to change values like [`StreamValue::Mask`], which are edited in place through their own suites.
+    /// Only valid for streams without keyframes, use the [`KeyframeSuite`](aegp::suites::Keyframe) otherwise.
+    pub fn modify_stream_value(&self, stream_ref: impl AsPtr<AEGP_StreamRefH>, plugin_id: PluginId, time_mode: TimeMode, time: Time, f: impl FnOnce(StreamValue) -> Result<(), Error>) -> Result<(), Error> {
+        let type_ = self.stream_type(stream_ref.as_ptr())?;
+
+        let mut stream_value2 = call_suite_fn_single!(self,
+            AEGP_GetNewStreamValue -> ae_sys::AEGP_StreamValue2,
+            plugin_id,
+            stream_ref.as_ptr(),
+            time_mode.into(),
+            &time.into() as *const _,
+            false as u8
+        )?;
+        let result = f(StreamValue::from_sys(type_, stream_value2.val))
+            .and_then(|_| call_suite_fn!(self, AEGP_SetStreamValue, plugin_id, stream_ref.as_ptr(), &mut stream_value2));
+
+        self.dispose_stream_value(&mut stream_value2)?;
+
+        result
+    }
+
     /// NOTE: This convenience function is only valid for streams with primitive data types, and not for `StreamType::ArbBlock`, `StreamType::Marker` or `StreamType::MaskOutline`.
     /// For these and other complex types, use [`new_stream_value()`](Self::new_stream_value), described above.
     pub fn layer_stream_value(&self, layer_handle: impl AsPtr<AEGP_LayerH>, stream: LayerStream, time_mode: TimeMode, time: Time, pre_expression: bool) -> Result<StreamValue, Error> {
@@ -476,8 +506,8 @@ define_enum! {
         TwoD          = ae_sys::AEGP_StreamType_TwoD,
         OneD          = ae_sys::AEGP_StreamType_OneD,
         Color         = ae_sys::AEGP_StreamType_COLOR,
-        // ArbBlock      = ae_sys::AEGP_StreamType_ARB,
-        // Marker        = ae_sys::AEGP_StreamType_MARKER,
+        ArbBlock      = ae_sys::AEGP_StreamType_ARB,
+        Marker        = ae_sys::AEGP_StreamType_MARKER,
         LayerId       = ae_sys::AEGP_StreamType_LAYER_ID,
         MaskId        = ae_sys::AEGP_StreamType_MASK_ID,
         Mask          = ae_sys::AEGP_StreamType_MASK,
@@ -680,8 +710,10 @@ pub enum StreamValue {
         green: ae_sys::A_FpLong,
         blue: ae_sys::A_FpLong,
     },
-    // ArbBlock,     // FIXME
-    // Marker,       // FIXME
+    /// Arbitrary data, owned by the stream value it was read from.
+    ArbBlock(ae_sys::AEGP_ArbBlockVal),
+    /// Owned by the stream value it was read from.
+    Marker(ae_sys::AEGP_MarkerValP),
     LayerId(ae_sys::AEGP_LayerIDVal),
     MaskId(ae_sys::AEGP_MaskIDVal),
     Mask(MaskOutlineHandle),
@@ -732,8 +764,12 @@ impl StreamValue {
                     blue:  val.color.blueF,
                 }
             },
-            // StreamType::ArbBlock => unsafe {},
-            // StreamType::Marker => unsafe {},
+            StreamType::ArbBlock => unsafe {
+                Self::ArbBlock(val.arbH)
+            },
+            StreamType::Marker => unsafe {
+                Self::Marker(val.markerP)
+            },
             StreamType::LayerId => unsafe {
                 Self::LayerId(val.layer_id)
             },
@@ -783,8 +819,12 @@ impl StreamValue {
                     blueF:  *blue,
                 }
             },
-            // Self::ArbBlock => {},
-            // Self::Marker => {},
+            Self::ArbBlock(x) => AEGP_StreamVal2 {
+                arbH: *x
+            },
+            Self::Marker(x) => AEGP_StreamVal2 {
+                markerP: *x
+            },
             Self::LayerId(x) => AEGP_StreamVal2 {
                 layer_id: *x
             },
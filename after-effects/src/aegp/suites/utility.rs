@@ -9,6 +9,54 @@ impl Drop for ErrReportState {
         }
     }
 }
+
+/// Keeps an undo group open until dropped. Everything the plug-in changes in the meantime is undone as one step.
+///
+/// The group is also closed when returning early with `?` or while unwinding from a panic.
+///
+/// Example usage:
+/// ```ignore
+/// let _undo = UndoGroup::new("Add Nulls")?;
+/// for layer in layers {
+///     comp.create_null(&layer.name, None)?;
+/// }
+/// ```
+pub struct UndoGroup {
+    suite: UtilitySuite,
+}
+impl UndoGroup {
+    /// Starts an undo group. The `undo_name` will appear in the Edit menu.
+    pub fn new(undo_name: &str) -> Result<Self, Error> {
+        let suite = UtilitySuite::new()?;
+        suite.start_undo_group(undo_name)?;
+        Ok(Self { suite })
+    }
+}
+impl Drop for UndoGroup {
+    fn drop(&mut self) {
+        let _ = self.suite.end_undo_group();
+    }
+}
+
+/// Runs `f` inside an [`UndoGroup`] named `undo_name`.
+pub fn with_undo<R>(undo_name: &str, f: impl FnOnce() -> R) -> Result<R, Error> {
+    let _undo = UndoGroup::new(undo_name)?;
+    Ok(f())
+}
+
+/// Silences error dialogs until dropped, also when returning early with `?` or while unwinding from a panic.
+pub struct QuietErrors {
+    _state: ErrReportState,
+}
+impl QuietErrors {
+    /// If `report_quieted_errors` is `true`, the errors silenced in the meantime are reported when the guard is dropped.
+    pub fn new(report_quieted_errors: bool) -> Result<Self, Error> {
+        Ok(Self {
+            _state: UtilitySuite::new()?.start_quiet_errors(report_quieted_errors)?,
+        })
+    }
+}
+
 define_suite!(
     /// The Utility suite supplies error message handling, AEGP version checking and access to the undo stack.
     ///
@@ -164,6 +212,11 @@ impl UtilitySuite {
         call_suite_fn!(self, AEGP_CauseIdleRoutinesToBeCalled,)
     }
 
+    /// Raw `AEGP_CauseIdleRoutinesToBeCalled`, so it can be called from other threads without acquiring the suite there.
+    pub(crate) fn cause_idle_routines_fn(&self) -> Option<unsafe extern "C" fn() -> ae_sys::A_Err> {
+        unsafe { (*self.suite_ptr).AEGP_CauseIdleRoutinesToBeCalled }
+    }
+
     /// Returns whether After Effects is running without a user interface.
     pub fn suppress_interactive_ui(&self) -> Result<bool, Error> {
         Ok(call_suite_fn_single!(self, AEGP_GetSuppressInteractiveUI -> ae_sys::A_Boolean)? != 0)
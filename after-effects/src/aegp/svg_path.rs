@@ -0,0 +1,453 @@
+use std::f64::consts::{FRAC_PI_2, TAU};
+
+use crate::aegp::suites::{Mask as MaskSuite, Stream as StreamSuite};
+use crate::aegp::{BezierPath, BezierVertex, Layer, Mask, MaskMode, MaskOutline, MaskStream, PluginId, StreamValue, TimeMode};
+use crate::{Error, Time};
+
+/// Identity for the `transform` of [`Layer::create_masks_from_svg()`] and [`BezierPath::transform()`].
+pub const SVG_IDENTITY: [f64; 6] = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+/// Options for [`Layer::create_masks_from_svg()`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SvgMaskOptions {
+    /// Mode of the created masks, [`MaskMode::Add`] by default.
+    pub mode: MaskMode,
+    /// Mode of closed subpaths winding against the first subpath, which are holes in SVG's non-zero fill.
+    /// [`MaskMode::Subtract`] by default, `None` uses [`mode`](Self::mode) for all masks.
+    pub hole_mode: Option<MaskMode>,
+    /// Creates one mask per subpath, `true` by default.
+    ///
+    /// A mask holds a single contour, so otherwise all subpaths are joined into one mask with straight segments.
+    pub per_subpath: bool,
+}
+
+impl Default for SvgMaskOptions {
+    fn default() -> Self {
+        Self {
+            mode: MaskMode::Add,
+            hole_mode: Some(MaskMode::Subtract),
+            per_subpath: true,
+        }
+    }
+}
+
+impl Layer {
+    /// Creates masks from SVG path data, like the `d` attribute of a `<path>` element.
+    ///
+    /// `transform` maps the SVG coordinates to layer pixels. It uses SVG's `matrix(a b c d e f)` notation,
+    /// transforming `(x, y)` to `(a*x + c*y + e, b*x + d*y + f)`; pass [`SVG_IDENTITY`] to keep them as is.
+    ///
+    /// Returns the new masks in the order of the subpaths. Use [`with_undo()`](crate::aegp::with_undo) to create them in a single undo step.
+    pub fn create_masks_from_svg(&self, plugin_id: PluginId, svg_path_d: &str, transform: [f64; 6], options: SvgMaskOptions) -> Result<Vec<Mask>, Error> {
+        let mut paths = BezierPath::from_svg(svg_path_d)?;
+        paths.iter_mut().for_each(|path| path.transform(transform));
+        if !options.per_subpath && paths.len() > 1 {
+            paths = vec![BezierPath {
+                closed: paths.iter().any(|path| path.closed),
+                vertices: paths.into_iter().flat_map(|path| path.vertices).collect(),
+                feathers: Vec::new(),
+            }];
+        }
+        let outer_winding = paths.first().map_or(0.0, |path| path.signed_area().signum());
+
+        let mask_suite = MaskSuite::new()?;
+        let stream_suite = StreamSuite::new()?;
+        paths
+            .iter()
+            .map(|path| {
+                let (handle, _index) = mask_suite.create_new_mask(self.as_ptr())?;
+                let mask = Mask::from_handle(handle, true);
+                let is_hole = path.closed && path.signed_area().signum() != outer_winding;
+                mask.set_mode(match options.hole_mode {
+                    Some(hole_mode) if is_hole => hole_mode,
+                    _ => options.mode,
+                })?;
+                let stream = mask.stream(plugin_id, MaskStream::Outline)?;
+                stream_suite.modify_stream_value(stream.as_ptr(), plugin_id, TimeMode::LayerTime, Time { value: 0, scale: 1 }, |value| match value {
+                    StreamValue::Mask(outline) => path.write_to_outline(&MaskOutline::from_handle(outline, false)),
+                    _ => Err(Error::Parameter),
+                })?;
+                Ok(mask)
+            })
+            .collect()
+    }
+}
+
+impl BezierPath {
+    /// Parses SVG path data into one path per subpath.
+    ///
+    /// Supports all path commands (`M`, `L`, `H`, `V`, `C`, `S`, `Q`, `T`, `A`, `Z`), absolute and relative.
+    /// Quadratic curves and elliptical arcs are converted to cubic segments.
+    /// Subpaths ending in `Z` are closed, others are open.
+    pub fn from_svg(d: &str) -> Result<Vec<Self>, Error> {
+        let mut parser = Parser { bytes: d.as_bytes(), pos: 0 };
+        let mut builder = Builder::default();
+        let mut command = None;
+        // Control points to reflect for `S` and `T`.
+        let mut last_cubic_control = None;
+        let mut last_quad_control = None;
+
+        loop {
+            match parser.command() {
+                Some(c) => command = Some(c),
+                None if parser.at_end() => break,
+                // Numbers repeat the last command.
+                None => {}
+            }
+            let Some(c) = command else {
+                return Err(parser.error("expected a command"));
+            };
+            let relative = c.is_ascii_lowercase();
+            let origin = if relative { builder.point } else { [0.0, 0.0] };
+            let point = |parser: &mut Parser| -> Result<[f64; 2], Error> { Ok([origin[0] + parser.number()?, origin[1] + parser.number()?]) };
+            let (mut cubic_control, mut quad_control) = (None, None);
+
+            match c.to_ascii_uppercase() {
+                b'M' => {
+                    builder.move_to(point(&mut parser)?);
+                    // Subsequent pairs are implicit line-to commands.
+                    command = Some(if relative { b'l' } else { b'L' });
+                }
+                b'L' => builder.line_to(point(&mut parser)?),
+                b'H' => builder.line_to([origin[0] + parser.number()?, builder.point[1]]),
+                b'V' => builder.line_to([builder.point[0], origin[1] + parser.number()?]),
+                b'C' => {
+                    let (c1, c2, end) = (point(&mut parser)?, point(&mut parser)?, point(&mut parser)?);
+                    builder.cubic_to(c1, c2, end);
+                    cubic_control = Some(c2);
+                }
+                b'S' => {
+                    let c1 = reflect(last_cubic_control, builder.point);
+                    let (c2, end) = (point(&mut parser)?, point(&mut parser)?);
+                    builder.cubic_to(c1, c2, end);
+                    cubic_control = Some(c2);
+                }
+                b'Q' => {
+                    let (control, end) = (point(&mut parser)?, point(&mut parser)?);
+                    builder.quad_to(control, end);
+                    quad_control = Some(control);
+                }
+                b'T' => {
+                    let control = reflect(last_quad_control, builder.point);
+                    builder.quad_to(control, point(&mut parser)?);
+                    quad_control = Some(control);
+                }
+                b'A' => {
+                    let radii = [parser.number()?, parser.number()?];
+                    let rotation = parser.number()?;
+                    let (large_arc, sweep) = (parser.flag()?, parser.flag()?);
+                    builder.arc_to(radii, rotation, large_arc, sweep, point(&mut parser)?);
+                }
+                b'Z' => {
+                    builder.close();
+                    // `Z` takes no arguments, so it can't be repeated.
+                    command = None;
+                }
+                _ => return Err(parser.error("unknown command")),
+            }
+            last_cubic_control = cubic_control;
+            last_quad_control = quad_control;
+        }
+        builder.finish();
+        Ok(builder.paths)
+    }
+
+    /// Applies an affine transform in SVG's `matrix(a b c d e f)` notation to all vertices.
+    pub fn transform(&mut self, [a, b, c, d, e, f]: [f64; 6]) {
+        let linear = |[x, y]: [f64; 2]| [a * x + c * y, b * x + d * y];
+        for vertex in &mut self.vertices {
+            let [x, y] = linear(vertex.position);
+            vertex.position = [x + e, y + f];
+            vertex.tangent_in = linear(vertex.tangent_in);
+            vertex.tangent_out = linear(vertex.tangent_out);
+        }
+    }
+
+    /// Area enclosed by the path, positive if it winds clockwise on screen.
+    fn signed_area(&self) -> f64 {
+        let points = self.flatten(0.5);
+        let n = points.len();
+        (0..n)
+            .map(|i| {
+                let ([x0, y0], [x1, y1]) = (points[i], points[(i + 1) % n]);
+                x0 * y1 - x1 * y0
+            })
+            .sum::<f64>()
+            / 2.0
+    }
+}
+
+fn reflect(control: Option<[f64; 2]>, point: [f64; 2]) -> [f64; 2] {
+    control.map_or(point, |c| [2.0 * point[0] - c[0], 2.0 * point[1] - c[1]])
+}
+
+fn sub(a: [f64; 2], b: [f64; 2]) -> [f64; 2] {
+    [a[0] - b[0], a[1] - b[1]]
+}
+
+fn lerp(a: [f64; 2], b: [f64; 2], t: f64) -> [f64; 2] {
+    [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t]
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn skip_separators(&mut self) {
+        while self.bytes.get(self.pos).is_some_and(|&b| b.is_ascii_whitespace() || b == b',') {
+            self.pos += 1;
+        }
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.skip_separators();
+        self.pos >= self.bytes.len()
+    }
+
+    fn command(&mut self) -> Option<u8> {
+        self.skip_separators();
+        let c = *self.bytes.get(self.pos).filter(|b| b.is_ascii_alphabetic() && !matches!(b, b'e' | b'E'))?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    fn number(&mut self) -> Result<f64, Error> {
+        self.skip_separators();
+        let start = self.pos;
+        let digits = |parser: &mut Self| {
+            let from = parser.pos;
+            while parser.bytes.get(parser.pos).is_some_and(u8::is_ascii_digit) {
+                parser.pos += 1;
+            }
+            parser.pos > from
+        };
+        if matches!(self.bytes.get(self.pos), Some(b'+' | b'-')) {
+            self.pos += 1;
+        }
+        let mut has_digits = digits(self);
+        // A second `.` starts the next number, as in `0.5.5`.
+        if self.bytes.get(self.pos) == Some(&b'.') {
+            self.pos += 1;
+            has_digits |= digits(self);
+        }
+        if !has_digits {
+            self.pos = start;
+            return Err(self.error("expected a number"));
+        }
+        if matches!(self.bytes.get(self.pos), Some(b'e' | b'E')) {
+            let mantissa_end = self.pos;
+            self.pos += 1;
+            if matches!(self.bytes.get(self.pos), Some(b'+' | b'-')) {
+                self.pos += 1;
+            }
+            if !digits(self) {
+                self.pos = mantissa_end;
+            }
+        }
+        std::str::from_utf8(&self.bytes[start..self.pos])
+            .ok()
+            .and_then(|number| number.parse().ok())
+            .ok_or_else(|| self.error("invalid number"))
+    }
+
+    /// Arc flags may be written without separators, as in `a1 1 0 00 1 1`.
+    fn flag(&mut self) -> Result<bool, Error> {
+        self.skip_separators();
+        let flag = match self.bytes.get(self.pos) {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => return Err(self.error("expected an arc flag")),
+        };
+        self.pos += 1;
+        Ok(flag)
+    }
+
+    fn error(&self, message: &str) -> Error {
+        log::error!("Invalid SVG path data at byte {}: {message}", self.pos);
+        Error::Parameter
+    }
+}
+
+#[derive(Default)]
+struct Builder {
+    paths: Vec<BezierPath>,
+    current: Option<BezierPath>,
+    point: [f64; 2],
+    start: [f64; 2],
+}
+
+impl Builder {
+    fn move_to(&mut self, point: [f64; 2]) {
+        self.finish();
+        self.point = point;
+        self.start = point;
+    }
+
+    fn cubic_to(&mut self, c1: [f64; 2], c2: [f64; 2], end: [f64; 2]) {
+        let start = self.point;
+        let path = self.current.get_or_insert_with(|| BezierPath {
+            vertices: vec![BezierVertex { position: start, ..Default::default() }],
+            ..Default::default()
+        });
+        if let Some(last) = path.vertices.last_mut() {
+            last.tangent_out = sub(c1, last.position);
+        }
+        path.vertices.push(BezierVertex {
+            position: end,
+            tangent_in: sub(c2, end),
+            tangent_out: [0.0, 0.0],
+        });
+        self.point = end;
+    }
+
+    fn line_to(&mut self, end: [f64; 2]) {
+        self.cubic_to(self.point, end, end);
+    }
+
+    fn quad_to(&mut self, control: [f64; 2], end: [f64; 2]) {
+        let start = self.point;
+        self.cubic_to(lerp(start, control, 2.0 / 3.0), lerp(end, control, 2.0 / 3.0), end);
+    }
+
+    /// Endpoint to center parameterization from the SVG specification, approximated by one cubic per quarter turn.
+    fn arc_to(&mut self, [rx, ry]: [f64; 2], rotation: f64, large_arc: bool, sweep: bool, end: [f64; 2]) {
+        let start = self.point;
+        if start == end {
+            return;
+        }
+        let (mut rx, mut ry) = (rx.abs(), ry.abs());
+        if rx == 0.0 || ry == 0.0 {
+            return self.line_to(end);
+        }
+        let (sin, cos) = rotation.to_radians().sin_cos();
+        let (dx, dy) = ((start[0] - end[0]) / 2.0, (start[1] - end[1]) / 2.0);
+        let (x1, y1) = (cos * dx + sin * dy, -sin * dx + cos * dy);
+        // Scale up radii which are too small to reach the end point.
+        let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+        if lambda > 1.0 {
+            rx *= lambda.sqrt();
+            ry *= lambda.sqrt();
+        }
+        let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+        let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+        let mut coefficient = (numerator / denominator).max(0.0).sqrt();
+        if large_arc == sweep {
+            coefficient = -coefficient;
+        }
+        let (cx1, cy1) = (coefficient * rx * y1 / ry, -coefficient * ry * x1 / rx);
+        let center = [
+            cos * cx1 - sin * cy1 + (start[0] + end[0]) / 2.0,
+            sin * cx1 + cos * cy1 + (start[1] + end[1]) / 2.0,
+        ];
+
+        let angle = |[ux, uy]: [f64; 2], [vx, vy]: [f64; 2]| (ux * vy - uy * vx).atan2(ux * vx + uy * vy);
+        let u = [(x1 - cx1) / rx, (y1 - cy1) / ry];
+        let v = [(-x1 - cx1) / rx, (-y1 - cy1) / ry];
+        let theta = angle([1.0, 0.0], u);
+        let mut delta = angle(u, v);
+        if !sweep && delta > 0.0 {
+            delta -= TAU;
+        } else if sweep && delta < 0.0 {
+            delta += TAU;
+        }
+
+        let point_at = |t: f64| {
+            let (s, c) = t.sin_cos();
+            [center[0] + rx * c * cos - ry * s * sin, center[1] + rx * c * sin + ry * s * cos]
+        };
+        let derivative_at = |t: f64| {
+            let (s, c) = t.sin_cos();
+            [-rx * s * cos - ry * c * sin, -rx * s * sin + ry * c * cos]
+        };
+        let segments = (delta.abs() / FRAC_PI_2 - 1e-9).ceil().max(1.0) as usize;
+        let step = delta / segments as f64;
+        let k = 4.0 / 3.0 * (step / 4.0).tan();
+        for i in 0..segments {
+            let (t0, t1) = (theta + step * i as f64, theta + step * (i + 1) as f64);
+            let (p0, d0, d1) = (point_at(t0), derivative_at(t0), derivative_at(t1));
+            let p1 = if i + 1 == segments { end } else { point_at(t1) };
+            self.cubic_to([p0[0] + k * d0[0], p0[1] + k * d0[1]], [p1[0] - k * d1[0], p1[1] - k * d1[1]], p1);
+        }
+    }
+
+    fn close(&mut self) {
+        if let Some(mut path) = self.current.take() {
+            // Merge the closing vertex into the first one.
+            if path.vertices.len() > 1 {
+                let (first, last) = (path.vertices[0].position, path.vertices[path.vertices.len() - 1].position);
+                if (first[0] - last[0]).abs() < 1e-9 && (first[1] - last[1]).abs() < 1e-9 {
+                    let last = path.vertices.pop().unwrap();
+                    path.vertices[0].tangent_in = last.tangent_in;
+                }
+            }
+            path.closed = true;
+            self.paths.push(path);
+        }
+        self.point = self.start;
+    }
+
+    fn finish(&mut self) {
+        if let Some(path) = self.current.take() {
+            self.paths.push(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positions(path: &BezierPath) -> Vec<[f64; 2]> {
+        path.vertices.iter().map(|v| v.position).collect()
+    }
+
+    #[test]
+    fn parses_commands_and_compact_syntax() {
+        let paths = BezierPath::from_svg("M10,10h20v20H10z m5-5l.5.5-1e1,0 M0 0 1 1").unwrap();
+        assert_eq!(paths.len(), 3);
+        assert!(paths[0].closed);
+        assert_eq!(positions(&paths[0]), [[10.0, 10.0], [30.0, 10.0], [30.0, 30.0], [10.0, 30.0]]);
+        // Relative move after `Z` starts from the start of the closed subpath.
+        assert!(!paths[1].closed);
+        assert_eq!(positions(&paths[1]), [[15.0, 5.0], [15.5, 5.5], [5.5, 5.5]]);
+        assert_eq!(positions(&paths[2]), [[0.0, 0.0], [1.0, 1.0]]);
+
+        let curves = &BezierPath::from_svg("M0 0 C 10 0 20 10 20 20 S 30 40 40 40 Q 50 40 50 50").unwrap()[0];
+        assert_eq!(curves.vertices[0].tangent_out, [10.0, 0.0]);
+        assert_eq!(curves.vertices[1].tangent_in, [0.0, -10.0]);
+        // `S` reflects the previous control point.
+        assert_eq!(curves.vertices[1].tangent_out, [0.0, 10.0]);
+        // Quadratic control points are converted to cubic tangents.
+        let q = &curves.vertices[2..];
+        assert!((q[0].tangent_out[0] - 20.0 / 3.0).abs() < 1e-9 && q[0].tangent_out[1] == 0.0);
+        assert!(q[1].tangent_in[0] == 0.0 && (q[1].tangent_in[1] + 20.0 / 3.0).abs() < 1e-9);
+
+        assert!(BezierPath::from_svg("M0 0 L 1").is_err());
+        assert!(BezierPath::from_svg("10 10").is_err());
+        assert!(BezierPath::from_svg("M0 0 X 1 1").is_err());
+    }
+
+    #[test]
+    fn arcs_become_cubics_on_the_ellipse() {
+        // Two half circles with compact flags, then transformed.
+        let mut path = BezierPath::from_svg("M 0 10 a10 10 0 00 20 0 A 10 10 0 1 0 0 10 Z").unwrap().remove(0);
+        assert!(path.closed);
+        assert_eq!(path.vertices.len(), 4);
+        let on_circle = |path: &BezierPath, center: [f64; 2], radius: f64| {
+            path.flatten(0.01).iter().all(|p| ((p[0] - center[0]).hypot(p[1] - center[1]) - radius).abs() < 0.02)
+        };
+        assert!(on_circle(&path, [10.0, 10.0], 10.0));
+
+        path.transform([2.0, 0.0, 0.0, 2.0, 5.0, -5.0]);
+        assert!(on_circle(&path, [25.0, 15.0], 20.0));
+        // Winds counter-clockwise on screen, the area is approximated from a coarse polygon.
+        let area = std::f64::consts::PI * 400.0;
+        assert!((path.signed_area() + area).abs() < 0.05 * area);
+
+        // Radii too small to reach the end point are scaled up.
+        let half = BezierPath::from_svg("M0 0 A 1 1 0 0 1 10 0").unwrap().remove(0);
+        assert!(on_circle(&half, [5.0, 0.0], 5.0));
+    }
+}
@@ -1,3 +1,282 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use parking_lot::RwLock;
+
+thread_local! {
+    static IN_SEQUENCE_SETDOWN: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Marks the current thread as handling [`Command::SequenceSetdown`](crate::pf::Command::SequenceSetdown) until dropped.
+#[doc(hidden)]
+pub struct SequenceSetdownGuard(bool);
+
+impl SequenceSetdownGuard {
+    pub fn new(is_setdown: bool) -> Self {
+        Self(IN_SEQUENCE_SETDOWN.with(|flag| flag.replace(is_setdown)))
+    }
+}
+
+impl Drop for SequenceSetdownGuard {
+    fn drop(&mut self) {
+        IN_SEQUENCE_SETDOWN.with(|flag| flag.set(self.0));
+    }
+}
+
+/// Returns `true` while the sequence data of an effect instance is being destroyed in
+/// [`Command::SequenceSetdown`](crate::pf::Command::SequenceSetdown).
+pub fn in_sequence_setdown() -> bool {
+    IN_SEQUENCE_SETDOWN.with(|flag| flag.get())
+}
+
+/// Bounds for the instances of a cross-thread type which are no longer referenced by any sequence data.
+///
+/// Unreferenced instances are kept around, because After Effects frequently flattens sequence data
+/// and sets it up again. Once a limit is exceeded, the least recently used unreferenced instances are evicted.
+/// Evicted instances are re-hydrated from the flattened sequence data when After Effects sets it up again.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CrossThreadLimits {
+    /// Maximum number of unreferenced instances to keep.
+    pub max_unreferenced: Option<usize>,
+    /// Maximum total size of all instances in bytes, as returned by the size estimator.
+    /// Only unreferenced instances are evicted to satisfy it.
+    pub max_bytes: Option<usize>,
+}
+
+/// Statistics of a cross-thread type, see `CrossThreadYourType::stats()`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CrossThreadStats {
+    /// Number of instances currently in the map.
+    pub instances: usize,
+    /// Number of instances referenced by at least one sequence data.
+    pub referenced: usize,
+    /// Number of instances no longer referenced by any sequence data.
+    pub unreferenced: usize,
+    /// Estimated size of all instances in bytes.
+    pub bytes: usize,
+    /// Number of instances created from scratch.
+    pub created: u64,
+    /// Number of instances restored from flattened sequence data.
+    pub rehydrated: u64,
+    /// Number of instances evicted because of the [`CrossThreadLimits`].
+    pub evicted: u64,
+    /// Number of instances removed because their last sequence data was set down.
+    pub released: u64,
+}
+
+struct Entry<T> {
+    data: Arc<RwLock<T>>,
+    refs: usize,
+    size: usize,
+    last_used: AtomicU64,
+}
+
+/// Storage behind a type defined with [`define_cross_thread_type!`](crate::define_cross_thread_type).
+#[doc(hidden)]
+pub struct CrossThreadRegistry<T> {
+    entries: RwLock<HashMap<u64, Entry<T>>>,
+    limits: RwLock<CrossThreadLimits>,
+    size_estimator: RwLock<fn(&T) -> usize>,
+    clock: AtomicU64,
+    created: AtomicU64,
+    rehydrated: AtomicU64,
+    evicted: AtomicU64,
+    released: AtomicU64,
+}
+
+impl<T> Default for CrossThreadRegistry<T> {
+    fn default() -> Self {
+        Self {
+            entries: RwLock::new(HashMap::new()),
+            limits: RwLock::new(CrossThreadLimits::default()),
+            size_estimator: RwLock::new(|_| std::mem::size_of::<T>()),
+            clock: AtomicU64::new(0),
+            created: AtomicU64::new(0),
+            rehydrated: AtomicU64::new(0),
+            evicted: AtomicU64::new(0),
+            released: AtomicU64::new(0),
+        }
+    }
+}
+
+impl<T> CrossThreadRegistry<T> {
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn new_entry(&self, data: T) -> Entry<T> {
+        Entry {
+            size: (self.size_estimator.read())(&data),
+            data: Arc::new(RwLock::new(data)),
+            refs: 1,
+            last_used: AtomicU64::new(self.tick()),
+        }
+    }
+
+    /// Inserts a newly created instance.
+    pub fn create(&self, id: u64, data: T) {
+        let entry = self.new_entry(data);
+        self.entries.write().insert(id, entry);
+        self.created.fetch_add(1, Ordering::Relaxed);
+        self.enforce_limits();
+    }
+
+    /// Adds a reference to an existing instance. Returns `false` if the instance doesn't exist.
+    pub fn acquire(&self, id: u64) -> bool {
+        match self.entries.write().get_mut(&id) {
+            Some(entry) => {
+                entry.refs += 1;
+                entry.last_used.store(self.tick(), Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Adds a reference to an existing instance, or restores it from `data` if it doesn't exist (anymore).
+    pub fn acquire_or_rehydrate(&self, id: u64, data: T) {
+        let mut entries = self.entries.write();
+        if let Some(entry) = entries.get_mut(&id) {
+            entry.refs += 1;
+            entry.last_used.store(self.tick(), Ordering::Relaxed);
+            return;
+        }
+        entries.insert(id, self.new_entry(data));
+        drop(entries);
+        self.rehydrated.fetch_add(1, Ordering::Relaxed);
+        self.enforce_limits();
+    }
+
+    /// Drops a reference to an instance.
+    ///
+    /// If this was the last reference and the sequence data is being set down, the instance is removed,
+    /// otherwise it's kept as unreferenced, because the sequence data may be set up again from its flattened copy.
+    pub fn release(&self, id: u64) {
+        let mut entries = self.entries.write();
+        let Some(entry) = entries.get_mut(&id) else { return };
+        entry.refs = entry.refs.saturating_sub(1);
+        if entry.refs > 0 {
+            return;
+        }
+        if in_sequence_setdown() {
+            entries.remove(&id);
+            self.released.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        // The instance may have changed since it was inserted. Estimate its size without holding the map lock,
+        // a render thread may hold the instance lock for a whole render and call into the map itself.
+        // If the instance is locked right now, keep the previous estimate.
+        let data = entry.data.clone();
+        drop(entries);
+        let size = data.try_read().map(|data| (self.size_estimator.read())(&data));
+        if let Some(size) = size {
+            self.store_size(id, &data, size);
+        }
+        self.enforce_limits();
+    }
+
+    /// Stores the size estimated for `data`, unless the instance was removed or replaced in the meantime.
+    fn store_size(&self, id: u64, data: &Arc<RwLock<T>>, size: usize) {
+        if let Some(entry) = self.entries.write().get_mut(&id)
+            && Arc::ptr_eq(&entry.data, data)
+        {
+            entry.size = size;
+        }
+    }
+
+    pub fn get(&self, id: u64) -> Option<Arc<RwLock<T>>> {
+        let entries = self.entries.read();
+        let entry = entries.get(&id)?;
+        entry.last_used.store(self.tick(), Ordering::Relaxed);
+        Some(entry.data.clone())
+    }
+
+    pub fn contains(&self, id: u64) -> bool {
+        self.entries.read().contains_key(&id)
+    }
+
+    pub fn clear(&self) {
+        self.entries.write().clear();
+    }
+
+    pub fn set_limits(&self, limits: CrossThreadLimits) {
+        *self.limits.write() = limits;
+        self.enforce_limits();
+    }
+
+    pub fn limits(&self) -> CrossThreadLimits {
+        *self.limits.read()
+    }
+
+    pub fn set_size_estimator(&self, estimator: fn(&T) -> usize) {
+        *self.size_estimator.write() = estimator;
+        // Don't hold the map lock while waiting for the instance locks, see `release()`.
+        let instances: Vec<(u64, Arc<RwLock<T>>)> = self.entries.read().iter().map(|(id, entry)| (*id, entry.data.clone())).collect();
+        for (id, data) in instances {
+            let size = estimator(&data.read());
+            self.store_size(id, &data, size);
+        }
+        self.enforce_limits();
+    }
+
+    /// Removes all unreferenced instances. Returns the number of removed instances.
+    pub fn evict_unreferenced(&self) -> usize {
+        let mut entries = self.entries.write();
+        let before = entries.len();
+        entries.retain(|_, entry| entry.refs > 0);
+        let evicted = before - entries.len();
+        self.evicted.fetch_add(evicted as u64, Ordering::Relaxed);
+        evicted
+    }
+
+    pub fn stats(&self) -> CrossThreadStats {
+        let entries = self.entries.read();
+        let referenced = entries.values().filter(|entry| entry.refs > 0).count();
+        CrossThreadStats {
+            instances: entries.len(),
+            referenced,
+            unreferenced: entries.len() - referenced,
+            bytes: entries.values().map(|entry| entry.size).sum(),
+            created: self.created.load(Ordering::Relaxed),
+            rehydrated: self.rehydrated.load(Ordering::Relaxed),
+            evicted: self.evicted.load(Ordering::Relaxed),
+            released: self.released.load(Ordering::Relaxed),
+        }
+    }
+
+    fn enforce_limits(&self) {
+        let limits = *self.limits.read();
+        if limits.max_unreferenced.is_none() && limits.max_bytes.is_none() {
+            return;
+        }
+        let mut entries = self.entries.write();
+
+        let mut unreferenced: Vec<(u64, u64, usize)> = entries
+            .iter()
+            .filter(|(_, entry)| entry.refs == 0)
+            .map(|(id, entry)| (*id, entry.last_used.load(Ordering::Relaxed), entry.size))
+            .collect();
+        // Least recently used first.
+        unreferenced.sort_by_key(|(_, last_used, _)| *last_used);
+
+        let mut bytes: usize = entries.values().map(|entry| entry.size).sum();
+        let mut remaining = unreferenced.len();
+        for (id, _, size) in unreferenced {
+            let too_many = limits.max_unreferenced.is_some_and(|max| remaining > max);
+            let too_big = limits.max_bytes.is_some_and(|max| bytes > max);
+            if !too_many && !too_big {
+                break;
+            }
+            entries.remove(&id);
+            self.evicted.fetch_add(1, Ordering::Relaxed);
+            remaining -= 1;
+            bytes -= size;
+        }
+    }
+}
+
 /// Cross-thread type to persist the same object across different rendering threads when MFR (Multi frame rendering) is enabled
 ///
 /// By default, Adobe clones the `Instance` data object for every rendering thread.
@@ -27,6 +306,17 @@
 ///
 /// Serialization and deserialization is handled automatically, so you can just use serde's derive macros.
 ///
+/// Every `CrossThreadMyInstance` value counts as a reference to the shared instance. When the last sequence data
+/// referencing it is destroyed in `SequenceSetdown` (e.g. the effect is deleted), the instance is removed from the map.
+/// Instances which are only unreferenced because After Effects flattened the sequence data are kept, until they're
+/// evicted according to the limits set with `CrossThreadMyInstance::set_limits()`:
+/// ```ignore
+/// CrossThreadMyInstance::set_limits(ae::CrossThreadLimits { max_unreferenced: Some(16), max_bytes: Some(512 << 20) });
+/// CrossThreadMyInstance::set_size_estimator(|inst| inst.inner_data.len());
+/// ```
+/// Evicted instances are re-hydrated from the flattened data when After Effects sets up the sequence data again.
+/// Use `CrossThreadMyInstance::stats()` to inspect the map.
+///
 /// When you no longer need the instances, you can call `CrossThreadMyInstance::clear_map()` to clear the global static map.
 /// You can do it in `GlobalSetdown`.
 #[macro_export]
@@ -39,25 +329,42 @@ macro_rules! define_cross_thread_type {
             impl Default for [<CrossThread $type_name>] {
                 fn default() -> Self {
                     let id = $crate::fastrand::u64(..);
-                    let mut inst = <$type_name>::default();
-                    Self::map().write().insert(id, std::sync::Arc::new($crate::parking_lot::RwLock::new(inst)));
+                    Self::map().create(id, <$type_name>::default());
                     Self { id }
                 }
             }
+            impl Drop for [<CrossThread $type_name>] {
+                fn drop(&mut self) {
+                    Self::map().release(self.id);
+                }
+            }
             impl [<CrossThread $type_name>] {
-                fn map() -> &'static $crate::parking_lot::RwLock<std::collections::HashMap<u64, std::sync::Arc<$crate::parking_lot::RwLock<$type_name>>>> {
-                    use std::{ collections::HashMap, sync::{ Arc, OnceLock } };
-                    use $crate::parking_lot::RwLock;
-
-                    static MAP: OnceLock<RwLock<HashMap<u64, Arc<RwLock<$type_name>>>>> = OnceLock::new();
-                    MAP.get_or_init(|| RwLock::new(HashMap::new()))
+                fn map() -> &'static $crate::CrossThreadRegistry<$type_name> {
+                    static MAP: std::sync::OnceLock<$crate::CrossThreadRegistry<$type_name>> = std::sync::OnceLock::new();
+                    MAP.get_or_init(Default::default)
                 }
 
                 pub fn get(&self) -> Option<std::sync::Arc<$crate::parking_lot::RwLock<$type_name>>> {
-                    Some(Self::map().read().get(&self.id)?.clone())
+                    Self::map().get(self.id)
                 }
                 pub fn clear_map() {
-                    Self::map().write().clear();
+                    Self::map().clear();
+                }
+                /// Sets the bounds for instances no longer referenced by any sequence data.
+                pub fn set_limits(limits: $crate::CrossThreadLimits) {
+                    Self::map().set_limits(limits);
+                }
+                /// Sets the function estimating the size of an instance in bytes, used for [`CrossThreadLimits::max_bytes`]($crate::CrossThreadLimits::max_bytes).
+                /// Defaults to `size_of::<T>()`.
+                pub fn set_size_estimator(estimator: fn(&$type_name) -> usize) {
+                    Self::map().set_size_estimator(estimator);
+                }
+                /// Removes all instances no longer referenced by any sequence data.
+                pub fn evict_unreferenced() -> usize {
+                    Self::map().evict_unreferenced()
+                }
+                pub fn stats() -> $crate::CrossThreadStats {
+                    Self::map().stats()
                 }
             }
             impl $crate::serde::Serialize for [<CrossThread $type_name>] {
@@ -87,15 +394,14 @@ macro_rules! define_cross_thread_type {
 
                         fn visit_seq<V>(self, mut seq: V) -> Result<[<CrossThread $type_name>], V::Error> where V: $crate::serde::de::SeqAccess<'de> {
                             let id: u64 = seq.next_element()?.ok_or_else(|| serde::de::Error::invalid_length(0, &self))?;
-                            if [<CrossThread $type_name>]::map().read().contains_key(&id) {
+                            if [<CrossThread $type_name>]::map().acquire(id) {
                                 return Ok([<CrossThread $type_name>] { id });
                             }
 
                             let data: $type_name = seq.next_element()?.ok_or_else(|| $crate::serde::de::Error::invalid_length(1, &self))?;
 
-                            // Use write lock and re-check to avoid race condition
-                            let mut map = [<CrossThread $type_name>]::map().write();
-                            map.entry(id).or_insert_with(|| std::sync::Arc::new($crate::parking_lot::RwLock::new(data)));
+                            // The instance may have been inserted by another thread in the meantime
+                            [<CrossThread $type_name>]::map().acquire_or_rehydrate(id, data);
 
                             Ok([<CrossThread $type_name>] { id })
                         }
@@ -107,7 +413,7 @@ macro_rules! define_cross_thread_type {
                                 match key {
                                     Field::Id => {
                                         let _id = map.next_value()?;
-                                        if [<CrossThread $type_name>]::map().read().contains_key(&_id) {
+                                        if [<CrossThread $type_name>]::map().acquire(_id) {
                                             return Ok([<CrossThread $type_name>] { id: _id });
                                         }
                                         id = Some(_id);
@@ -121,9 +427,8 @@ macro_rules! define_cross_thread_type {
 
                             let data: $type_name = data.ok_or_else(|| $crate::serde::de::Error::missing_field("data"))?;
 
-                            // Use write lock and entry API to avoid race condition
-                            let mut write_map = [<CrossThread $type_name>]::map().write();
-                            write_map.entry(id).or_insert_with(|| std::sync::Arc::new($crate::parking_lot::RwLock::new(data)));
+                            // The instance may have been inserted by another thread in the meantime
+                            [<CrossThread $type_name>]::map().acquire_or_rehydrate(id, data);
 
                             Ok([<CrossThread $type_name>] { id })
                         }
@@ -135,3 +440,106 @@ macro_rules! define_cross_thread_type {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn setdown_of_last_reference_removes_instance() {
+        let registry = CrossThreadRegistry::<u32>::default();
+        registry.create(1, 10);
+        assert!(registry.acquire(1));
+
+        let _guard = SequenceSetdownGuard::new(true);
+        registry.release(1);
+        assert!(registry.contains(1));
+        registry.release(1);
+        assert!(!registry.contains(1));
+        assert_eq!(registry.stats().released, 1);
+    }
+
+    #[test]
+    fn flattened_instances_are_kept_and_rehydrated() {
+        let registry = CrossThreadRegistry::<u32>::default();
+        registry.create(1, 10);
+        registry.release(1);
+        assert_eq!(registry.stats().unreferenced, 1);
+
+        // Still in the map, the flattened copy is ignored.
+        registry.acquire_or_rehydrate(1, 0);
+        assert_eq!(*registry.get(1).unwrap().read(), 10);
+
+        registry.release(1);
+        assert_eq!(registry.evict_unreferenced(), 1);
+        registry.acquire_or_rehydrate(1, 10);
+        assert_eq!(registry.stats().rehydrated, 1);
+    }
+
+    #[test]
+    fn limits_evict_least_recently_used_unreferenced_instances() {
+        let registry = CrossThreadRegistry::<u32>::default();
+        for id in 0..4 {
+            registry.create(id, 0);
+        }
+        registry.release(0);
+        registry.release(1);
+        registry.release(2);
+        registry.get(0);
+
+        registry.set_limits(CrossThreadLimits { max_unreferenced: Some(1), max_bytes: None });
+        assert!(registry.contains(0));
+        assert!(!registry.contains(1));
+        assert!(!registry.contains(2));
+        assert!(registry.contains(3));
+
+        registry.set_limits(CrossThreadLimits { max_unreferenced: None, max_bytes: Some(0) });
+        let stats = registry.stats();
+        assert_eq!((stats.instances, stats.referenced, stats.evicted), (1, 1, 3));
+    }
+
+    #[test]
+    fn release_does_not_wait_for_locked_instances() {
+        use std::sync::{Barrier, mpsc};
+        use std::time::Duration;
+
+        let registry = Arc::new(CrossThreadRegistry::<u32>::default());
+        registry.create(1, 10);
+        registry.create(2, 20);
+        registry.set_size_estimator(|value| *value as usize);
+
+        // A render thread holds the instance for the whole render and reads another instance from the map meanwhile.
+        let locked = Arc::new(Barrier::new(2));
+        let released = Arc::new(Barrier::new(2));
+        let render = std::thread::spawn({
+            let (registry, locked, released) = (registry.clone(), locked.clone(), released.clone());
+            move || {
+                let instance = registry.get(1).unwrap();
+                let mut guard = instance.write();
+                locked.wait();
+                released.wait();
+                *guard = 30;
+                *registry.get(2).unwrap().read()
+            }
+        });
+        locked.wait();
+
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn({
+            let registry = registry.clone();
+            move || {
+                registry.release(1);
+                tx.send(()).unwrap();
+            }
+        });
+        rx.recv_timeout(Duration::from_secs(5)).expect("release() blocked on a locked instance");
+        released.wait();
+        assert_eq!(render.join().unwrap(), 20);
+
+        // The locked instance kept its previous size, and is estimated again on the next release.
+        assert_eq!(registry.stats().bytes, 30);
+        registry.acquire(1);
+        registry.release(1);
+        assert_eq!(registry.stats().bytes, 50);
+    }
+}
@@ -40,6 +40,7 @@ pub use plugin_base::{AegpPlugin, AegpSeal};
 
 #[macro_use]
 mod cross_thread_type;
+pub use cross_thread_type::{CrossThreadLimits, CrossThreadRegistry, CrossThreadStats, SequenceSetdownGuard, in_sequence_setdown};
 
 pub mod aegp;
 pub mod aeio;
@@ -78,6 +79,18 @@ pub(crate) fn borrow_pica_basic_as_ptr() -> *const ae_sys::SPBasicSuite {
     pica_basic_ptr
 }
 
+/// Runs `f` called from the host, which must never be unwound into as that would abort.
+/// A panic is logged as coming from `what`, and `None` is returned.
+pub(crate) fn catch_ffi_panic<R>(what: &str, f: impl FnOnce() -> R) -> Option<R> {
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)) {
+        Ok(result) => Some(result),
+        Err(_) => {
+            log::error!("Panic in {what}");
+            None
+        }
+    }
+}
+
 /// This lets us access a thread-local version of the `PicaBasic`
 /// suite. Whenever we generate a new `SPBasic_Suite` from Ae somehow,
 /// we create a PicaBasicSuite::new() from that and use that to initialize
@@ -145,7 +158,7 @@ impl Drop for PicaBasicSuite {
 }
 
 // Error 10007 was Seen in the wild in AE2019
-#[cfg(target_os = "macos")]
+#[cfg(any(target_os = "macos", target_os = "linux"))]
 const UNKNOWN_ERR_10007: ::std::os::raw::c_uint = 10007;
 #[cfg(target_os = "windows")]
 const UNKNOWN_ERR_10007: ::std::os::raw::c_int = 10007;
@@ -308,11 +321,9 @@ impl From<uv::DMat4> for Matrix4 {
 
 #[cfg(feature = "ultraviolet")]
 #[test]
-fn test_from() {
-    let m = Matrix4 {
-        0: [[0.; 4], [0.; 4], [0.; 4], [0.; 4]],
-    };
-    let _matrix = uv::mat4D::from(m);
+fn test_from_ultraviolet() {
+    let m = Matrix4([[0.; 4], [0.; 4], [0.; 4], [0.; 4]]);
+    let _matrix = uv::DMat4::from(m);
 }
 
 #[cfg(feature = "nalgebra")]
@@ -323,12 +334,19 @@ impl From<Matrix4> for nalgebra::Matrix4<f64> {
     }
 }
 
+#[cfg(feature = "nalgebra")]
+impl From<nalgebra::Matrix4<f64>> for Matrix4 {
+    #[inline]
+    fn from(m: nalgebra::Matrix4<f64>) -> Self {
+        // nalgebra arrays are column-major
+        Self(m.transpose().into())
+    }
+}
+
 #[cfg(feature = "nalgebra")]
 #[test]
-fn test_from() {
-    let m = Matrix4 {
-        0: [[0.; 4], [0.; 4], [0.; 4], [0.; 4]],
-    };
+fn test_from_nalgebra() {
+    let m = Matrix4([[0.; 4], [0.; 4], [0.; 4], [0.; 4]]);
     let _matrix = nalgebra::Matrix4::<f64>::from(m);
 }
 
@@ -89,6 +89,8 @@ pub use suites::pixel_format::PixelFormat;
 pub use suites::path::{
     MaskMode,
     PathOutline,
+    PathSample,
+    PathSampler,
     PathSegPrep,
 };
 
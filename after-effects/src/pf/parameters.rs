@@ -1071,6 +1071,8 @@ impl<'p> ParamDef<'p> {
         // According to Adobe docs, the encoding expected for the name is the system encoding.
         // Reference: https://ae-plugins.docsforadobe.dev/intro/localization/
         let mut bytes = {
+            #[cfg(target_os = "linux")]
+            { name.as_bytes().to_vec() }
             #[cfg(target_os = "macos")]
             unsafe {
                 let cfstr = CFString::from_str(name);
@@ -226,6 +226,48 @@ impl PathOutline {
         })
     }
 
+    /// Prepares all segments for sampling by arc length. `frequency` is passed to
+    /// [`prepare_seg_length()`](Self::prepare_seg_length); our internal effects use 100.
+    pub fn sampler(&self, frequency: i32) -> Result<PathSampler<'_>, Error> {
+        let mut segments = (0..self.num_segments()?)
+            .map(|segment| self.prepare_seg_length(segment, frequency))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let mut total = 0.0;
+        let ends = segments
+            .iter_mut()
+            .map(|segment| {
+                total += segment.length()?;
+                Ok(total)
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(PathSampler {
+            segments,
+            ends,
+            closed: !self.is_open()?,
+        })
+    }
+
+    /// Copies the vertices into a [`BezierPath`](aegp::BezierPath).
+    pub fn to_bezier_path(&self) -> Result<aegp::BezierPath, Error> {
+        let num_segments = self.num_segments()?;
+        let closed = !self.is_open()?;
+        // A closed path has as many vertices as segments, the last segment leads back to the first vertex.
+        // An open path has one more vertex than segments.
+        let num_vertices = if closed { num_segments } else { num_segments + 1 };
+        Ok(aegp::BezierPath {
+            vertices: (0..num_vertices).map(|i| self.vertex(i).map(Into::into)).collect::<Result<_, _>>()?,
+            closed,
+            feathers: Vec::new(),
+        })
+    }
+
+    /// Approximates the path with line segments, deviating at most `tolerance` pixels from the curve.
+    ///
+    /// Closed paths don't repeat the first point at the end.
+    pub fn flatten(&self, tolerance: f64) -> Result<Vec<FloatPoint>, Error> {
+        Ok(self.to_bezier_path()?.flatten(tolerance).into_iter().map(|[x, y]| FloatPoint { x, y }).collect())
+    }
+
     /// Returns `true` if the path is inverted.
     pub fn is_inverted(&self) -> Result<bool, Error> {
         self.suite.path_is_inverted(self.effect_ref, self.unique_id)
@@ -288,3 +330,119 @@ impl<'a> Drop for PathSegPrep<'a> {
             .expect("Failed to clean up PF_PathSegPrepPtr");
     }
 }
+
+/// A point on a path, see [`PathSampler`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PathSample {
+    /// Distance along the path.
+    pub distance: f64,
+    pub position: FloatPoint,
+    /// Unit direction of travel.
+    pub tangent: FloatPoint,
+}
+
+impl PathSample {
+    /// Unit normal, pointing to the right of the direction of travel on screen.
+    pub fn normal(&self) -> FloatPoint {
+        FloatPoint { x: -self.tangent.y, y: self.tangent.x }
+    }
+}
+
+/// Evaluates a [`PathOutline`] by distance along the whole path instead of per segment.
+///
+/// Created with [`PathOutline::sampler()`]. Keeps the prepared segments until dropped.
+/// Distances are clamped to the path for open paths and wrap around for closed paths.
+///
+/// Example usage:
+/// ```ignore
+/// let mut sampler = path.sampler(100)?;
+/// for sample in sampler.evenly_spaced(num_particles)? {
+///     emit(sample.position, sample.normal());
+/// }
+/// ```
+pub struct PathSampler<'a> {
+    segments: Vec<PathSegPrep<'a>>,
+    /// Distance along the path at the end of each segment.
+    ends: Vec<f64>,
+    closed: bool,
+}
+
+impl PathSampler<'_> {
+    /// Length of the whole path.
+    pub fn length(&self) -> f64 {
+        self.ends.last().copied().unwrap_or(0.0)
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    pub fn point_at_distance(&mut self, distance: f64) -> Result<FloatPoint, Error> {
+        let (segment, local) = locate(&self.ends, self.closed, distance).ok_or(Error::Parameter)?;
+        let (x, y) = self.segments[segment].eval(local)?;
+        Ok(FloatPoint { x, y })
+    }
+
+    /// Unit direction of travel at `distance`.
+    pub fn tangent_at(&mut self, distance: f64) -> Result<FloatPoint, Error> {
+        Ok(self.sample(distance)?.tangent)
+    }
+
+    /// Unit normal at `distance`, pointing to the right of the direction of travel on screen.
+    pub fn normal_at(&mut self, distance: f64) -> Result<FloatPoint, Error> {
+        Ok(self.sample(distance)?.normal())
+    }
+
+    /// Position and tangent at `distance`. The distance of the sample is clamped or wrapped to the path.
+    pub fn sample(&mut self, distance: f64) -> Result<PathSample, Error> {
+        let (segment, local) = locate(&self.ends, self.closed, distance).ok_or(Error::Parameter)?;
+        let (x, y, dx, dy) = self.segments[segment].eval_deriv1(local)?;
+        let length = dx.hypot(dy);
+        let tangent = if length > 0.0 { FloatPoint { x: dx / length, y: dy / length } } else { FloatPoint { x: 0.0, y: 0.0 } };
+        Ok(PathSample {
+            distance: segment.checked_sub(1).map_or(0.0, |i| self.ends[i]) + local,
+            position: FloatPoint { x, y },
+            tangent,
+        })
+    }
+
+    /// `count` samples spaced evenly by arc length.
+    ///
+    /// Open paths are sampled from start to end inclusive, closed paths don't repeat the start at the end.
+    pub fn evenly_spaced(&mut self, count: usize) -> Result<Vec<PathSample>, Error> {
+        let spacing = match count {
+            _ if self.closed => self.length() / count.max(1) as f64,
+            0 | 1 => 0.0,
+            _ => self.length() / (count - 1) as f64,
+        };
+        (0..count).map(|i| self.sample(i as f64 * spacing)).collect()
+    }
+}
+
+/// Finds the segment and the distance within it for a distance along the whole path.
+fn locate(ends: &[f64], closed: bool, distance: f64) -> Option<(usize, f64)> {
+    let length = *ends.last()?;
+    let distance = if closed && length > 0.0 { distance.rem_euclid(length) } else { distance.clamp(0.0, length) };
+    let segment = ends.partition_point(|&end| end < distance).min(ends.len() - 1);
+    let start = if segment == 0 { 0.0 } else { ends[segment - 1] };
+    Some((segment, distance - start))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locates_segments_by_distance() {
+        let ends = [10.0, 10.0, 25.0];
+        assert_eq!(locate(&ends, false, 0.0), Some((0, 0.0)));
+        assert_eq!(locate(&ends, false, 4.0), Some((0, 4.0)));
+        // Zero length segments are skipped.
+        assert_eq!(locate(&ends, false, 12.5), Some((2, 2.5)));
+        assert_eq!(locate(&ends, false, 30.0), Some((2, 15.0)));
+        assert_eq!(locate(&ends, false, -1.0), Some((0, 0.0)));
+        assert_eq!(locate(&ends, true, 30.0), Some((0, 5.0)));
+        assert_eq!(locate(&ends, true, -5.0), Some((2, 10.0)));
+        assert_eq!(locate(&[], false, 1.0), None);
+    }
+}
@@ -265,6 +265,10 @@ macro_rules! define_effect {
 
             let cmd = RawCommand::from(cmd);
 
+            // Lets cross-thread types know their instance is being destroyed, not just flattened.
+            // Declared before the sequence handle, so it's still set when the handle is dropped.
+            let _setdown_guard = $crate::SequenceSetdownGuard::new(cmd == RawCommand::SequenceSetdown);
+
             // Allocate or restore global data pointer
             let mut global_handle = if cmd == RawCommand::GlobalSetup {
                 // Allocate global data
@@ -299,6 +303,10 @@ macro_rules! define_effect {
                     global_inst.params_map.set((*params.map).clone()).unwrap();
                 }
                 global_inst.params_num.store(params.num_params(), std::sync::atomic::Ordering::Release);
+
+                // Lets the Premiere GPU filter look up parameters by the same enum
+                #[cfg(with_premiere)]
+                ::premiere::register_param_indices(params.map.iter().map(|(param, info)| (*param, info.index)));
             }
 
             let params_num = global_inst.params_num.load(std::sync::atomic::Ordering::Acquire);
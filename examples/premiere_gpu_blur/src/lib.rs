@@ -0,0 +1,111 @@
+use after_effects as ae;
+use premiere as pr;
+
+const RADIUS_MIN:  f32 = 0.0;
+const RADIUS_MAX:  f32 = 100.0;
+const RADIUS_DFLT: f64 = 10.0;
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+enum Params {
+    Radius,
+    RepeatEdges,
+}
+
+#[derive(Default)]
+struct Plugin { }
+
+ae::define_effect!(Plugin, (), Params);
+
+impl AdobePluginGlobal for Plugin {
+    fn params_setup(&self, params: &mut ae::Parameters<Params>, _: InData, _: OutData) -> Result<(), Error> {
+        params.add(Params::Radius, "Radius", ae::FloatSliderDef::setup(|f| {
+            f.set_valid_min(RADIUS_MIN);
+            f.set_valid_max(RADIUS_MAX);
+            f.set_slider_min(RADIUS_MIN);
+            f.set_slider_max(RADIUS_MAX);
+            f.set_default(RADIUS_DFLT);
+            f.set_value(f.default());
+            f.set_precision(1);
+        }))?;
+
+        params.add(Params::RepeatEdges, "Edges", ae::CheckBoxDef::setup(|f| {
+            f.set_default(true);
+            f.set_value(true);
+            f.set_label("Repeat edge pixels");
+        }))?;
+
+        Ok(())
+    }
+
+    fn handle_command(&self, cmd: ae::Command, in_data: InData, mut out_data: OutData, params: &mut ae::Parameters<Params>) -> Result<(), ae::Error> {
+        match cmd {
+            ae::Command::About => {
+                out_data.set_return_msg("Premiere GPU Blur, v1.0,\rBlurs on the CPU in After Effects and on the GPU in Premiere Pro,\rreading the same parameters in both.");
+            }
+            ae::Command::Render { in_layer, mut out_layer } => {
+                let radius = params.get(Params::Radius)?.as_float_slider()?.value();
+                let repeat_edges = params.get(Params::RepeatEdges)?.as_checkbox()?.value();
+
+                if radius <= 0.0 {
+                    return out_layer.copy_from(&in_layer, None, None);
+                }
+
+                // A gaussian blur is separable, so blur horizontally into a temporary world and then vertically into the output.
+                let mut diameter = radius.ceil() as i32 * 2 + 1;
+                let mut kernel = vec![0i32; diameter as usize];
+                in_data.utils().gaussian_kernel(radius, ae::KernelFlags::ONE_D | ae::KernelFlags::NORMALIZED | ae::KernelFlags::USE_LONG, 1.0, &mut diameter, kernel.as_mut_ptr() as *mut _)?;
+                let kernel_ptr = kernel.as_mut_ptr() as *mut _;
+
+                let flags = |direction: ae::KernelFlags| {
+                    let borders = if repeat_edges { ae::KernelFlags::REPLICATE_BORDERS } else { ae::KernelFlags::TRANSPARENT_BORDERS };
+                    ae::KernelFlags::ONE_D | ae::KernelFlags::CLAMP | ae::KernelFlags::USE_LONG | borders | direction
+                };
+                let mut temp = in_data.utils().new_world(out_layer.width(), out_layer.height(), ae::NewWorldFlags::NONE)?;
+                in_data.utils().convolve(&in_layer, None, flags(ae::KernelFlags::HORIZONTAL), diameter, kernel_ptr, kernel_ptr, kernel_ptr, kernel_ptr, &mut temp)?;
+                in_data.utils().convolve(&temp, None, flags(ae::KernelFlags::VERTICAL), diameter, kernel_ptr, kernel_ptr, kernel_ptr, kernel_ptr, &mut out_layer)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct GpuBlur { }
+
+pr::define_gpu_filter!(GpuBlur);
+
+impl pr::GpuFilter for GpuBlur {
+    type Params = Params;
+
+    fn global_init() { }
+    fn global_destroy() { }
+
+    fn precompute(&self, _: &pr::GpuFilterData<Params>, _: pr::RenderParams, _: i32, _: &pr::GpuFrame) -> Result<(), pr::Error> {
+        Ok(())
+    }
+
+    fn render(&self, filter: &pr::GpuFilterData<Params>, render_params: pr::RenderParams, frames: &[pr::GpuFrame], output: &mut pr::OutputFrame<Params>) -> Result<(), pr::Error> {
+        let input = frames.first().ok_or(pr::Error::InvalidParms)?;
+
+        // Same enum as in `params_setup()`, registered by `define_effect!` because `build.rs` sets `with_premiere`
+        let time = render_params.clip_time();
+        let radius = filter.float(Params::Radius, time)? as f32;
+        let repeat_edges = filter.float(Params::RepeatEdges, time)? != 0.0;
+
+        let (downsample_x, downsample_y) = render_params.downsample_factor();
+        let out = output.allocate_like(input)?;
+        filter.gpu_image_processing_suite.gaussian_blur(
+            filter.device_index(),
+            input.data(), input.row_bytes(), input.width(), input.height(),
+            out.data(),   out.row_bytes(),   out.width(),   out.height(),
+            input.pixel_format(),
+            radius * downsample_x,
+            radius * downsample_y,
+            repeat_edges,
+            true,
+            true,
+            render_params.quality()
+        )
+    }
+}
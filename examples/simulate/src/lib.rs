@@ -13,8 +13,6 @@ enum Params {
     UseCache,
 }
 
-use simulation::CACHE_ID;
-
 #[derive(Default)]
 struct Plugin {}
 
@@ -117,16 +115,10 @@ impl AdobePluginGlobal for Plugin {
     ) -> Result<(), ae::Error> {
         match cmd {
             ae::Command::GlobalSetup => {
-                ae::aegp::suites::ComputeCache::new()?.register_class(
-                    &CACHE_ID,
-                    simulation::SimStep::generate_key,
-                    simulation::SimStep::compute,
-                    simulation::SimStep::approx_size,
-                    simulation::SimStep::delete,
-                )?;
+                simulation::register_cache()?;
             }
             ae::Command::GlobalSetdown => {
-                ae::aegp::suites::ComputeCache::new()?.unregister_class(&CACHE_ID)?;
+                simulation::unregister_cache();
             }
             ae::Command::Render {
                 in_layer,
@@ -1,8 +1,23 @@
-use after_effects::{self as ae, aegp::ComputeClassId};
+use after_effects::{self as ae, aegp::ComputeCache, parking_lot::RwLock};
 use bytemuck::{Pod, Zeroable};
+use std::hash::{Hash, Hasher};
 use std::mem::size_of_val;
 
-pub const CACHE_ID: ComputeClassId<SimOptions, SimStep> = ComputeClassId::new("particle_cache");
+/// Registered in `GlobalSetup` and dropped (which unregisters it) in `GlobalSetdown`.
+pub static CACHE: RwLock<Option<ComputeCache<SimOptions, SimStep>>> = RwLock::new(None);
+
+pub fn register_cache() -> Result<(), ae::Error> {
+    let cache = ComputeCache::builder("particle_cache", SimStep::compute)
+        .approx_size(SimStep::approx_size)
+        .on_delete(SimStep::delete)
+        .register()?;
+    *CACHE.write() = Some(cache);
+    Ok(())
+}
+
+pub fn unregister_cache() {
+    CACHE.write().take();
+}
 
 // Store the particle state every 8 frames - 6 times per second of footage.
 // 16 bytes * 36 saves a minte * 1 million particles 500mb per minute of simulation max
@@ -24,6 +39,13 @@ pub struct SimOptions {
     pub step: SimStep,
 }
 
+// Only the parameters identify a cache entry, the step is just the value to store.
+impl Hash for SimOptions {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write(bytemuck::bytes_of(&self.params));
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct Particle {
     pub pos: [f32; 2],
@@ -35,12 +57,6 @@ pub struct Particle {
 pub struct SimStep(pub Vec<Particle>);
 
 impl SimStep {
-    pub fn generate_key(opts: &SimOptions) -> Result<ae::aegp::Guid, ae::Error> {
-        let hash_suite = ae::aegp::suites::Hash::new()?;
-        let key_data: &[u8] = bytemuck::bytes_of(&opts.params);
-        hash_suite.create_hash_from_ptr(key_data)
-    }
-
     pub fn delete(_value: SimStep) {
         log::info!("Dropping simstep");
     }
@@ -88,7 +104,7 @@ impl SimStep {
 
 /// Find the most recent cached frame, or None if no cache hits.
 fn find_cached_frame<F>(
-    cache: &ae::aegp::suites::ComputeCache,
+    cache: &ComputeCache<SimOptions, SimStep>,
     target_frame: u32,
     num_particles: u32,
     seed: i32,
@@ -104,7 +120,7 @@ where
     {
         let (gravity_pt, gravity_str) = get_gravity_at(frame).ok()?;
 
-        let mut opts = SimOptions {
+        let opts = SimOptions {
             params: SimParams {
                 frame,
                 num_particles,
@@ -117,24 +133,19 @@ where
             step: SimStep(Vec::new()),
         };
 
-        if let Some(receipt) = cache.checkout_cached(&CACHE_ID, &mut opts).ok().flatten()
-            && let Ok(step) = cache.receipt_compute_value::<SimStep>(&receipt) {
-                let result = step.clone();
-                let _ = cache.check_in_compute_receipt(receipt);
-                return Some((frame, result));
-            }
+        if let Some(step) = cache.cached(&opts).ok().flatten() {
+            return Some((frame, step.clone()));
+        }
     }
     None
 }
 
-fn cache_frame(cache: &ae::aegp::suites::ComputeCache, params: SimParams, step: &SimStep) {
-    let mut opts = SimOptions {
+fn cache_frame(cache: &ComputeCache<SimOptions, SimStep>, params: SimParams, step: &SimStep) {
+    let opts = SimOptions {
         params,
         step: step.clone(),
     };
-    if let Ok(receipt) = cache.compute_if_needed_and_checkout(CACHE_ID, &mut opts, true) {
-        let _ = cache.check_in_compute_receipt(receipt);
-    }
+    let _ = cache.compute(&opts);
 }
 
 /// Run the simulation to target_frame, using cached keyframes when available.
@@ -148,10 +159,11 @@ pub fn simulate_up_to_frame<F>(
 where
     F: Fn(u32) -> Result<([f32; 2], f32), ae::Error>,
 {
-    let cache = ae::aegp::suites::ComputeCache::new()?;
+    let cache = CACHE.read();
+    let cache = cache.as_ref().ok_or(ae::Error::Generic)?;
 
     let (start_frame, mut step) =
-        find_cached_frame(&cache, target_frame, num_particles, seed, get_gravity_at)
+        find_cached_frame(cache, target_frame, num_particles, seed, get_gravity_at)
             .unwrap_or_else(|| (0, SimStep::initial(num_particles, seed)));
 
     for frame in (start_frame + 1)..=target_frame {
@@ -166,7 +178,7 @@ where
                 gravity_pt,
                 gravity_str,
             };
-            cache_frame(&cache, params, &step);
+            cache_frame(cache, params, &step);
         }
     }
 
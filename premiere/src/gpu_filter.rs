@@ -1,4 +1,10 @@
 use crate::*;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::sync::Arc;
 
 #[derive(Clone)]
 /// Information about a frame render
@@ -58,7 +64,8 @@ impl RenderParams {
     }
 }
 
-pub struct GpuFilterData {
+/// Data of a GPU filter instance. `P` is the parameters enum of the effect, see [`GpuFilter::Params`].
+pub struct GpuFilterData<P = ()> {
     pub instance_ptr: *mut crate::sys::PrGPUFilterInstance,
     pub gpu_device_suite: suites::GPUDevice,
     pub gpu_image_processing_suite: suites::GPUImageProcessing,
@@ -67,8 +74,10 @@ pub struct GpuFilterData {
     pub ppix2_suite: suites::PPix2,
     pub video_segment_suite: suites::VideoSegment,
     pub gpu_info: crate::sys::PrGPUDeviceInfo,
+    #[doc(hidden)]
+    pub _params: PhantomData<fn() -> P>,
 }
-impl GpuFilterData {
+impl<P> GpuFilterData<P> {
     pub fn timeline_id(&self) -> crate::sys::PrTimelineID {
         assert!(!self.instance_ptr.is_null());
         unsafe { (*self.instance_ptr).inTimelineID }
@@ -81,18 +90,128 @@ impl GpuFilterData {
         assert!(!self.instance_ptr.is_null());
         unsafe { (*self.instance_ptr).inDeviceIndex }
     }
+    pub fn property(&self, property: Property) -> Result<PropertyData, Error> {
+        self.video_segment_suite.node_property(self.node_id(), property)
+    }
+
+    fn decode_arbitrary<T: for<'a> serde::Deserialize<'a>>(&self, value: crate::Param) -> Result<T, Error> {
+        let crate::Param::MemoryPtr(ptr) = value else {
+            return Err(Error::InvalidParms);
+        };
+        if ptr.is_null() {
+            return Err(Error::InvalidParms);
+        }
+        let serialized = unsafe { std::slice::from_raw_parts(ptr as *const u8, self.memory_manager_suite.ptr_size(ptr) as _) };
+        bincode::serde::decode_from_slice::<T, _>(serialized, bincode::config::legacy())
+            .map(|(t, _)| t)
+            .map_err(|_| Error::InvalidParms)
+    }
 
     /// Get a specific param value at a specific time
     /// * `index` - The index of the param
     /// * `time` - The time requested (in Media time)
     ///
     /// Returns the param
+    #[deprecated(note = "Use `value()` or the typed accessors with the effect's `Params` enum")]
     pub fn param(&self, index: usize, time: i64) -> Result<crate::Param, Error> {
         let index = index as i32 - 1; // GPU filters don't include the input frame as first paramter
 
         self.video_segment_suite.param(self.node_id(), index, time)
     }
 
+    /// Get the next keyframe time after the specified time, see [`next_keyframe()`](Self::next_keyframe).
+    /// * `index` - The index of the param
+    /// * `time` - The lower bound time
+    #[deprecated(note = "Use `next_keyframe()` with the effect's `Params` enum")]
+    pub fn next_keyframe_time(&self, index: usize, time: i64) -> Result<(i64, KeyframeInterpolationMode), Error> {
+        let index = index as i32 - 1; // GPU filters don't include the input frame as first paramter
+
+        self.video_segment_suite.next_keyframe_time(self.node_id(), index, time)
+    }
+
+    #[deprecated(note = "Use `arbitrary()` with the effect's `Params` enum")]
+    pub fn param_arbitrary_data<T: for<'a> serde::Deserialize<'a>>(&self, index: usize, time: i64) -> Result<T, Error> {
+        let index = index as i32 - 1; // GPU filters don't include the input frame as first paramter
+
+        self.decode_arbitrary(self.video_segment_suite.param(self.node_id(), index, time)?)
+    }
+}
+impl<P: Copy + Eq + Hash + Debug + Send + Sync + 'static> GpuFilterData<P> {
+    /// Index of `param` in the video segment node. GPU filters don't include the input layer as first parameter.
+    ///
+    /// The indices are looked up on every call, because instances can be created before `ParamsSetup` registered them.
+    fn segment_param_index(&self, param: P) -> Result<i32, Error> {
+        match param_indices::<P>().get(&param) {
+            Some(&index) if index > 0 => Ok(index as i32 - 1),
+            _ => {
+                log::error!("Parameter {param:?} is not registered. `define_effect!` registers them at `ParamsSetup` when built with `cargo:rustc-cfg=with_premiere`");
+                Err(Error::InvalidParms)
+            }
+        }
+    }
+
+    /// Get the raw value of `param` at a specific time
+    /// * `param` - The parameter, using the same enum as in `define_effect!`
+    /// * `time` - The time requested (in Media time)
+    pub fn value(&self, param: P, time: i64) -> Result<crate::Param, Error> {
+        self.video_segment_suite.param(self.node_id(), self.segment_param_index(param)?, time)
+    }
+
+    /// Value of a float slider, angle or checkbox. Integer values are converted.
+    pub fn float(&self, param: P, time: i64) -> Result<f64, Error> {
+        match self.value(param, time)? {
+            crate::Param::Float64(v) => Ok(v),
+            crate::Param::Float32(v) => Ok(v as f64),
+            crate::Param::Int8(v)    => Ok(v as f64),
+            crate::Param::Int16(v)   => Ok(v as f64),
+            crate::Param::Int32(v)   => Ok(v as f64),
+            crate::Param::Int64(v)   => Ok(v as f64),
+            crate::Param::Bool(v)    => Ok(if v { 1.0 } else { 0.0 }),
+            _ => Err(Error::InvalidParms),
+        }
+    }
+
+    /// Value of a point parameter, as reported by Premiere.
+    pub fn point(&self, param: P, time: i64) -> Result<(f64, f64), Error> {
+        match self.value(param, time)? {
+            crate::Param::Point(p) => Ok((p.x, p.y)),
+            _ => Err(Error::InvalidParms),
+        }
+    }
+
+    /// Value of a color parameter as `[red, green, blue, alpha]` in the range 0-1.
+    ///
+    /// Premiere passes colors as ARGB with 16 bits per channel, or 8 bits per channel in 32-bit values.
+    pub fn color(&self, param: P, time: i64) -> Result<[f32; 4], Error> {
+        let (argb, max) = match self.value(param, time)? {
+            crate::Param::Int64(v) => (v as u64, u16::MAX as u64),
+            crate::Param::Int32(v) => {
+                let v = v as u32 as u64;
+                // Spread the 8-bit channels to 16-bit positions.
+                (((v >> 24) & 0xff) << 48 | ((v >> 16) & 0xff) << 32 | ((v >> 8) & 0xff) << 16 | (v & 0xff), u8::MAX as u64)
+            }
+            _ => return Err(Error::InvalidParms),
+        };
+        let channel = |shift: u32| ((argb >> shift) & 0xffff).min(max) as f32 / max as f32;
+        Ok([channel(32), channel(16), channel(0), channel(48)])
+    }
+
+    /// Selected item of a popup, starting at 1 like in After Effects.
+    pub fn popup(&self, param: P, time: i64) -> Result<i32, Error> {
+        match self.value(param, time)? {
+            crate::Param::Int8(v)  => Ok(v as i32),
+            crate::Param::Int16(v) => Ok(v as i32),
+            crate::Param::Int32(v) => Ok(v),
+            crate::Param::Int64(v) => i32::try_from(v).map_err(|_| Error::InvalidParms),
+            _ => Err(Error::InvalidParms),
+        }
+    }
+
+    /// Value of an arbitrary data parameter, decoded the same way it is flattened by `ArbParamsExtra` in After Effects.
+    pub fn arbitrary<T: for<'a> serde::Deserialize<'a>>(&self, param: P, time: i64) -> Result<T, Error> {
+        self.decode_arbitrary(self.value(param, time)?)
+    }
+
     /// Get the next keyframe time after the specified time.
     /// Example: Keyframes at 0 and 10
     /// - `time` = -1, keyframe_time = 0
@@ -101,35 +220,248 @@ impl GpuFilterData {
     /// - `time` = 10, returns [`Error::NoKeyframeAfterInTime`]
     ///
     /// Parameters:
-    /// * `index` - The index of the param
+    /// * `param` - The parameter, using the same enum as in `define_effect!`
     /// * `time` - The lower bound time
     ///
     /// Returns a tuple containing:
     /// * `keyframe_time` - The time of the next keyframe > inTime
     /// * `keyframe_interpolation_mode` - The temporal interpolation mode of the keyframe
-    pub fn next_keyframe_time(&self, index: usize, time: i64) -> Result<(i64, KeyframeInterpolationMode), Error> {
-        let index = index as i32 - 1; // GPU filters don't include the input frame as first paramter
+    pub fn next_keyframe(&self, param: P, time: i64) -> Result<(i64, KeyframeInterpolationMode), Error> {
+        self.video_segment_suite.next_keyframe_time(self.node_id(), self.segment_param_index(param)?, time)
+    }
+}
 
-        self.video_segment_suite.next_keyframe_time(self.node_id(), index, time)
+static PARAM_INDICES: parking_lot::RwLock<Vec<(TypeId, Arc<dyn Any + Send + Sync>)>> = parking_lot::RwLock::new(Vec::new());
+
+/// Registers the indices of the effect parameters of type `P`, so GPU filters can read them by their enum value.
+///
+/// `define_effect!` from the `after-effects` crate calls this at `ParamsSetup` when built with Premiere support.
+/// The indices are the After Effects ones, with the input layer at 0.
+pub fn register_param_indices<P: Copy + Eq + Hash + Send + Sync + 'static>(indices: impl IntoIterator<Item = (P, usize)>) {
+    let map: Arc<dyn Any + Send + Sync> = Arc::new(indices.into_iter().collect::<HashMap<P, usize>>());
+    let mut registry = PARAM_INDICES.write();
+    registry.retain(|(id, _)| *id != TypeId::of::<P>());
+    registry.push((TypeId::of::<P>(), map));
+}
+
+/// The indices registered with [`register_param_indices()`], or an empty map if `ParamsSetup` didn't run yet.
+pub fn param_indices<P: Copy + Eq + Hash + Send + Sync + 'static>() -> Arc<HashMap<P, usize>> {
+    PARAM_INDICES.read()
+        .iter()
+        .find(|(id, _)| *id == TypeId::of::<P>())
+        .and_then(|(_, map)| map.clone().downcast().ok())
+        .unwrap_or_default()
+}
+
+/// Something a render needs in addition to the current frame, see [`GpuFilter::get_frame_dependencies()`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dependency {
+    /// A frame of `track` at `time_offset` ticks from the current sequence time, passed to [`GpuFilter::render()`] after the current frame.
+    InputFrame { track: i32, time_offset: i64 },
+    /// Data computed on the CPU in [`GpuFilter::precompute()`] and uploaded by the host, passed to [`GpuFilter::render()`] like an input frame.
+    ///
+    /// Frames in formats other than [`PixelFormat::GpuBgra4444_32f`] or custom ones are converted to the GPU pixel format.
+    /// The frame uses the pixel aspect ratio and field type of the render.
+    Precompute { pixel_format: PixelFormat, size: (u32, u32), custom_data_size: usize },
+    /// Renders each field separately, see [`RenderParams::render_field()`].
+    FieldSeparation,
+    /// Transitions only: whether the incoming frame is read.
+    TransitionInputFrame { read_incoming: bool },
+}
+impl Dependency {
+    fn to_raw(self, render_params: &RenderParams) -> crate::sys::PrGPUFilterFrameDependency {
+        let mut raw: crate::sys::PrGPUFilterFrameDependency = unsafe { std::mem::zeroed() };
+        match self {
+            Self::InputFrame { track, time_offset } => {
+                raw.outDependencyType = crate::sys::PrGPUFilterFrameDependencyType_PrGPUDependency_InputFrame;
+                raw.outTrackID = track;
+                raw.outSequenceTime = render_params.sequence_time() + time_offset;
+            }
+            Self::Precompute { pixel_format, size, custom_data_size } => {
+                let (par_num, par_den) = render_params.render_pixel_aspect_ratio();
+                raw.outDependencyType = crate::sys::PrGPUFilterFrameDependencyType_PrGPUDependency_Precompute;
+                raw.outPrecomputePixelFormat = pixel_format.into();
+                raw.outPrecomputeFrameWidth = size.0;
+                raw.outPrecomputeFrameHeight = size.1;
+                raw.outPrecomputeFramePARNumerator = par_num;
+                raw.outPrecomputeFramePARDenominator = par_den;
+                raw.outPrecomputeFrameFieldType = render_params.render_field_type();
+                raw.outPrecomputeCustomDataSize = custom_data_size as _;
+            }
+            Self::FieldSeparation => {
+                raw.outDependencyType = crate::sys::PrGPUFilterFrameDependencyType_PrGPUDependency_FieldSeparation;
+                raw.outNeedsFieldSeparation = 1;
+            }
+            Self::TransitionInputFrame { read_incoming } => {
+                raw.outDependencyType = crate::sys::PrGPUFilterFrameDependencyType_PrGPUDependency_TransitionInputFrame;
+                raw.outReadIncomingTransition = read_incoming as _;
+            }
+        }
+        raw
     }
+}
 
-    pub fn param_arbitrary_data<T: for<'a> serde::Deserialize<'a>>(&self, index: usize, time: i64) -> Result<T, Error> {
-        let ptr = self.param(index, time)?;
-        if let crate::Param::MemoryPtr(ptr) = ptr
-            && !ptr.is_null() {
-                let serialized = unsafe { std::slice::from_raw_parts(ptr as *mut u8, self.memory_manager_suite.ptr_size(ptr) as _) };
-                if let Ok(t) = bincode::serde::decode_from_slice::<T, _>(serialized, bincode::config::legacy()) {
-                    return Ok(t.0);
-                }
+/// A frame passed to [`GpuFilter::render()`] or [`GpuFilter::precompute()`], with its properties read through the `PPix` and `GPUDevice` suites.
+#[derive(Debug, Clone, Copy)]
+pub struct GpuFrame {
+    handle: crate::sys::PPixHand,
+    data: *mut std::ffi::c_void,
+    pixel_format: PixelFormat,
+    bounds: crate::sys::prRect,
+    row_bytes: i32,
+    pixel_aspect_ratio: (u32, u32),
+    field_type: crate::sys::prFieldType,
+}
+impl GpuFrame {
+    /// Reads the properties of a GPU frame.
+    pub fn from_handle<P>(filter: &GpuFilterData<P>, handle: crate::sys::PPixHand) -> Result<Self, Error> {
+        if handle.is_null() {
+            return Err(Error::InvalidParms);
+        }
+        Self::read(filter, handle, filter.gpu_device_suite.gpu_ppix_data(handle)?)
+    }
+
+    /// Reads the properties of a frame in host memory, like the one passed to [`GpuFilter::precompute()`].
+    pub fn from_host_handle<P>(filter: &GpuFilterData<P>, handle: crate::sys::PPixHand) -> Result<Self, Error> {
+        if handle.is_null() {
+            return Err(Error::InvalidParms);
+        }
+        Self::read(filter, handle, filter.ppix_suite.pixels(handle, PPixBufferAccess::ReadWrite)? as *mut _)
+    }
+
+    fn read<P>(filter: &GpuFilterData<P>, handle: crate::sys::PPixHand, data: *mut std::ffi::c_void) -> Result<Self, Error> {
+        Ok(Self {
+            handle,
+            data,
+            pixel_format: filter.ppix_suite.pixel_format(handle)?,
+            bounds: filter.ppix_suite.bounds(handle)?,
+            row_bytes: filter.ppix_suite.row_bytes(handle)?,
+            pixel_aspect_ratio: filter.ppix_suite.pixel_aspect_ratio(handle)?,
+            field_type: filter.ppix2_suite.field_order(handle)?,
+        })
+    }
+
+    pub fn handle(&self) -> crate::sys::PPixHand {
+        self.handle
+    }
+    /// Device memory of a GPU frame (a `CUdeviceptr`, `cl_mem` or `MTLBuffer`, depending on the framework), or host memory of a precompute frame.
+    pub fn data(&self) -> *mut std::ffi::c_void {
+        self.data
+    }
+    pub fn pixel_format(&self) -> PixelFormat {
+        self.pixel_format
+    }
+    pub fn bounds(&self) -> crate::sys::prRect {
+        self.bounds
+    }
+    pub fn width(&self) -> u32 {
+        (self.bounds.right - self.bounds.left).max(0) as u32
+    }
+    pub fn height(&self) -> u32 {
+        (self.bounds.bottom - self.bounds.top).max(0) as u32
+    }
+    /// How many bytes must be added to the data address to get to the next line.
+    pub fn row_bytes(&self) -> i32 {
+        self.row_bytes
+    }
+    /// Numerator and denominator.
+    pub fn pixel_aspect_ratio(&self) -> (u32, u32) {
+        self.pixel_aspect_ratio
+    }
+    pub fn field_type(&self) -> crate::sys::prFieldType {
+        self.field_type
+    }
+}
+
+/// The result of [`GpuFilter::render()`].
+///
+/// Either renders [`in_place()`](Self::in_place) into an input frame, or into a frame from [`allocate()`](Self::allocate).
+/// Allocated frames are handed to the host if the render succeeds and disposed otherwise.
+pub struct OutputFrame<'a, P = ()> {
+    filter: &'a GpuFilterData<P>,
+    frame: Option<GpuFrame>,
+    allocated: bool,
+}
+impl<'a, P> OutputFrame<'a, P> {
+    fn new(filter: &'a GpuFilterData<P>) -> Self {
+        Self { filter, frame: None, allocated: false }
+    }
+
+    /// Renders into `input`, leaving the output of the render unset.
+    pub fn in_place(&mut self, input: &GpuFrame) -> GpuFrame {
+        self.release();
+        self.frame = Some(*input);
+        *input
+    }
+
+    /// Allocates a frame on the filter's device with the pixel format, size, pixel aspect ratio and field type of `like`.
+    pub fn allocate_like(&mut self, like: &GpuFrame) -> Result<GpuFrame, Error> {
+        self.allocate(like.pixel_format(), like.width(), like.height(), like.pixel_aspect_ratio(), like.field_type())
+    }
+
+    /// Allocates a frame on the filter's device. The result must be in the same pixel format as the input.
+    pub fn allocate(&mut self, pixel_format: PixelFormat, width: u32, height: u32, pixel_aspect_ratio: (u32, u32), field_type: crate::sys::prFieldType) -> Result<GpuFrame, Error> {
+        self.release();
+        let handle = self.filter.gpu_device_suite.create_gpu_ppix(
+            self.filter.device_index(),
+            pixel_format,
+            width as i32,
+            height as i32,
+            pixel_aspect_ratio.0 as i32,
+            pixel_aspect_ratio.1 as i32,
+            field_type,
+        )?;
+        match GpuFrame::from_handle(self.filter, handle) {
+            Ok(frame) => {
+                self.frame = Some(frame);
+                self.allocated = true;
+                Ok(frame)
             }
-        Err(Error::InvalidParms)
+            Err(e) => {
+                let _ = self.filter.ppix_suite.dispose(handle);
+                Err(e)
+            }
+        }
     }
-    pub fn property(&self, property: Property) -> Result<PropertyData, Error> {
-        self.video_segment_suite.node_property(self.node_id(), property)
+
+    /// The frame chosen with [`in_place()`](Self::in_place) or [`allocate()`](Self::allocate).
+    pub fn frame(&self) -> Option<&GpuFrame> {
+        self.frame.as_ref()
+    }
+
+    fn release(&mut self) {
+        if let Some(frame) = self.frame.take()
+            && self.allocated
+            && let Err(e) = self.filter.ppix_suite.dispose(frame.handle) {
+                log::error!("Failed to dispose GPU frame: {e:?}");
+            }
+        self.allocated = false;
+    }
+
+    /// Hands an allocated frame over to the host.
+    fn commit(mut self, out_frame: *mut crate::sys::PPixHand) -> Result<(), Error> {
+        let frame = self.frame.ok_or(Error::InvalidCall)?;
+        if self.allocated {
+            if out_frame.is_null() {
+                return Err(Error::InvalidParms);
+            }
+            unsafe { *out_frame = frame.handle };
+            self.allocated = false;
+        }
+        Ok(())
+    }
+}
+impl<P> Drop for OutputFrame<'_, P> {
+    fn drop(&mut self) {
+        self.release();
     }
 }
 
 pub trait GpuFilter : Default {
+    /// The parameters enum passed to `define_effect!`, read with the accessors of [`GpuFilterData`].
+    /// Use `()` if the filter doesn't read any parameters.
+    type Params: Copy + Eq + Hash + Debug + Send + Sync + 'static;
+
     /// Called once at startup to initialize any global state.
     /// * Note that the instances are created and destroyed many times during the same render,
     ///   so don't rely on `Default` or `Drop` for any global state
@@ -141,27 +473,57 @@ pub trait GpuFilter : Default {
     fn global_destroy();
 
     /// Return dependency information about a render, or nothing if only the current frame is required.
-    fn get_frame_dependencies(&self, filter: &GpuFilterData, render_params: RenderParams, query_index: &mut i32) -> Result<crate::sys::PrGPUFilterFrameDependency, Error>;
+    ///
+    /// Input frames and precomputed data are passed to [`render()`](Self::render) in the same order, after the current frame.
+    fn get_frame_dependencies(&self, _filter: &GpuFilterData<Self::Params>, _render_params: RenderParams) -> Result<Vec<Dependency>, Error> {
+        Ok(Vec::new())
+    }
 
     /// Precompute a result into preallocated uninitialized host (pinned) memory.
     /// Will only be called if PrGPUDependency_Precompute was returned from GetFrameDependencies.
     /// Precomputation may be called ahead of render time. Results will be
     /// uploaded to the GPU by the host. If outPrecomputePixelFormat is not custom,
     /// frames will be converted to the GPU pixel format.
-    fn precompute(&self, filter: &GpuFilterData, render_params: RenderParams, index: i32, frame: crate::sys::PPixHand) -> Result<(), Error>;
+    fn precompute(&self, filter: &GpuFilterData<Self::Params>, render_params: RenderParams, index: i32, frame: &GpuFrame) -> Result<(), Error>;
 
     /// Render into an allocated outFrame allocated with PrSDKGPUDeviceSuite or operate
     /// in place. Result must be in the same pixel format as the input. For effects, frame 0
     /// will always be the frame at the current time, other input frames will be in the same order as
     /// returned from GetFrameDependencies. For transitions frame 0 will be the incoming frame and
     /// frame 1 the outgoing frame. Transitions may not have other frame dependencies.
-    fn render(&self, filter: &GpuFilterData, render_params: RenderParams, frames: *const crate::sys::PPixHand, frame_count: usize, out_frame: *mut crate::sys::PPixHand) -> Result<(), Error>;
+    fn render(&self, filter: &GpuFilterData<Self::Params>, render_params: RenderParams, frames: &[GpuFrame], output: &mut OutputFrame<Self::Params>) -> Result<(), Error>;
 }
 
 pub struct GpuFilterInstance<T: GpuFilter> {
-    pub data: GpuFilterData,
+    pub data: GpuFilterData<T::Params>,
     pub instance: T,
 }
+impl<T: GpuFilter> GpuFilterInstance<T> {
+    /// Reports the dependency at `query_index`, and advances it if more are left.
+    /// Returns [`Error::NotImplemented`] once all dependencies have been reported.
+    pub fn frame_dependency(&self, render_params: RenderParams, query_index: &mut i32) -> Result<crate::sys::PrGPUFilterFrameDependency, Error> {
+        let dependencies = self.instance.get_frame_dependencies(&self.data, render_params.clone())?;
+        let index = usize::try_from(*query_index).map_err(|_| Error::InvalidParms)?;
+        let dependency = dependencies.get(index).ok_or(Error::NotImplemented)?;
+        if index + 1 < dependencies.len() {
+            *query_index += 1;
+        }
+        Ok(dependency.to_raw(&render_params))
+    }
+
+    pub fn precompute(&self, render_params: RenderParams, index: i32, frame: crate::sys::PPixHand) -> Result<(), Error> {
+        let frame = GpuFrame::from_host_handle(&self.data, frame)?;
+        self.instance.precompute(&self.data, render_params, index, &frame)
+    }
+
+    pub fn render(&self, render_params: RenderParams, frames: *const crate::sys::PPixHand, frame_count: usize, out_frame: *mut crate::sys::PPixHand) -> Result<(), Error> {
+        let handles = if frames.is_null() { &[][..] } else { unsafe { std::slice::from_raw_parts(frames, frame_count) } };
+        let frames = handles.iter().map(|&handle| GpuFrame::from_handle(&self.data, handle)).collect::<Result<Vec<_>, _>>()?;
+        let mut output = OutputFrame::new(&self.data);
+        self.instance.render(&self.data, render_params, &frames, &mut output)?;
+        output.commit(out_frame)
+    }
+}
 
 /// Define a GPU filter entry point and register the `struct_name` as the filter handler.
 ///
@@ -170,6 +532,9 @@ pub struct GpuFilterInstance<T: GpuFilter> {
 /// GPU filter instances are created and destroyed on demand. They work together with the AfterEffects main entry point, where you define
 /// all the parameters and handle other properties. Premiere's GPU filter is an additional layer to just handle the rendering on the GPU.
 ///
+/// Parameters are read by the same enum as in `define_effect!`, set as [`GpuFilter::Params`], using the accessors of [`GpuFilterData`]. This requires building with
+/// `cargo:rustc-cfg=with_premiere`, so `define_effect!` registers the parameter indices, see the `premiere_gpu_blur` example.
+///
 /// To share data between AfterEffects plugin interface and GPU filter interface, see [`suites::OpaqueEffectData`]
 #[macro_export]
 macro_rules! define_gpu_filter {
@@ -196,7 +561,8 @@ macro_rules! define_gpu_filter {
                         ppix_suite:                 $crate::suites::PPix::new()?,
                         ppix2_suite:                $crate::suites::PPix2::new()?,
                         video_segment_suite:        $crate::suites::VideoSegment::new()?,
-                        gpu_info
+                        gpu_info,
+                        _params: std::marker::PhantomData,
                     },
                     instance: <$struct_name>::default(),
                 }))
@@ -238,7 +604,7 @@ macro_rules! define_gpu_filter {
             instance.data.instance_ptr = instance_data;
 
             let render_params = $crate::RenderParams::from_raw(render_params);
-            let result = instance.instance.get_frame_dependencies(&instance.data, render_params, &mut *io_query_index);
+            let result = instance.frame_dependency(render_params, &mut *io_query_index);
 
             let _ = Box::into_raw(instance); // leak the box so it doesn't run the destructor
 
@@ -265,7 +631,7 @@ macro_rules! define_gpu_filter {
             instance.data.instance_ptr = instance_data;
 
             let render_params = $crate::RenderParams::from_raw(render_params);
-            let result = instance.instance.precompute(&instance.data, render_params, index, frame);
+            let result = instance.precompute(render_params, index, frame);
 
             let _ = Box::into_raw(instance); // leak the box so it doesn't run the destructor
 
@@ -290,7 +656,7 @@ macro_rules! define_gpu_filter {
             instance.data.instance_ptr = instance_data;
 
             let render_params = $crate::RenderParams::from_raw(render_params);
-            let result = instance.instance.render(&instance.data, render_params, frames, frame_count as usize, out_frame);
+            let result = instance.render(render_params, frames, frame_count as usize, out_frame);
 
             let _ = Box::into_raw(instance); // leak the box so it doesn't run the destructor
 
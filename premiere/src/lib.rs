@@ -40,6 +40,14 @@ pub mod suites {
     pub(crate) mod video_segment;            pub use video_segment       ::VideoSegmentSuite       as VideoSegment;
     pub(crate) mod string;                   pub use string              ::PrStringSuite           as PrString;
     pub(crate) mod window;                   pub use window              ::WindowSuite             as Window;
+    pub(crate) mod sequence_render;          pub use sequence_render     ::SequenceRenderSuite     as SequenceRender;
+    pub(crate) mod clip_render;              pub use clip_render         ::ClipRenderSuite         as ClipRender;
+    pub(crate) mod ppix_creator;             pub use ppix_creator        ::{PPixCreatorSuite       as PPixCreator,
+                                                                            PPixCreator2Suite      as PPixCreator2 };
+    pub(crate) mod ppix_cache;               pub use ppix_cache          ::PPixCacheSuite          as PPixCache;
+    pub(crate) mod threaded_work;            pub use threaded_work       ::ThreadedWorkSuite       as ThreadedWork;
+    pub(crate) mod error;                    pub use error               ::{ErrorSuite2,
+                                                                            ErrorSuite3 };
     pub(crate) mod video_segment_properties;
     #[cfg(has_ae_sdk)] mod opaque_effect_data;
     #[cfg(has_ae_sdk)] pub use opaque_effect_data::OpaqueEffectDataSuite as OpaqueEffectData;
@@ -53,13 +61,20 @@ pub mod suites {
 }
 
 pub mod utils {
-    pub mod video_sequence_parser; pub use video_sequence_parser::VideoSequenceParser;
+    pub mod video_sequence_parser; pub use video_sequence_parser::{VideoSequenceParser, TimelineGraph, Segment, Node, NodeInput, NodeType};
+    pub mod pixel_conversion;
 }
 
 pub use suites::string::PrString;
 pub use suites::video_segment_properties::*;
 pub use suites::video_segment::VideoSegmentProperties;
-pub use suites::ppix::YUV420PlanarBuffers;
+pub use suites::ppix::{YUV420PlanarBuffers, PPixFrame};
+pub use suites::sequence_render::{SequenceRenderParams, RenderedFrame, VideoRenderer};
+pub use suites::clip_render::ClipFrameFormat;
+pub use suites::ppix_creator::custom_pixel_format;
+pub use suites::ppix_cache::CacheFrameFormat;
+pub use suites::threaded_work::{JobHandle, JobToken};
+pub use suites::error::{EventType, EventFlags, EventsLogger};
 pub use suites::sequence_info::ImmersiveVideoVRConfiguration;
 pub use pf_suites::background_frame::TransferMode;
 pub use pf_suites::pixel_format::NewWorldFlags;
@@ -219,6 +234,18 @@ pub(crate) fn borrow_pica_basic_as_ptr() -> *const pr_sys::SPBasicSuite {
     pica_basic_ptr
 }
 
+/// Runs `f` called from the host, which must never be unwound into as that would abort.
+/// A panic is logged as coming from `what`, and `None` is returned.
+pub(crate) fn catch_ffi_panic<R>(what: &str, f: impl FnOnce() -> R) -> Option<R> {
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)) {
+        Ok(result) => Some(result),
+        Err(_) => {
+            log::error!("Panic in {what}");
+            None
+        }
+    }
+}
+
 /// This lets us access a thread-local version of the `PicaBasic`
 /// suite. Whenever we generate a new `SPBasic_Suite` from Ae somehow,
 /// we create a PicaBasicSuite::new() from that and use that to initialize
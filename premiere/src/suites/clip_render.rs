@@ -0,0 +1,93 @@
+use crate::*;
+
+define_suite!(
+    /// Reads frames of a clip directly from the importer, without rendering the sequence.
+    ///
+    /// Frames can be read synchronously with [`find_frame()`](Self::find_frame), or requested with
+    /// [`initiate_async_read()`](Self::initiate_async_read) and picked up later with a non-blocking [`find_frame()`](Self::find_frame).
+    ClipRenderSuite,
+    PrSDKClipRenderSuite,
+    kPrSDKClipRenderSuite,
+    kPrSDKClipRenderSuiteVersion
+);
+
+/// A format a clip frame can be read in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClipFrameFormat {
+    pub pixel_format: PixelFormat,
+    pub width: i32,
+    pub height: i32,
+}
+impl From<ClipFrameFormat> for pr_sys::ClipFrameFormat {
+    fn from(format: ClipFrameFormat) -> Self {
+        Self {
+            inPixelFormat: format.pixel_format.into(),
+            inWidth: format.width,
+            inHeight: format.height,
+        }
+    }
+}
+
+impl ClipRenderSuite {
+    /// Acquire this suite from the host. Returns error if the suite is not available.
+    /// Suite is released on drop.
+    pub fn new() -> Result<Self, Error> {
+        crate::Suite::new()
+    }
+    /// Does this clip support the clip render suite functions?
+    ///
+    /// Returns a tuple containing:
+    /// * `supported` - If true, the clip supports this suite
+    /// * `async_io_supported` - If true, the clip supports asynchronous IO. If false, it is still legal to call [`initiate_async_read()`](Self::initiate_async_read)
+    pub fn supports_clip_render_suite(&self, clip_id: pr_sys::PrClipID) -> Result<(bool, bool), Error> {
+        let (supported, async_io) = call_suite_fn_double!(self, SupportsClipRenderSuite -> pr_sys::prBool, pr_sys::prBool, clip_id)?;
+        Ok((supported != 0, async_io != 0))
+    }
+    /// Pixel formats supported by the clip, in order of preference.
+    pub fn pixel_formats(&self, clip_id: pr_sys::PrClipID) -> Result<Vec<PixelFormat>, Error> {
+        let count = call_suite_fn_single!(self, GetNumPixelFormats -> i32, clip_id)?;
+        (0..count)
+            .map(|i| Ok(call_suite_fn_single!(self, GetPixelFormat -> pr_sys::PrPixelFormat, clip_id, i)?.into()))
+            .collect()
+    }
+    /// Custom pixel formats supported by the clip, as raw values.
+    pub fn custom_pixel_formats(&self, clip_id: pr_sys::PrClipID) -> Result<Vec<pr_sys::PrPixelFormat>, Error> {
+        let count = call_suite_fn_single!(self, GetNumCustomPixelFormats -> i32, clip_id)?;
+        (0..count)
+            .map(|i| call_suite_fn_single!(self, GetCustomPixelFormat -> pr_sys::PrPixelFormat, clip_id, i))
+            .collect()
+    }
+    /// Frame sizes the clip can be read at in `pixel_format` without scaling, as `(width, height)`.
+    pub fn preferred_frame_sizes(&self, clip_id: pr_sys::PrClipID, pixel_format: PixelFormat) -> Result<Vec<(i32, i32)>, Error> {
+        let count = call_suite_fn_single!(self, GetNumPreferredFrameSizes -> i32, clip_id, pixel_format.into())?;
+        (0..count)
+            .map(|i| call_suite_fn_double!(self, GetPreferredFrameSize -> i32, i32, clip_id, pixel_format.into(), i))
+            .collect()
+    }
+    /// Starts reading the frame at `time` in the background. Pick it up with a non-blocking [`find_frame()`](Self::find_frame).
+    pub fn initiate_async_read(&self, clip_id: pr_sys::PrClipID, time: pr_sys::PrTime, format: ClipFrameFormat) -> Result<(), Error> {
+        let mut format = format.into();
+        call_suite_fn!(self, InitiateAsyncRead, clip_id, &time, &mut format)
+    }
+    pub fn cancel_async_read(&self, clip_id: pr_sys::PrClipID, time: pr_sys::PrTime, format: ClipFrameFormat) -> Result<(), Error> {
+        let mut format = format.into();
+        call_suite_fn!(self, CancelAsyncRead, clip_id, &time, &mut format)
+    }
+    /// Gets the frame at `time` in one of `formats`, in order of preference.
+    /// * `synchronous` - If true, reads the frame if needed. Otherwise, only returns frames which are already available
+    ///
+    /// Returns `None` if the frame isn't available yet.
+    pub fn find_frame(&self, clip_id: pr_sys::PrClipID, time: pr_sys::PrTime, formats: &[ClipFrameFormat], synchronous: bool) -> Result<Option<PPixFrame>, Error> {
+        let mut formats: Vec<pr_sys::ClipFrameFormat> = formats.iter().map(|&f| f.into()).collect();
+        let ppix_suite = suites::PPix::new()?;
+        let frame = call_suite_fn_single!(self, FindFrame -> pr_sys::PPixHand, clip_id, &time, formats.as_mut_ptr(), formats.len() as _, synchronous)?;
+        if frame.is_null() {
+            Ok(None)
+        } else {
+            PPixFrame::from_raw(frame, ppix_suite).map(Some)
+        }
+    }
+    pub fn clip_field_type(&self, clip_id: pr_sys::PrClipID) -> Result<FieldType, Error> {
+        Ok(call_suite_fn_single!(self, GetClipFieldType -> pr_sys::prFieldType, clip_id)?.into())
+    }
+}
@@ -0,0 +1,265 @@
+use crate::*;
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::time::{Duration, Instant};
+
+define_suite!(
+    /// Reports events to the Events panel, with UTF-8 strings.
+    ErrorSuite2,
+    PrSDKErrorSuite2,
+    kPrSDKErrorSuite,
+    kPrSDKErrorSuiteVersion2
+);
+define_suite!(
+    /// Reports events to the Events panel, with UTF-16 strings.
+    ///
+    /// See [`EventsLogger`] to report `log` records.
+    ErrorSuite3,
+    PrSDKErrorSuite3,
+    kPrSDKErrorSuite,
+    kPrSDKErrorSuiteVersion3
+);
+
+define_enum! {
+    pr_sys::PrSDKErrorSuite3__bindgen_ty_1,
+    EventType {
+        Informational = pr_sys::PrSDKErrorSuite3_kEventTypeInformational,
+        Warning       = pr_sys::PrSDKErrorSuite3_kEventTypeWarning,
+        Error         = pr_sys::PrSDKErrorSuite3_kEventTypeError,
+    }
+}
+
+bitflags::bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct EventFlags: u32 {
+        const DECODE_ERROR      = pr_sys::PrSDKErrorSuite3_kEventFlag_DecodeError;
+        const SUBSTITUTED_FRAME = pr_sys::PrSDKErrorSuite3_kEventFlag_SubstitutedFrame;
+        const IMPORT_OPERATION  = pr_sys::PrSDKErrorSuite3_kEventFlag_ImportOperation;
+    }
+}
+
+impl ErrorSuite2 {
+    /// Acquire this suite from the host. Returns error if the suite is not available.
+    /// Suite is released on drop.
+    pub fn new() -> Result<Self, Error> {
+        crate::Suite::new()
+    }
+    /// Adds an event to the Events panel. Premiere copies the strings.
+    /// * `title` - Shown in the list of events
+    /// * `description` - Shown when the event is selected
+    pub fn set_event_string(&self, event_type: EventType, title: &str, description: &str) -> Result<(), Error> {
+        let title = CString::new(title).map_err(|_| Error::InvalidParms)?;
+        let description = CString::new(description).map_err(|_| Error::InvalidParms)?;
+        call_suite_fn!(self, SetEventString, pr_sys::PrSDKErrorSuite3__bindgen_ty_1::from(event_type), title.as_ptr(), description.as_ptr())
+    }
+}
+
+impl ErrorSuite3 {
+    /// Acquire this suite from the host. Returns error if the suite is not available.
+    /// Suite is released on drop.
+    pub fn new() -> Result<Self, Error> {
+        crate::Suite::new()
+    }
+    /// Adds an event to the Events panel. Premiere copies the strings.
+    /// * `flags` - What the event is about, or [`EventFlags::empty()`]
+    /// * `title` - Shown in the list of events
+    /// * `description` - Shown when the event is selected
+    pub fn set_event_string_unicode(&self, event_type: EventType, flags: EventFlags, title: &str, description: &str) -> Result<(), Error> {
+        let mut title = title.encode_utf16().chain(Some(0)).collect::<Vec<u16>>();
+        let mut description = description.encode_utf16().chain(Some(0)).collect::<Vec<u16>>();
+        let event_type = pr_sys::PrSDKErrorSuite3__bindgen_ty_1::from(event_type) | flags.bits();
+        call_suite_fn!(self, SetEventStringUnicode, event_type, title.as_mut_ptr(), description.as_mut_ptr())
+    }
+}
+
+/// A [`log::Log`] implementation which adds records to Premiere's Events panel.
+///
+/// Repeated messages are reported once per [`dedup_interval()`](Self::dedup_interval), and at most
+/// [`max_events()`](Self::max_events) events are reported per [`period()`](Self::period). The number of
+/// suppressed events is added to the next reported one.
+///
+/// Events can only be reported on threads where the host passed the suite pointers, like the plugin entry point;
+/// records from other threads are only passed to the [`forward_to()`](Self::forward_to) logger.
+///
+/// Example usage:
+/// ```ignore
+/// premiere::EventsLogger::new("My Plugin")
+///     .level(log::LevelFilter::Warn)
+///     .forward_to(Box::new(win_dbg_logger::DEBUGGER_LOGGER))
+///     .install();
+/// ```
+pub struct EventsLogger {
+    title: String,
+    level: log::LevelFilter,
+    dedup_interval: Duration,
+    max_events: u32,
+    period: Duration,
+    forward: Option<Box<dyn log::Log>>,
+    state: parking_lot::Mutex<LimiterState>,
+}
+
+struct LimiterState {
+    last_reported: HashMap<(log::Level, String), Instant>,
+    period_start: Option<Instant>,
+    reported_in_period: u32,
+    suppressed: u32,
+}
+
+impl EventsLogger {
+    /// Reports warnings and errors with `title`, usually the plugin name.
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.to_owned(),
+            level: log::LevelFilter::Warn,
+            dedup_interval: Duration::from_secs(10),
+            max_events: 10,
+            period: Duration::from_secs(1),
+            forward: None,
+            state: parking_lot::Mutex::new(LimiterState {
+                last_reported: HashMap::new(),
+                period_start: None,
+                reported_in_period: 0,
+                suppressed: 0,
+            }),
+        }
+    }
+    /// Least severe level reported to the Events panel, [`log::LevelFilter::Warn`] by default.
+    pub fn level(mut self, level: log::LevelFilter) -> Self {
+        self.level = level;
+        self
+    }
+    /// How long identical messages are suppressed for, 10 seconds by default.
+    pub fn dedup_interval(mut self, interval: Duration) -> Self {
+        self.dedup_interval = interval;
+        self
+    }
+    /// Maximum number of events reported per [`period()`](Self::period), 10 by default.
+    pub fn max_events(mut self, max_events: u32) -> Self {
+        self.max_events = max_events;
+        self
+    }
+    /// 1 second by default.
+    pub fn period(mut self, period: Duration) -> Self {
+        self.period = period;
+        self
+    }
+    /// Passes all records to `logger` as well, regardless of the level and limits of the Events panel.
+    pub fn forward_to(mut self, logger: Box<dyn log::Log>) -> Self {
+        self.forward = Some(logger);
+        self
+    }
+    /// Sets this as the global logger. Does nothing if a logger was already set.
+    pub fn install(self) {
+        let max_level = self.forward.as_ref().map_or(self.level, |_| log::LevelFilter::Trace);
+        if log::set_logger(Box::leak(Box::new(self))).is_ok() {
+            log::set_max_level(max_level);
+        }
+    }
+
+    /// Returns the number of previously suppressed events if the message should be reported now.
+    fn admit(&self, level: log::Level, message: &str, now: Instant) -> Option<u32> {
+        let mut state = self.state.lock();
+        let key = (level, message.to_owned());
+        if state.last_reported.get(&key).is_some_and(|&last| now.duration_since(last) < self.dedup_interval) {
+            return None;
+        }
+        if state.period_start.is_none_or(|start| now.duration_since(start) >= self.period) {
+            state.period_start = Some(now);
+            state.reported_in_period = 0;
+        }
+        if state.reported_in_period >= self.max_events {
+            state.suppressed += 1;
+            return None;
+        }
+        state.reported_in_period += 1;
+        if state.last_reported.len() >= 256 {
+            let dedup_interval = self.dedup_interval;
+            state.last_reported.retain(|_, &mut last| now.duration_since(last) < dedup_interval);
+        }
+        state.last_reported.insert(key, now);
+        Some(std::mem::take(&mut state.suppressed))
+    }
+}
+
+impl log::Log for EventsLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.level || self.forward.as_ref().is_some_and(|f| f.enabled(metadata))
+    }
+
+    fn log(&self, record: &log::Record) {
+        if let Some(forward) = &self.forward {
+            forward.log(record);
+        }
+        if record.level() > self.level {
+            return;
+        }
+        // Without the suite pointers on this thread there is nowhere to report to,
+        // so don't count the event against the limits either.
+        let Ok(suite) = ErrorSuite3::new() else {
+            return;
+        };
+        let message = record.args().to_string();
+        let Some(suppressed) = self.admit(record.level(), &message, Instant::now()) else {
+            return;
+        };
+        let event_type = match record.level() {
+            log::Level::Error => EventType::Error,
+            log::Level::Warn => EventType::Warning,
+            _ => EventType::Informational,
+        };
+        let description = if suppressed > 0 {
+            format!("{message}\n\n({suppressed} more events were suppressed)")
+        } else {
+            message
+        };
+        let _ = suite.set_event_string_unicode(event_type, EventFlags::empty(), &self.title, &description);
+    }
+
+    fn flush(&self) {
+        if let Some(forward) = &self.forward {
+            forward.flush();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_messages_are_deduplicated() {
+        let logger = EventsLogger::new("Test").dedup_interval(Duration::from_secs(10));
+        let start = Instant::now();
+        assert_eq!(logger.admit(log::Level::Error, "failed", start), Some(0));
+        assert_eq!(logger.admit(log::Level::Error, "failed", start + Duration::from_secs(5)), None);
+        assert_eq!(logger.admit(log::Level::Warn,  "failed", start + Duration::from_secs(5)), Some(0));
+        assert_eq!(logger.admit(log::Level::Error, "other",  start + Duration::from_secs(5)), Some(0));
+        assert_eq!(logger.admit(log::Level::Error, "failed", start + Duration::from_secs(10)), Some(0));
+    }
+
+    #[test]
+    fn events_are_capped_per_period() {
+        let logger = EventsLogger::new("Test").max_events(2).period(Duration::from_secs(1));
+        let start = Instant::now();
+        assert_eq!(logger.admit(log::Level::Error, "1", start), Some(0));
+        assert_eq!(logger.admit(log::Level::Error, "2", start), Some(0));
+        assert_eq!(logger.admit(log::Level::Error, "3", start), None);
+        assert_eq!(logger.admit(log::Level::Error, "4", start + Duration::from_millis(999)), None);
+        assert_eq!(logger.admit(log::Level::Error, "5", start + Duration::from_secs(1)), Some(2));
+    }
+
+    #[test]
+    fn suppressed_count_is_reported_once() {
+        let logger = EventsLogger::new("Test").max_events(1).period(Duration::from_secs(1));
+        let start = Instant::now();
+        assert_eq!(logger.admit(log::Level::Error, "1", start), Some(0));
+        assert_eq!(logger.admit(log::Level::Error, "2", start), None);
+        assert_eq!(logger.admit(log::Level::Error, "3", start), None);
+        // Carried over into the first event of a later period, then reset.
+        assert_eq!(logger.admit(log::Level::Error, "4", start + Duration::from_secs(1)), Some(2));
+        assert_eq!(logger.admit(log::Level::Error, "5", start + Duration::from_secs(2)), Some(0));
+        // Deduplicated messages aren't counted as suppressed.
+        assert_eq!(logger.admit(log::Level::Error, "5", start + Duration::from_secs(3)), None);
+        assert_eq!(logger.admit(log::Level::Error, "6", start + Duration::from_secs(3)), Some(0));
+    }
+}
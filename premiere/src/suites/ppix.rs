@@ -140,3 +140,83 @@ impl PPix2Suite {
         call_suite_fn_single!(self, GetFieldOrder -> pr_sys::prFieldType, ppix_handle)
     }
 }
+
+/// A PPix owned by the plugin, like the frames returned by [`SequenceRenderSuite`](crate::suites::SequenceRender)
+/// or created with [`PPixCreator2Suite`](crate::suites::PPixCreator2).
+///
+/// Disposed on drop, unless handed back to the host with [`into_raw()`](Self::into_raw).
+#[derive(Debug)]
+pub struct PPixFrame {
+    handle: pr_sys::PPixHand,
+    suite: PPixSuite,
+}
+impl PPixFrame {
+    /// Creates a writable frame with square pixels, see [`PPixCreator2Suite::create_ppix()`](crate::suites::PPixCreator2::create_ppix).
+    pub fn new(pixel_format: PixelFormat, width: i32, height: i32) -> Result<Self, Error> {
+        crate::suites::PPixCreator2::new()?.create_ppix(PPixBufferAccess::ReadWrite, pixel_format, width, height, None, (1, 1))
+    }
+    /// A read-only reference to the same pixels, see [`PPixCreatorSuite::clone_ppix()`](crate::suites::PPixCreator::clone_ppix).
+    pub fn clone_read_only(&self) -> Result<Self, Error> {
+        crate::suites::PPixCreator::new()?.clone_ppix(self, PPixBufferAccess::ReadOnly)
+    }
+    /// Takes ownership of `handle`, which is disposed with `suite`.
+    ///
+    /// Acquire the suite before creating the PPix, so the handle can't be leaked if that fails.
+    pub fn from_raw(handle: pr_sys::PPixHand, suite: PPixSuite) -> Result<Self, Error> {
+        if handle.is_null() {
+            return Err(Error::InvalidParms);
+        }
+        Ok(Self { handle, suite })
+    }
+    pub fn handle(&self) -> pr_sys::PPixHand {
+        self.handle
+    }
+    /// Releases ownership of the PPix, which will not be disposed.
+    pub fn into_raw(self) -> pr_sys::PPixHand {
+        let handle = self.handle;
+        std::mem::forget(self);
+        handle
+    }
+
+    /// See [`PPixSuite::pixels()`].
+    pub fn pixels(&self, requested_access: PPixBufferAccess) -> Result<*mut std::ffi::c_char, Error> {
+        self.suite.pixels(self.handle, requested_access)
+    }
+    pub fn bounds(&self) -> Result<pr_sys::prRect, Error> {
+        self.suite.bounds(self.handle)
+    }
+    pub fn width(&self) -> Result<u32, Error> {
+        self.bounds().map(|b| (b.right - b.left).max(0) as u32)
+    }
+    pub fn height(&self) -> Result<u32, Error> {
+        self.bounds().map(|b| (b.bottom - b.top).max(0) as u32)
+    }
+    /// May be negative.
+    pub fn row_bytes(&self) -> Result<i32, Error> {
+        self.suite.row_bytes(self.handle)
+    }
+    pub fn pixel_aspect_ratio(&self) -> Result<(u32, u32), Error> {
+        self.suite.pixel_aspect_ratio(self.handle)
+    }
+    pub fn pixel_format(&self) -> Result<PixelFormat, Error> {
+        self.suite.pixel_format(self.handle)
+    }
+    /// Total size in bytes, see [`PPix2Suite::size()`].
+    pub fn size(&self) -> Result<usize, Error> {
+        PPix2Suite::new()?.size(self.handle)
+    }
+    /// Planes of a frame in a planar YUV 4:2:0 format, see [`PPix2Suite::yuv420_planar_buffers()`].
+    pub fn yuv420_planar_buffers(&self, requested_access: PPixBufferAccess) -> Result<YUV420PlanarBuffers, Error> {
+        PPix2Suite::new()?.yuv420_planar_buffers(self.handle, requested_access)
+    }
+    pub fn field_type(&self) -> Result<FieldType, Error> {
+        Ok(PPix2Suite::new()?.field_order(self.handle)?.into())
+    }
+}
+impl Drop for PPixFrame {
+    fn drop(&mut self) {
+        if let Err(e) = self.suite.dispose(self.handle) {
+            log::error!("Failed to dispose PPix: {e:?}");
+        }
+    }
+}
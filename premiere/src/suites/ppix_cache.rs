@@ -0,0 +1,98 @@
+use crate::*;
+
+define_suite!(
+    /// Premiere's frame cache. Frames are added by importers for their own streams, or shared between plugins by name.
+    ///
+    /// The cache keeps its own reference to added frames, so they are still disposed by the caller.
+    /// Frames returned from the cache are new references owned by the caller.
+    PPixCacheSuite,
+    PrSDKPPixCacheSuite,
+    kPrSDKPPixCacheSuite,
+    kPrSDKPPixCacheSuiteVersion
+);
+
+/// A frame format accepted by [`PPixCacheSuite::frame_from_cache()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheFrameFormat {
+    pub width: i32,
+    pub height: i32,
+    pub pixel_format: PixelFormat,
+}
+impl From<CacheFrameFormat> for pr_sys::imFrameFormat {
+    fn from(format: CacheFrameFormat) -> Self {
+        Self {
+            inFrameWidth: format.width,
+            inFrameHeight: format.height,
+            inPixelFormat: format.pixel_format.into(),
+        }
+    }
+}
+
+fn frame_or_none(handle: pr_sys::PPixHand, ppix_suite: suites::PPix) -> Result<Option<PPixFrame>, Error> {
+    if handle.is_null() {
+        Ok(None)
+    } else {
+        PPixFrame::from_raw(handle, ppix_suite).map(Some)
+    }
+}
+
+impl PPixCacheSuite {
+    /// Acquire this suite from the host. Returns error if the suite is not available.
+    /// Suite is released on drop.
+    pub fn new() -> Result<Self, Error> {
+        crate::Suite::new()
+    }
+    /// Adds a frame of an importer stream to the cache.
+    /// * `importer_id` - The ID of this importer instance (passed down in imGetInfo8)
+    /// * `stream_index` - The stream of the frame
+    /// * `frame_number` - The frame number of the frame
+    /// * `preferences` - The importer preferences the frame was decoded with, empty if they don't affect the frame
+    pub fn add_frame_to_cache(&self, importer_id: u32, stream_index: i32, frame: &PPixFrame, frame_number: i32, preferences: &[u8]) -> Result<(), Error> {
+        call_suite_fn!(self, AddFrameToCache, importer_id, stream_index, frame.handle(), frame_number, preferences.as_ptr() as *mut _, preferences.len() as _)
+    }
+    /// Gets a frame of an importer stream added with [`add_frame_to_cache()`](Self::add_frame_to_cache), in one of `formats`.
+    ///
+    /// Returns `None` if the frame isn't cached.
+    pub fn frame_from_cache(&self, importer_id: u32, stream_index: i32, frame_number: i32, formats: &[CacheFrameFormat], preferences: &[u8]) -> Result<Option<PPixFrame>, Error> {
+        let mut formats: Vec<pr_sys::imFrameFormat> = formats.iter().map(|&f| f.into()).collect();
+        let ppix_suite = suites::PPix::new()?;
+        let mut handle: pr_sys::PPixHand = std::ptr::null_mut();
+        call_suite_fn!(self, GetFrameFromCache, importer_id, stream_index, frame_number, formats.len() as _, formats.as_mut_ptr(), &mut handle, preferences.as_ptr() as *mut _, preferences.len() as _)?;
+        frame_or_none(handle, ppix_suite)
+    }
+    /// Adds a raw ppix to the cache of an importer under `key`.
+    pub fn add_raw_ppix_to_cache(&self, importer_id: u32, frame: &PPixFrame, key: i32) -> Result<(), Error> {
+        call_suite_fn!(self, AddRawPPixToCache, importer_id, frame.handle(), key)
+    }
+    /// Gets a raw ppix added with [`add_raw_ppix_to_cache()`](Self::add_raw_ppix_to_cache).
+    ///
+    /// Returns `None` if the ppix isn't cached.
+    pub fn raw_ppix_from_cache(&self, importer_id: u32, key: i32) -> Result<Option<PPixFrame>, Error> {
+        let ppix_suite = suites::PPix::new()?;
+        frame_or_none(call_suite_fn_single!(self, GetRawPPixFromCache -> pr_sys::PPixHand, importer_id, key)?, ppix_suite)
+    }
+    /// Adds a ppix to the cache under a GUID, like a [`VideoSegmentSuite::hash()`](crate::suites::VideoSegment::hash), so other plugins can find it.
+    pub fn add_named_ppix_to_cache(&self, id: &pr_sys::prPluginID, frame: &PPixFrame) -> Result<(), Error> {
+        call_suite_fn!(self, AddNamedPPixToCache, id, frame.handle())
+    }
+    /// Gets a ppix added with [`add_named_ppix_to_cache()`](Self::add_named_ppix_to_cache).
+    ///
+    /// Returns `None` if the ppix isn't cached.
+    pub fn named_ppix_from_cache(&self, id: &pr_sys::prPluginID) -> Result<Option<PPixFrame>, Error> {
+        let ppix_suite = suites::PPix::new()?;
+        frame_or_none(call_suite_fn_single!(self, GetNamedPPixFromCache -> pr_sys::PPixHand, id)?, ppix_suite)
+    }
+    /// Keeps a named ppix in the cache until [`unregister_dependency_on_named_ppix()`](Self::unregister_dependency_on_named_ppix).
+    pub fn register_dependency_on_named_ppix(&self, id: &pr_sys::prPluginID) -> Result<(), Error> {
+        call_suite_fn!(self, RegisterDependencyOnNamedPPix, id)
+    }
+    pub fn unregister_dependency_on_named_ppix(&self, id: &pr_sys::prPluginID) -> Result<(), Error> {
+        call_suite_fn!(self, UnregisterDependencyOnNamedPPix, id)
+    }
+    pub fn expire_named_ppix_from_cache(&self, id: &pr_sys::prPluginID) -> Result<(), Error> {
+        call_suite_fn!(self, ExpireNamedPPixFromCache, id)
+    }
+    pub fn expire_all_ppixes_from_cache(&self) -> Result<(), Error> {
+        call_suite_fn!(self, ExpireAllPPixesFromCache,)
+    }
+}
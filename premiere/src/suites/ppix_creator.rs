@@ -0,0 +1,126 @@
+use crate::*;
+
+define_suite!(
+    /// Creates and clones PPixs.
+    PPixCreatorSuite,
+    PrSDKPPixCreatorSuite,
+    kPrSDKPPixCreatorSuite,
+    kPrSDKPPixCreatorSuiteVersion
+);
+define_suite!(
+    /// Creates PPixs with a given size and pixel aspect ratio, in custom pixel formats or as raw buffers.
+    PPixCreator2Suite,
+    PrSDKPPixCreator2Suite,
+    kPrSDKPPixCreator2Suite,
+    kPrSDKPPixCreator2SuiteVersion
+);
+
+impl PPixCreatorSuite {
+    /// Acquire this suite from the host. Returns error if the suite is not available.
+    /// Suite is released on drop.
+    pub fn new() -> Result<Self, Error> {
+        crate::Suite::new()
+    }
+    /// This will create a new ppix.
+    /// * `requested_access` - Requested pixel access. [`PPixBufferAccess::ReadOnly`] is not allowed
+    /// * `pixel_format` - The pixel format of this ppix
+    /// * `bounds` - The bounding rect of this ppix
+    pub fn create_ppix(&self, requested_access: PPixBufferAccess, pixel_format: PixelFormat, bounds: pr_sys::prRect) -> Result<PPixFrame, Error> {
+        let ppix_suite = suites::PPix::new()?;
+        let mut handle: pr_sys::PPixHand = std::ptr::null_mut();
+        call_suite_fn!(self, CreatePPix, &mut handle, requested_access.into(), pixel_format.into(), &bounds)?;
+        PPixFrame::from_raw(handle, ppix_suite)
+    }
+    /// This will clone an existing ppix. It will ref-count the ppix if only read-access is requested and
+    /// the ppix to copy from is read-only as well, otherwise it will create a new one and copy.
+    /// * `requested_access` - Requested pixel access on the new ppix. Only [`PPixBufferAccess::ReadOnly`] is allowed right now
+    pub fn clone_ppix(&self, ppix: &PPixFrame, requested_access: PPixBufferAccess) -> Result<PPixFrame, Error> {
+        let ppix_suite = suites::PPix::new()?;
+        let mut handle: pr_sys::PPixHand = std::ptr::null_mut();
+        call_suite_fn!(self, ClonePPix, ppix.handle(), &mut handle, requested_access.into())?;
+        PPixFrame::from_raw(handle, ppix_suite)
+    }
+}
+
+impl PPixCreator2Suite {
+    /// Acquire this suite from the host. Returns error if the suite is not available.
+    /// Suite is released on drop.
+    pub fn new() -> Result<Self, Error> {
+        crate::Suite::new()
+    }
+    /// This will create a new ppix, including planar YUV 4:2:0 ones.
+    /// * `requested_access` - Requested pixel access. [`PPixBufferAccess::ReadOnly`] is not allowed
+    /// * `pixel_format` - The pixel format of this ppix
+    /// * `width`, `height` - The size of the ppix
+    /// * `field` - If set, the ppix is the first (0) or second (1) field of a frame
+    /// * `pixel_aspect_ratio` - Numerator and denominator
+    pub fn create_ppix(&self, requested_access: PPixBufferAccess, pixel_format: PixelFormat, width: i32, height: i32, field: Option<i32>, pixel_aspect_ratio: (i32, i32)) -> Result<PPixFrame, Error> {
+        let ppix_suite = suites::PPix::new()?;
+        let mut handle: pr_sys::PPixHand = std::ptr::null_mut();
+        call_suite_fn!(self, CreatePPix, &mut handle,
+            requested_access.into(),
+            pixel_format.into(),
+            width,
+            height,
+            field.is_some(),
+            field.unwrap_or(0),
+            pixel_aspect_ratio.0,
+            pixel_aspect_ratio.1
+        )?;
+        PPixFrame::from_raw(handle, ppix_suite)
+    }
+    /// This will create a new raw ppix, an untyped buffer.
+    /// * `requested_access` - Requested pixel access. [`PPixBufferAccess::ReadOnly`] is not allowed
+    /// * `size` - The size of the ppix in bytes
+    /// * `alignment` - The alignment of the beginning of the ppix in bytes
+    pub fn create_raw_ppix(&self, requested_access: PPixBufferAccess, size: i32, alignment: i32) -> Result<PPixFrame, Error> {
+        let ppix_suite = suites::PPix::new()?;
+        let mut handle: pr_sys::PPixHand = std::ptr::null_mut();
+        call_suite_fn!(self, CreateRawPPix, &mut handle, requested_access.into(), size, alignment)?;
+        PPixFrame::from_raw(handle, ppix_suite)
+    }
+    /// This will create a new ppix in a custom pixel format.
+    /// * `requested_access` - Requested pixel access. [`PPixBufferAccess::ReadOnly`] is not allowed
+    /// * `pixel_format` - The custom pixel format, see [`custom_pixel_format()`]
+    /// * `width`, `height` - The size of the ppix
+    /// * `pixel_aspect_ratio` - Numerator and denominator
+    /// * `data_buffer_size` - The number of bytes requested for the pixel buffer
+    pub fn create_custom_ppix(&self, requested_access: PPixBufferAccess, pixel_format: pr_sys::PrPixelFormat, width: i32, height: i32, pixel_aspect_ratio: (i32, i32), data_buffer_size: i32) -> Result<PPixFrame, Error> {
+        let ppix_suite = suites::PPix::new()?;
+        let mut handle: pr_sys::PPixHand = std::ptr::null_mut();
+        call_suite_fn!(self, CreateCustomPPix, &mut handle,
+            requested_access.into(),
+            pixel_format,
+            width,
+            height,
+            pixel_aspect_ratio.0,
+            pixel_aspect_ratio.1,
+            data_buffer_size
+        )?;
+        PPixFrame::from_raw(handle, ppix_suite)
+    }
+    /// This will create a new ppix properly aligned for reading from disk.
+    /// * `memory_alignment` - The alignment of memory required for the start of the first sector
+    /// * `sector_size` - The size of a sector on disk
+    /// * `offset_to_pixel_data` - Offset from the start of the first sector to the pixel data
+    pub fn create_disk_aligned_ppix(&self, pixel_format: PixelFormat, width: i32, height: i32, pixel_aspect_ratio: (i32, i32), memory_alignment: i32, sector_size: i32, offset_to_pixel_data: i32) -> Result<PPixFrame, Error> {
+        let ppix_suite = suites::PPix::new()?;
+        let mut handle: pr_sys::PPixHand = std::ptr::null_mut();
+        call_suite_fn!(self, CreateDiskAlignedPPix, &mut handle,
+            pixel_format.into(),
+            width,
+            height,
+            pixel_aspect_ratio.0,
+            pixel_aspect_ratio.1,
+            memory_alignment,
+            sector_size,
+            offset_to_pixel_data
+        )?;
+        PPixFrame::from_raw(handle, ppix_suite)
+    }
+}
+
+/// Builds a custom pixel format from its four character code, like `MAKE_PIXEL_FORMAT_FOURCC` in the SDK.
+pub const fn custom_pixel_format(fourcc: [u8; 4]) -> pr_sys::PrPixelFormat {
+    u32::from_le_bytes(fourcc) as _
+}
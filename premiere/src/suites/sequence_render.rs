@@ -0,0 +1,348 @@
+use crate::*;
+
+define_suite!(
+    /// Renders frames of a sequence, synchronously or asynchronously.
+    ///
+    /// See [`VideoRenderer`] for an owned renderer with closure based async requests.
+    SequenceRenderSuite,
+    PrSDKSequenceRenderSuite,
+    kPrSDKSequenceRenderSuite,
+    kPrSDKSequenceRenderSuiteVersion
+);
+
+/// Parameters of a sequence frame render.
+///
+/// Example usage:
+/// ```ignore
+/// let params = SequenceRenderParams::new(1920, 1080)
+///     .pixel_formats(&[PixelFormat::Bgra4444_32f])
+///     .quality(RenderQuality::High)
+///     .field_type(FieldType::None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SequenceRenderParams {
+    pixel_formats: Vec<PixelFormat>,
+    width: u32,
+    height: u32,
+    pixel_aspect_ratio: (u32, u32),
+    quality: RenderQuality,
+    field_type: FieldType,
+    deinterlace: Option<RenderQuality>,
+    composite_on_black: bool,
+    cache_flags: pr_sys::PrRenderCacheType,
+}
+impl SequenceRenderParams {
+    /// Renders `width` x `height` frames in [`PixelFormat::Bgra4444_8u`], square pixels, high quality and progressive.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            pixel_formats: Vec::new(),
+            width,
+            height,
+            pixel_aspect_ratio: (1, 1),
+            quality: RenderQuality::High,
+            field_type: FieldType::None,
+            deinterlace: None,
+            composite_on_black: false,
+            cache_flags: 0,
+        }
+    }
+    /// Pixel formats in order of preference. [`PixelFormat::Bgra4444_8u`] is always accepted as the last resort.
+    pub fn pixel_formats(mut self, pixel_formats: &[PixelFormat]) -> Self {
+        self.pixel_formats = pixel_formats.to_vec();
+        self
+    }
+    pub fn pixel_aspect_ratio(mut self, numerator: u32, denominator: u32) -> Self {
+        self.pixel_aspect_ratio = (numerator, denominator);
+        self
+    }
+    pub fn quality(mut self, quality: RenderQuality) -> Self {
+        self.quality = quality;
+        self
+    }
+    pub fn field_type(mut self, field_type: FieldType) -> Self {
+        self.field_type = field_type;
+        self
+    }
+    /// Deinterlaces the frames with the given quality.
+    pub fn deinterlace(mut self, quality: RenderQuality) -> Self {
+        self.deinterlace = Some(quality);
+        self
+    }
+    /// Composites the frame on black instead of leaving transparent areas.
+    pub fn composite_on_black(mut self, composite_on_black: bool) -> Self {
+        self.composite_on_black = composite_on_black;
+        self
+    }
+    /// `kRenderCacheType_*` flags from `PrSDKRenderCacheType.h`, none by default.
+    pub fn cache_flags(mut self, cache_flags: pr_sys::PrRenderCacheType) -> Self {
+        self.cache_flags = cache_flags;
+        self
+    }
+
+    /// The requested pixel formats, which must end with [`PixelFormat::Bgra4444_8u`].
+    fn raw_pixel_formats(&self) -> Vec<pr_sys::PrPixelFormat> {
+        let mut formats: Vec<pr_sys::PrPixelFormat> = self.pixel_formats.iter().map(|&f| f.into()).collect();
+        if formats.last() != Some(&PixelFormat::Bgra4444_8u.into()) {
+            formats.push(PixelFormat::Bgra4444_8u.into());
+        }
+        formats
+    }
+
+    /// `formats` must be the result of [`raw_pixel_formats()`](Self::raw_pixel_formats) and outlive the returned struct.
+    fn to_raw(&self, formats: &[pr_sys::PrPixelFormat]) -> pr_sys::SequenceRender_ParamsRec {
+        pr_sys::SequenceRender_ParamsRec {
+            inRequestedPixelFormatArray: formats.as_ptr(),
+            inRequestedPixelFormatArrayCount: formats.len() as _,
+            inWidth: self.width as _,
+            inHeight: self.height as _,
+            inPixelAspectRatioNumerator: self.pixel_aspect_ratio.0 as _,
+            inPixelAspectRatioDenominator: self.pixel_aspect_ratio.1 as _,
+            inRenderQuality: self.quality.into(),
+            inFieldType: self.field_type.into(),
+            inDeinterlace: self.deinterlace.is_some() as _,
+            inDeinterlaceQuality: self.deinterlace.unwrap_or(self.quality).into(),
+            inCompositeOnBlack: self.composite_on_black as _,
+        }
+    }
+}
+
+/// A frame rendered by [`SequenceRenderSuite`].
+#[derive(Debug)]
+pub struct RenderedFrame {
+    pub frame: PPixFrame,
+    /// Number of following frames which are identical to this one.
+    pub repeat_count: i32,
+    /// Whether the frame is on a marker.
+    pub on_marker: bool,
+}
+impl RenderedFrame {
+    fn from_raw(ret: &pr_sys::SequenceRender_GetFrameReturnRec, frame: pr_sys::PPixHand, ppix_suite: suites::PPix) -> Result<Self, Error> {
+        let return_value = ret.returnVal;
+        if return_value != 0 {
+            if !frame.is_null() {
+                drop(PPixFrame::from_raw(frame, ppix_suite));
+            }
+            return Err(Error::from(return_value as pr_sys::prSuiteError));
+        }
+        Ok(Self {
+            frame: PPixFrame::from_raw(frame, ppix_suite)?,
+            repeat_count: ret.repeatCount,
+            on_marker: ret.onMarker != 0,
+        })
+    }
+}
+
+impl SequenceRenderSuite {
+    /// Acquire this suite from the host. Returns error if the suite is not available.
+    /// Suite is released on drop.
+    pub fn new() -> Result<Self, Error> {
+        crate::Suite::new()
+    }
+    /// Creates a renderer for the sequence the plugin is applied to.
+    /// * `plugin_id` - The plugin id, as passed by the host
+    /// * `frame_rate` - Frame rate of the renderer in ticks
+    ///
+    /// Returns the id of the video renderer, release it with [`release_video_renderer()`](Self::release_video_renderer).
+    pub fn make_video_renderer(&self, plugin_id: u32, frame_rate: pr_sys::PrTime) -> Result<u32, Error> {
+        let mut id = 0;
+        call_suite_fn!(self, MakeVideoRenderer, plugin_id, &mut id, frame_rate)?;
+        Ok(id)
+    }
+    pub fn release_video_renderer(&self, plugin_id: u32, video_renderer_id: u32) -> Result<(), Error> {
+        call_suite_fn!(self, ReleaseVideoRenderer, plugin_id, video_renderer_id)
+    }
+    /// Creates a renderer for any timeline, at the frame rate of the timeline if `frame_rate` is `None`.
+    ///
+    /// Returns the id of the video renderer, release it with [`release_video_renderer_for_timeline()`](Self::release_video_renderer_for_timeline).
+    pub fn make_video_renderer_for_timeline(&self, timeline_id: pr_sys::PrTimelineID, frame_rate: Option<pr_sys::PrTime>) -> Result<u32, Error> {
+        match frame_rate {
+            Some(frame_rate) => call_suite_fn_single!(self, MakeVideoRendererForTimelineWithFrameRate -> u32, timeline_id, frame_rate),
+            None => call_suite_fn_single!(self, MakeVideoRendererForTimeline -> u32, timeline_id),
+        }
+    }
+    /// Creates a renderer for a stream of the timeline, like the left or right eye of a stereoscopic sequence.
+    pub fn make_video_renderer_for_timeline_with_stream_label(&self, timeline_id: pr_sys::PrTimelineID, stream_label: &str) -> Result<u32, Error> {
+        let stream_label = std::ffi::CString::new(stream_label).map_err(|_| Error::InvalidParms)?;
+        call_suite_fn_single!(self, MakeVideoRendererForTimelineWithStreamLabel -> u32, timeline_id, stream_label.as_ptr())
+    }
+    pub fn release_video_renderer_for_timeline(&self, video_renderer_id: u32) -> Result<(), Error> {
+        call_suite_fn!(self, ReleaseVideoRendererForTimeline, video_renderer_id)
+    }
+    /// Renders a frame synchronously.
+    /// * `time` - The time of the frame, in ticks
+    pub fn render_video_frame(&self, video_renderer_id: u32, time: pr_sys::PrTime, params: &SequenceRenderParams) -> Result<RenderedFrame, Error> {
+        let formats = params.raw_pixel_formats();
+        let mut raw_params = params.to_raw(&formats);
+        let mut ret: pr_sys::SequenceRender_GetFrameReturnRec = unsafe { std::mem::zeroed() };
+        let ppix_suite = suites::PPix::new()?;
+        call_suite_fn!(self, RenderVideoFrame, video_renderer_id, time, &mut raw_params, params.cache_flags, &mut ret)?;
+        RenderedFrame::from_raw(&ret, ret.outFrame, ppix_suite)
+    }
+    /// Renders a frame synchronously and converts it to `conform_to` if none of the requested pixel formats is available.
+    pub fn render_video_frame_and_conform_to_pixel_format(&self, video_renderer_id: u32, time: pr_sys::PrTime, params: &SequenceRenderParams, conform_to: PixelFormat) -> Result<RenderedFrame, Error> {
+        let formats = params.raw_pixel_formats();
+        let mut raw_params = params.to_raw(&formats);
+        let mut ret: pr_sys::SequenceRender_GetFrameReturnRec = unsafe { std::mem::zeroed() };
+        let ppix_suite = suites::PPix::new()?;
+        call_suite_fn!(self, RenderVideoFrameAndConformToPixelFormat, video_renderer_id, time, &mut raw_params, params.cache_flags, conform_to.into(), &mut ret)?;
+        RenderedFrame::from_raw(&ret, ret.outFrame, ppix_suite)
+    }
+    /// Queues an asynchronous render. The completion proc set with [`set_async_render_completion_proc()`](Self::set_async_render_completion_proc)
+    /// is called with `completion_data` in the `asyncCompletionData` of the return struct.
+    ///
+    /// Returns the request id.
+    pub fn queue_async_video_frame_render(&self, video_renderer_id: u32, time: pr_sys::PrTime, params: &SequenceRenderParams, completion_data: *mut std::ffi::c_void) -> Result<u32, Error> {
+        let formats = params.raw_pixel_formats();
+        let mut raw_params = params.to_raw(&formats);
+        let mut request_id = 0;
+        call_suite_fn!(self, QueueAsyncVideoFrameRender, video_renderer_id, time, &mut request_id, &mut raw_params, params.cache_flags, completion_data)?;
+        Ok(request_id)
+    }
+    pub fn set_async_render_completion_proc(&self, video_renderer_id: u32, completion_proc: pr_sys::PrSDKSequenceAsyncRenderCompletionProc, callback_ref: *mut std::ffi::c_void) -> Result<(), Error> {
+        call_suite_fn!(self, SetAsyncRenderCompletionProc, video_renderer_id, completion_proc, callback_ref)
+    }
+    /// Returns the number of following frames which are identical to the frame at `time`.
+    pub fn frame_info(&self, video_renderer_id: u32, time: pr_sys::PrTime) -> Result<i32, Error> {
+        Ok(call_suite_fn_single!(self, GetFrameInfo -> pr_sys::SequenceRender_FrameInfoRec, video_renderer_id, time)?.repeatCount)
+    }
+    /// Hints the importers to begin reading the media needed to render the frame at `time`.
+    pub fn prefetch_media(&self, video_renderer_id: u32, time: pr_sys::PrTime) -> Result<(), Error> {
+        call_suite_fn!(self, PrefetchMedia, video_renderer_id, time)
+    }
+    /// Like [`prefetch_media()`](Self::prefetch_media), using all of the parameters used to render the frame.
+    pub fn prefetch_media_with_render_parameters(&self, video_renderer_id: u32, time: pr_sys::PrTime, params: &SequenceRenderParams) -> Result<(), Error> {
+        let formats = params.raw_pixel_formats();
+        let mut raw_params = params.to_raw(&formats);
+        call_suite_fn!(self, PrefetchMediaWithRenderParameters, video_renderer_id, time, &mut raw_params)
+    }
+    pub fn cancel_all_outstanding_media_prefetches(&self, video_renderer_id: u32) -> Result<(), Error> {
+        call_suite_fn!(self, CancelAllOutstandingMediaPrefetches, video_renderer_id)
+    }
+    pub fn is_prefetched_media_ready(&self, video_renderer_id: u32, time: pr_sys::PrTime) -> Result<bool, Error> {
+        Ok(call_suite_fn_single!(self, IsPrefetchedMediaReady -> pr_sys::prBool, video_renderer_id, time)? != 0)
+    }
+}
+
+type Completion = Box<dyn FnOnce(Result<RenderedFrame, Error>) + Send>;
+
+struct AsyncRequest {
+    pica_basic_suite_ptr: *const pr_sys::SPBasicSuite,
+    completion: Completion,
+}
+
+unsafe extern "C" fn async_render_completion(
+    _video_render_id: u32,
+    _callback_ref: *mut std::ffi::c_void,
+    _time: pr_sys::PrTime,
+    rendered_frame: pr_sys::PPixHand,
+    get_frame_return: *mut pr_sys::SequenceRender_GetFrameReturnRec,
+) {
+    if get_frame_return.is_null() {
+        log::error!("Async render completed without a return struct");
+        return;
+    }
+    let ret = unsafe { *get_frame_return };
+    if ret.asyncCompletionData.is_null() {
+        return;
+    }
+    let request = unsafe { Box::from_raw(ret.asyncCompletionData as *mut AsyncRequest) };
+    let _pica = PicaBasicSuite::from_sp_basic_suite_raw(request.pica_basic_suite_ptr);
+    let frame = if rendered_frame.is_null() { ret.outFrame } else { rendered_frame };
+    crate::catch_ffi_panic("async render completion", || (request.completion)(suites::PPix::new().and_then(|ppix_suite| RenderedFrame::from_raw(&ret, frame, ppix_suite))));
+}
+
+/// An owned video renderer, released on drop.
+///
+/// Example usage:
+/// ```ignore
+/// let renderer = VideoRenderer::for_timeline(timeline_id, None)?;
+/// let params = SequenceRenderParams::new(1920, 1080).pixel_formats(&[PixelFormat::Bgra4444_32f]);
+/// let frame = renderer.render(time, &params)?;
+/// renderer.render_async(time + ticks_per_frame, &params, |result| {
+///     if let Ok(rendered) = result {
+///         // Runs on a render thread
+///     }
+/// })?;
+/// ```
+pub struct VideoRenderer {
+    suite: SequenceRenderSuite,
+    id: u32,
+    plugin_id: Option<u32>,
+}
+impl VideoRenderer {
+    /// Renders the sequence the plugin is applied to, see [`SequenceRenderSuite::make_video_renderer()`].
+    pub fn new(plugin_id: u32, frame_rate: pr_sys::PrTime) -> Result<Self, Error> {
+        let suite = SequenceRenderSuite::new()?;
+        let id = suite.make_video_renderer(plugin_id, frame_rate)?;
+        Self::init(suite, id, Some(plugin_id))
+    }
+    /// Renders any timeline, at the frame rate of the timeline if `frame_rate` is `None`.
+    pub fn for_timeline(timeline_id: pr_sys::PrTimelineID, frame_rate: Option<pr_sys::PrTime>) -> Result<Self, Error> {
+        let suite = SequenceRenderSuite::new()?;
+        let id = suite.make_video_renderer_for_timeline(timeline_id, frame_rate)?;
+        Self::init(suite, id, None)
+    }
+    /// Renders a stream of a timeline, like the left or right eye of a stereoscopic sequence.
+    pub fn for_timeline_stream(timeline_id: pr_sys::PrTimelineID, stream_label: &str) -> Result<Self, Error> {
+        let suite = SequenceRenderSuite::new()?;
+        let id = suite.make_video_renderer_for_timeline_with_stream_label(timeline_id, stream_label)?;
+        Self::init(suite, id, None)
+    }
+    fn init(suite: SequenceRenderSuite, id: u32, plugin_id: Option<u32>) -> Result<Self, Error> {
+        let renderer = Self { suite, id, plugin_id };
+        renderer.suite.set_async_render_completion_proc(id, Some(async_render_completion), std::ptr::null_mut())?;
+        Ok(renderer)
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+    /// Renders the frame at `time`, in ticks.
+    pub fn render(&self, time: pr_sys::PrTime, params: &SequenceRenderParams) -> Result<RenderedFrame, Error> {
+        self.suite.render_video_frame(self.id, time, params)
+    }
+    /// Renders the frame at `time` and converts it to `conform_to` if none of the requested pixel formats is available.
+    pub fn render_conformed(&self, time: pr_sys::PrTime, params: &SequenceRenderParams, conform_to: PixelFormat) -> Result<RenderedFrame, Error> {
+        self.suite.render_video_frame_and_conform_to_pixel_format(self.id, time, params, conform_to)
+    }
+    /// Queues a render of the frame at `time`. `completion` is called once with the result, usually on another thread.
+    ///
+    /// Returns the request id. Requests still pending when the renderer is dropped never complete, and their closures are leaked.
+    pub fn render_async(&self, time: pr_sys::PrTime, params: &SequenceRenderParams, completion: impl FnOnce(Result<RenderedFrame, Error>) + Send + 'static) -> Result<u32, Error> {
+        let request = Box::into_raw(Box::new(AsyncRequest {
+            pica_basic_suite_ptr: borrow_pica_basic_as_ptr(),
+            completion: Box::new(completion),
+        }));
+        self.suite.queue_async_video_frame_render(self.id, time, params, request as *mut _).inspect_err(|_| {
+            drop(unsafe { Box::from_raw(request) });
+        })
+    }
+    /// Number of following frames which are identical to the frame at `time`.
+    pub fn repeat_count(&self, time: pr_sys::PrTime) -> Result<i32, Error> {
+        self.suite.frame_info(self.id, time)
+    }
+    /// Hints the importers to begin reading the media needed to render the frame at `time`, optionally with the parameters of the render.
+    pub fn prefetch(&self, time: pr_sys::PrTime, params: Option<&SequenceRenderParams>) -> Result<(), Error> {
+        match params {
+            Some(params) => self.suite.prefetch_media_with_render_parameters(self.id, time, params),
+            None => self.suite.prefetch_media(self.id, time),
+        }
+    }
+    pub fn cancel_prefetches(&self) -> Result<(), Error> {
+        self.suite.cancel_all_outstanding_media_prefetches(self.id)
+    }
+    pub fn is_prefetched_media_ready(&self, time: pr_sys::PrTime) -> Result<bool, Error> {
+        self.suite.is_prefetched_media_ready(self.id, time)
+    }
+}
+impl Drop for VideoRenderer {
+    fn drop(&mut self) {
+        let result = match self.plugin_id {
+            Some(plugin_id) => self.suite.release_video_renderer(plugin_id, self.id),
+            None => self.suite.release_video_renderer_for_timeline(self.id),
+        };
+        if let Err(e) = result {
+            log::error!("Failed to release video renderer: {e:?}");
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use crate::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+
+define_suite!(
+    /// Runs work on Premiere's worker threads, so it doesn't block the UI or compete with Premiere's own threads.
+    ///
+    /// See [`spawn()`](Self::spawn) for a closure based job API.
+    ThreadedWorkSuite,
+    PrSDKThreadedWorkSuiteVersion3,
+    kPrSDKThreadedWorkSuite,
+    kPrSDKThreadedWorkSuiteVersion3
+);
+
+impl ThreadedWorkSuite {
+    /// Acquire this suite from the host. Returns error if the suite is not available.
+    /// Suite is released on drop.
+    pub fn new() -> Result<Self, Error> {
+        crate::Suite::new()
+    }
+    /// Registers a callback which is called with `instance_data` each time work is queued.
+    ///
+    /// Returns the registration, to pass to [`queue_threaded_work()`](Self::queue_threaded_work).
+    pub fn register_for_threaded_work(&self, callback: pr_sys::ThreadedWorkCallbackVersion3, instance_data: *mut std::ffi::c_void) -> Result<pr_sys::ThreadedWorkRegistration, Error> {
+        call_suite_fn_single!(self, RegisterForThreadedWork -> pr_sys::ThreadedWorkRegistration, callback, instance_data)
+    }
+    /// Like [`register_for_threaded_work()`](Self::register_for_threaded_work), but the callback is never called on more than one thread at a time.
+    pub fn register_for_single_threaded_work(&self, callback: pr_sys::ThreadedWorkCallbackVersion3, instance_data: *mut std::ffi::c_void) -> Result<pr_sys::ThreadedWorkRegistration, Error> {
+        call_suite_fn_single!(self, RegisterForSingleThreadedWork -> pr_sys::ThreadedWorkRegistration, callback, instance_data)
+    }
+    /// Queues one call of the registered callback.
+    /// * `plugin_id` - Passed back to the callback
+    pub fn queue_threaded_work(&self, registration: pr_sys::ThreadedWorkRegistration, plugin_id: i32) -> Result<(), Error> {
+        call_suite_fn!(self, QueueThreadedWork, registration, plugin_id)
+    }
+    /// The callback will not be called anymore after this returns.
+    pub fn unregister_for_threaded_work(&self, registration: pr_sys::ThreadedWorkRegistration) -> Result<(), Error> {
+        call_suite_fn!(self, UnregisterForThreadedWork, registration)
+    }
+
+    /// Runs `job` once on a Premiere worker thread and returns a handle to wait for its result.
+    ///
+    /// `job` should check [`JobToken::is_cancelled()`] regularly if it runs for long.
+    ///
+    /// Example usage:
+    /// ```ignore
+    /// let job = suites::ThreadedWork::spawn(move |token| {
+    ///     let mut histogram = [0u32; 256];
+    ///     for frame in frames {
+    ///         if token.is_cancelled() { break; }
+    ///         accumulate(&mut histogram, frame);
+    ///     }
+    ///     histogram
+    /// })?;
+    /// // Later, from the UI thread
+    /// if let Some(Ok(histogram)) = job.try_result() { ... }
+    /// ```
+    pub fn spawn<T: Send + 'static>(job: impl FnOnce(&JobToken) -> T + Send + 'static) -> Result<JobHandle<T>, Error> {
+        let suite = Self::new()?;
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let work: Work = Box::new(move |token, status| {
+            if token.is_cancelled() {
+                return;
+            }
+            let result = if status != pr_sys::suiteError_NoError {
+                Err(Error::from(status))
+            } else {
+                crate::catch_ffi_panic("threaded work", || job(token)).ok_or(Error::Fail)
+            };
+            let _ = sender.send(result);
+        });
+        let shared = Arc::new(JobShared {
+            pica_basic_suite_ptr: borrow_pica_basic_as_ptr(),
+            token: JobToken { cancelled: AtomicBool::new(false) },
+            work: parking_lot::Mutex::new(Some(work)),
+            host_ref_released: AtomicBool::new(false),
+        });
+        // Premiere gets its own reference, so the job outlives the handle until it ran or was unregistered.
+        let host_ref = Arc::into_raw(shared.clone());
+        let registration = match suite.register_for_threaded_work(Some(run_job), host_ref as *mut _) {
+            Ok(registration) => registration,
+            Err(e) => {
+                drop(unsafe { Arc::from_raw(host_ref) });
+                return Err(e);
+            }
+        };
+        let handle = JobHandle { suite, registration, shared, receiver };
+        handle.suite.queue_threaded_work(registration, 0)?;
+        Ok(handle)
+    }
+}
+
+type Work = Box<dyn FnOnce(&JobToken, pr_sys::prSuiteError) + Send>;
+
+struct JobShared {
+    pica_basic_suite_ptr: *const pr_sys::SPBasicSuite,
+    token: JobToken,
+    work: parking_lot::Mutex<Option<Work>>,
+    /// Whether the reference handed to Premiere in `spawn()` was dropped.
+    host_ref_released: AtomicBool,
+}
+impl JobShared {
+    /// Drops the reference handed to Premiere, unless that already happened.
+    ///
+    /// # Safety
+    /// `ptr` must come from `spawn()` and Premiere must not call the job with it anymore.
+    unsafe fn release_host_ref(ptr: *const Self) {
+        if !unsafe { &*ptr }.host_ref_released.swap(true, Ordering::AcqRel) {
+            drop(unsafe { Arc::from_raw(ptr) });
+        }
+    }
+}
+// The basic suite is process-wide and can be used from any thread.
+unsafe impl Send for JobShared {}
+unsafe impl Sync for JobShared {}
+
+unsafe extern "C" fn run_job(instance_data: *mut std::ffi::c_void, _plugin_id: i32, status: pr_sys::prSuiteError) {
+    if instance_data.is_null() {
+        return;
+    }
+    let ptr = instance_data as *const JobShared;
+    // Keeps the job alive for this call, even if the handle is dropped meanwhile.
+    let shared = unsafe {
+        Arc::increment_strong_count(ptr);
+        Arc::from_raw(ptr)
+    };
+    if let Some(work) = shared.work.lock().take() {
+        let _pica = PicaBasicSuite::from_sp_basic_suite_raw(shared.pica_basic_suite_ptr);
+        work(&shared.token, status);
+    }
+    // The job is queued only once, so Premiere won't call it again.
+    unsafe { JobShared::release_host_ref(ptr) };
+}
+
+/// Passed to a job started with [`ThreadedWorkSuite::spawn()`].
+#[derive(Debug)]
+pub struct JobToken {
+    cancelled: AtomicBool,
+}
+impl JobToken {
+    /// Whether [`JobHandle::cancel()`] was called. The job should return as soon as possible.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+}
+
+/// A job started with [`ThreadedWorkSuite::spawn()`].
+///
+/// Dropping the handle cancels the job and unregisters it from Premiere.
+/// If unregistering fails, the job's state is leaked, because Premiere may still call it.
+pub struct JobHandle<T> {
+    suite: ThreadedWorkSuite,
+    registration: pr_sys::ThreadedWorkRegistration,
+    shared: Arc<JobShared>,
+    receiver: Receiver<Result<T, Error>>,
+}
+impl<T> JobHandle<T> {
+    /// Asks the job to stop. Jobs which haven't started yet never run.
+    pub fn cancel(&self) {
+        self.shared.token.cancelled.store(true, Ordering::Release);
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.shared.token.is_cancelled()
+    }
+    /// The channel the result is sent through, for use with `select`-like loops.
+    ///
+    /// It is disconnected without a result if the job was cancelled before it started.
+    pub fn receiver(&self) -> &Receiver<Result<T, Error>> {
+        &self.receiver
+    }
+    /// The result of the job if it finished, without blocking.
+    pub fn try_result(&self) -> Option<Result<T, Error>> {
+        self.receiver.try_recv().ok()
+    }
+    /// Waits for the job to finish.
+    ///
+    /// Returns `None` if the job was cancelled before it started.
+    pub fn join(self) -> Result<Option<T>, Error> {
+        match self.receiver.recv() {
+            Ok(result) => result.map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+}
+impl<T> Drop for JobHandle<T> {
+    fn drop(&mut self) {
+        self.cancel();
+        match self.suite.unregister_for_threaded_work(self.registration) {
+            // Premiere won't call the job anymore, so its reference can go if the job never ran.
+            Ok(()) => unsafe { JobShared::release_host_ref(Arc::as_ptr(&self.shared)) },
+            Err(e) => log::error!("Failed to unregister threaded work, leaking the job: {e:?}"),
+        }
+    }
+}
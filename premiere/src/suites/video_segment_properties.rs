@@ -2,9 +2,10 @@
 
 use crate::*;
 use pr_sys::*;
-use std::{ops::{Deref, DerefMut}, str::FromStr};
+use serde::{Deserialize, Serialize};
+use std::{fmt::{self, Display}, ops::{Deref, DerefMut}, str::FromStr};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PropertyData {
     Int32(i32),
     Int64(i64),
@@ -18,11 +19,42 @@ pub enum PropertyData {
     Point32(Point32),
     Time(PrTime),
     Keyframes(Keyframes),
+    FrameRate(FrameRate),
+    FieldType(FieldType),
+    ScalePolicy(ScalePolicy),
+    ColorSpace(ColorSpace),
+    StreamLabel(StreamLabel),
     Unknown(String),
 }
 
+/// Formats the value the way Premiere does, so parsing the result gives the same value.
+impl Display for PropertyData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Int32(v)       => v.fmt(f),
+            Self::Int64(v)       => v.fmt(f),
+            Self::UInt32(v)      => v.fmt(f),
+            Self::USize(v)       => v.fmt(f),
+            Self::Float32(v)     => v.fmt(f),
+            Self::Float64(v)     => v.fmt(f),
+            Self::Bool(v)        => v.fmt(f),
+            Self::String(v)      => v.fmt(f),
+            Self::Binary(v)      => v.fmt(f),
+            Self::Point32(v)     => v.fmt(f),
+            Self::Time(v)        => v.fmt(f),
+            Self::Keyframes(v)   => v.fmt(f),
+            Self::FrameRate(v)   => v.fmt(f),
+            Self::FieldType(v)   => pr_sys::prFieldType::from(*v).fmt(f),
+            Self::ScalePolicy(v) => v.fmt(f),
+            Self::ColorSpace(v)  => v.fmt(f),
+            Self::StreamLabel(v) => v.fmt(f),
+            Self::Unknown(v)     => v.fmt(f),
+        }
+    }
+}
+
 // ------------------- Float point -------------------
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Point32 { pub x: f32, pub y: f32 }
 
 impl FromStr for Point32 {
@@ -34,9 +66,14 @@ impl FromStr for Point32 {
         Ok(Point32 { x, y })
     }
 }
+impl Display for Point32 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.x, self.y)
+    }
+}
 // ------------------- Float point -------------------
 // ------------------- Binary data -------------------
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Binary(Vec<u8>);
 impl FromStr for Binary {
     type Err = Error;
@@ -45,6 +82,12 @@ impl FromStr for Binary {
         Ok(Binary(BASE64_STANDARD.decode(s).map_err(|_| Error::InvalidParms)?))
     }
 }
+impl Display for Binary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use base64::prelude::*;
+        f.write_str(&BASE64_STANDARD.encode(&self.0))
+    }
+}
 impl Deref for Binary {
     type Target = Vec<u8>;
     fn deref(&self) -> &Self::Target { &self.0 }
@@ -54,21 +97,181 @@ impl DerefMut for Binary {
 }
 // ------------------- Binary data -------------------
 // -------------------- Keyframes --------------------
-#[derive(Debug, Clone)]
-#[allow(dead_code)]
-pub struct Keyframes(pub String); // TODO: not implemented
+/// Serialized parameters and keyframes of an effect, a transition or the time remapping of a clip.
+///
+/// The format is private to Premiere and not documented in the SDK, so the string is kept as is. Read the values with
+/// [`VideoSegmentSuite::param()`](crate::suites::VideoSegment::param) and
+/// [`VideoSegmentSuite::next_keyframe_time()`](crate::suites::VideoSegment::next_keyframe_time) instead.
+/// This includes the position, scale, rotation and anchor point of a clip, which are parameters of its intrinsic Motion effect node
+/// rather than properties. Neither this string nor the parameter layout of the Motion effect
+/// is decoded yet, as both have to be verified against a running Premiere first.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keyframes(pub String);
 
 impl FromStr for Keyframes {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self(s.to_owned())) // TODO implement parsing this
+        Ok(Self(s.to_owned()))
+    }
+}
+impl Display for Keyframes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
     }
 }
 // -------------------- Keyframes --------------------
+// -------------------- Frame rate -------------------
+/// The duration of a frame in ticks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FrameRate(pub PrTime);
+impl FrameRate {
+    /// Ticks per second, as returned by [`TimeSuite::ticks_per_second()`](crate::suites::Time::ticks_per_second).
+    pub const TICKS_PER_SECOND: PrTime = 254016000000;
+
+    pub fn frames_per_second(&self) -> f64 {
+        Self::TICKS_PER_SECOND as f64 / self.0 as f64
+    }
+}
+impl FromStr for FrameRate {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Self).map_err(|_| Error::InvalidParms)
+    }
+}
+impl Display for FrameRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+// -------------------- Frame rate -------------------
+// -------------------- Field type -------------------
+impl FromStr for FieldType {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<prFieldType>().map(Self::from).map_err(|_| Error::InvalidParms)
+    }
+}
+// -------------------- Field type -------------------
+// ------------------- Scale policy ------------------
+/// How a clip is scaled to the sequence frame size, `PrNodeScalePolicy` in the SDK.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ScalePolicy {
+    None,
+    ScaleToFrame,
+    ScaleToFillCrop,
+    ScaleToFillDistort,
+    Unknown(PrNodeScalePolicy),
+}
+impl FromStr for ScalePolicy {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.parse::<PrNodeScalePolicy>().map_err(|_| Error::InvalidParms)? {
+            PrNodeScalePolicy_kPrNodeScalePolicy_None               => Self::None,
+            PrNodeScalePolicy_kPrNodeScalePolicy_ScaleToFrame       => Self::ScaleToFrame,
+            PrNodeScalePolicy_kPrNodeScalePolicy_ScaleToFillCrop    => Self::ScaleToFillCrop,
+            PrNodeScalePolicy_kPrNodeScalePolicy_ScaleToFillDistort => Self::ScaleToFillDistort,
+            v => Self::Unknown(v),
+        })
+    }
+}
+impl Display for ScalePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None               => PrNodeScalePolicy_kPrNodeScalePolicy_None,
+            Self::ScaleToFrame       => PrNodeScalePolicy_kPrNodeScalePolicy_ScaleToFrame,
+            Self::ScaleToFillCrop    => PrNodeScalePolicy_kPrNodeScalePolicy_ScaleToFillCrop,
+            Self::ScaleToFillDistort => PrNodeScalePolicy_kPrNodeScalePolicy_ScaleToFillDistort,
+            Self::Unknown(v)         => *v,
+        }.fmt(f)
+    }
+}
+// ------------------- Scale policy ------------------
+// ------------------- Color space -------------------
+macro_rules! define_named_values {
+    ($(#[$attr:meta])* $name:ident { $($variant:ident = $id:ident,)* }) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+        pub enum $name {
+            $( $variant, )*
+            Other(String),
+        }
+        impl FromStr for $name {
+            type Err = Error;
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                $(
+                    if s.as_bytes() == &$id[..$id.len() - 1] { return Ok(Self::$variant); }
+                )*
+                Ok(Self::Other(s.to_owned()))
+            }
+        }
+        impl Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let id: &[u8] = match self {
+                    $( Self::$variant => &$id[..$id.len() - 1], )*
+                    Self::Other(s) => return f.write_str(s),
+                };
+                f.write_str(&String::from_utf8_lossy(id))
+            }
+        }
+    };
+}
+
+define_named_values! {
+    /// A color space by the name Premiere uses in video segment properties.
+    ColorSpace {
+        Srgb                    = kPrSRGBColorSpace,
+        Rec601Ntsc              = kPrRec601525ColorSpace,
+        Rec601Pal               = kPrRec601625ColorSpace,
+        Rec709                  = kPrRec709,
+        Rec709Scene             = kPrRec709Scene,
+        Rec709Rgb               = kPrRec709RGB,
+        Rec709RgbScene          = kPrRec709RGBScene,
+        Rec709RgbFull           = kPrOverranged709,
+        Rec709RgbFullScene      = kPrOverranged709Scene,
+        Rec709RgbFullDisplay    = kPrOverranged709Display,
+        Rec2020                 = kPrRec2020,
+        Rec2020Scene            = kPrRec2020Scene,
+        Rec2020Rgb              = kPrRec2020RGB,
+        Rec2020RgbScene         = kPrRec2020RGBScene,
+        Rec2020RgbFull          = kPrOverranged2020,
+        Rec2020RgbFullScene     = kPrOverranged2020Scene,
+        Rec2020RgbFullDisplay   = kPrOverranged2020Display,
+        Rec2100Hlg              = kPrRec2100HLG,
+        Rec2100HlgScene         = kPrRec2100HLGScene,
+        Rec2100HlgRgb           = kPrRec2100HLGRGB,
+        Rec2100HlgRgbScene      = kPrRec2100HLGRGBScene,
+        Rec2100HlgRgbFull       = kPrOverranged2100HLG,
+        Rec2100HlgRgbFullScene  = kPrOverranged2100HLGScene,
+        Rec2100HlgRgbFullDisplay = kPrOverranged2100HLGDisplay,
+        Rec2100Pq               = kPrRec2100PQ,
+        Rec2100PqScene          = kPrRec2100PQScene,
+        Rec2100PqRgb            = kPrRec2100PQRGB,
+        Rec2100PqRgbScene       = kPrRec2100PQRGBScene,
+        Rec2100PqRgbFull        = kPrOverranged2100PQ,
+        Rec2100PqRgbFullScene   = kPrOverranged2100PQScene,
+        Rec2100PqRgbFullDisplay = kPrOverranged2100PQDisplay,
+        DcdmXyz                 = kPrDCDMXYZ,
+        SonySGamutSLog2         = kPrSonySGamutSLog2,
+        Rec2020SLog3            = kPrSony2020SLog3,
+        SonySGamut3CineSLog3    = kPrSonySGamut3CineSLog3,
+        SonySGamut3SLog3        = kPrSonySGamut3SLog3,
+        Working                 = kPrWorkingColorSpace,
+    }
+}
+// ------------------- Color space -------------------
+// ------------------- Stream label ------------------
+define_named_values! {
+    /// The label of a media stream, like one eye of stereoscopic media. Empty for the main stream.
+    StreamLabel {
+        StereoscopicLeft  = kPrSDK_StreamLabel_Stereoscopic_Left,
+        StereoscopicRight = kPrSDK_StreamLabel_Stereoscopic_Right,
+    }
+}
+// ------------------- Stream label ------------------
 
 macro_rules! define_properties {
     ($(($field:ident, $ty:ident, $prop:ident),)*) => {
-        #[derive(Debug, Clone)]
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
         #[allow(non_camel_case_types)]
         pub enum Property {
             $( $field, )*
@@ -110,13 +313,13 @@ define_properties! {
     (Media_ProxyInstanceString,                      String,        kVideoSegmentProperty_Media_ProxyInstanceString),
     (Media_ImplementationID,                         String,        kVideoSegmentProperty_Media_ImplementationID),
     (Media_StreamGroup,                              USize,         kVideoSegmentProperty_Media_StreamGroup),
-    (Media_StreamLabel,                              String,        kVideoSegmentProperty_Media_StreamLabel),
+    (Media_StreamLabel,                              StreamLabel,   kVideoSegmentProperty_Media_StreamLabel),
     (Media_IsDraft,                                  Bool,          kVideoSegmentProperty_Media_IsDraft),
     (Media_ModState,                                 Binary,        kVideoSegmentProperty_Media_ModState),
     (Media_IsOffline,                                Bool,          kVideoSegmentProperty_Media_IsOffline),
     (Media_IsPending,                                Bool,          kVideoSegmentProperty_Media_IsPending),
     (Media_CaptioningID,                             String,        kVideoSegmentProperty_Media_CaptioningID),
-    (Media_StreamFrameRate,                          Time,          kVideoSegmentProperty_Media_StreamFrameRate),
+    (Media_StreamFrameRate,                          FrameRate,     kVideoSegmentProperty_Media_StreamFrameRate),
     (Media_StreamAlphaType,                          Int32,         kVideoSegmentProperty_Media_StreamAlphaType),
     (Media_StreamIgnoreAlpha,                        Bool,          kVideoSegmentProperty_Media_StreamIgnoreAlpha),
     (Media_StreamInvertAlpha,                        Bool,          kVideoSegmentProperty_Media_StreamInvertAlpha),
@@ -126,7 +329,7 @@ define_properties! {
     (Media_StreamFrameWidth,                         Int32,         kVideoSegmentProperty_Media_StreamFrameWidth),
     (Media_StreamFrameHeight,                        Int32,         kVideoSegmentProperty_Media_StreamFrameHeight),
     (Media_StreamPixelAspectRatioDen,                Int32,         kVideoSegmentProperty_Media_StreamPixelAspectRatioDen),
-    (Media_StreamFieldType,                          Int32,         kVideoSegmentProperty_Media_StreamFieldType),
+    (Media_StreamFieldType,                          FieldType,     kVideoSegmentProperty_Media_StreamFieldType),
     (Media_StreamOpaqueData,                         Binary,        kVideoSegmentProperty_Media_StreamOpaqueData),
     (Media_ProxyStreamOpaqueData,                    Binary,        kVideoSegmentProperty_Media_ProxyStreamOpaqueData),
     (Media_StreamPullDownCadence,                    Int32,         kVideoSegmentProperty_Media_StreamPullDownCadence),
@@ -151,19 +354,19 @@ define_properties! {
     (Media_StreamFrameBlend,                         Bool,          kVideoSegmentProperty_Media_StreamFrameBlend),
     (Media_StreamTimeInterpolationType,              UInt32,        kVideoSegmentProperty_Media_StreamTimeInterpolationType), // dvamediatypes::TimeInterpolationType
     (Media_ClipScaleToFrameSize,                     Bool,          kVideoSegmentProperty_Media_ClipScaleToFrameSize),
-    (Media_ClipScaleToFramePolicy,                   Int32,         kVideoSegmentProperty_Media_ClipScaleToFramePolicy), // int, optional see PrNodeScalePolicy
+    (Media_ClipScaleToFramePolicy,                   ScalePolicy,   kVideoSegmentProperty_Media_ClipScaleToFramePolicy), // optional
     (Media_StreamReverseFieldDominance,              Bool,          kVideoSegmentProperty_Media_StreamReverseFieldDominance),
     (Media_DeinterlaceAlways,                        Bool,          kVideoSegmentProperty_Media_DeinterlaceAlways),
     (Media_RemoveFlicker,                            Bool,          kVideoSegmentProperty_Media_RemoveFlicker),
     (Media_InterlaceConsecutiveFrames,               Bool,          kVideoSegmentProperty_Media_InterlaceConsecutiveFrames),
-    (Media_SequenceColorSpace,                       String,        kVideoSegmentProperty_Media_SequenceColorSpace),
-    (Media_StreamColorSpace,                         String,        kVideoSegmentProperty_Media_StreamColorSpace),
+    (Media_SequenceColorSpace,                       ColorSpace,    kVideoSegmentProperty_Media_SequenceColorSpace),
+    (Media_StreamColorSpace,                         ColorSpace,    kVideoSegmentProperty_Media_StreamColorSpace),
     (Media_StreamInputLUTID,                         String,        kVideoSegmentProperty_Media_StreamInputLUTID),
     (Media_ScanlineOffsetToImproveVerticalCentering, Int32,         kVideoSegmentProperty_Media_ScanlineOffsetToImproveVerticalCentering), // positive values mean shift up, negative means shift down
     (Media_InPointMediaTimeAsTicks,                  Int64,         kVideoSegmentProperty_Media_InPointMediaTimeAsTicks),                  // media in point in units of ticks in media time
     (Media_OutPointMediaTimeAsTicks,                 Int64,         kVideoSegmentProperty_Media_OutPointMediaTimeAsTicks),                 // media out point in units of ticks in media time
-    (Media_SequenceFieldType,                        Int32,         kVideoSegmentProperty_Media_SequenceFieldType),                        // containing sequence field type
-    (Media_SequenceFrameRate,                        Int32,         kVideoSegmentProperty_Media_SequenceFrameRate),                        // containing sequence frame rate
+    (Media_SequenceFieldType,                        FieldType,     kVideoSegmentProperty_Media_SequenceFieldType),                        // containing sequence field type
+    (Media_SequenceFrameRate,                        FrameRate,     kVideoSegmentProperty_Media_SequenceFrameRate),                        // containing sequence frame rate
     (Media_SequenceWidth,                            Int32,         kVideoSegmentProperty_Media_SequenceWidth),                            // containing sequence width
     (Media_SequenceHeight,                           Int32,         kVideoSegmentProperty_Media_SequenceHeight),                           // containing sequence height
     (Media_SequencePixelAspectRatioNum,              Int32,         kVideoSegmentProperty_Media_SequencePixelAspectRatioNum),              // containing sequence pixel aspect ratio num
@@ -207,7 +410,7 @@ define_properties! {
     (Effect_RuntimeInstanceID,                       UInt32,        kVideoSegmentProperty_Effect_RuntimeInstanceID),
     (Effect_LayerInputHashes,                        String,        kVideoSegmentProperty_Effect_LayerInputHashes),
     (Effect_RuntimeHash,                             String,        kVideoSegmentProperty_Effect_RuntimeHash),
-    (Effect_StreamLabel,                             String,        kVideoSegmentProperty_Effect_StreamLabel),
+    (Effect_StreamLabel,                             StreamLabel,   kVideoSegmentProperty_Effect_StreamLabel),
     (Effect_ClipName,                                String,        kVideoSegmentProperty_Effect_ClipName),
     (Effect_MasterClipName,                          String,        kVideoSegmentProperty_Effect_MasterClipName),
     (Effect_FileName,                                String,        kVideoSegmentProperty_Effect_FileName),
@@ -234,3 +437,80 @@ define_properties! {
     (Adjustment_MediaIsOpaque,                       Bool,          kVideoSegmentProperty_Adjustment_AdjustmentMediaIsOpaque),
     (Adjustment_InvertAlpha,                         Bool,          kVideoSegmentProperty_Adjustment_InvertAlpha),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(key: &[u8], value: &str) -> PropertyData {
+        Property::from_id(&key[..key.len() - 1]).parse_result(value)
+    }
+
+    /// Value of a string constant from the SDK headers.
+    fn sdk_str(constant: &[u8]) -> String {
+        String::from_utf8(constant[..constant.len() - 1].to_vec()).unwrap()
+    }
+
+    fn assert_round_trips(key: &[u8], value: &str) {
+        let decoded = decode(key, value);
+        assert!(!matches!(decoded, PropertyData::Unknown(_)), "{} = {value:?} was not decoded", String::from_utf8_lossy(key));
+        assert_eq!(decoded.to_string(), value);
+    }
+
+    /// Values built from the SDK constants that define each format, as no samples captured from Premiere are available.
+    #[test]
+    fn typed_values_round_trip() {
+        const TICKS_PER_SECOND: i64 = 254016000000;
+        let samples: &[(&[u8], String)] = &[
+            (kVideoSegmentProperty_Media_StreamFrameRate,        (TICKS_PER_SECOND * 1001 / 24000).to_string()),
+            (kVideoSegmentProperty_Media_SequenceFrameRate,      (TICKS_PER_SECOND / 30).to_string()),
+            (kVideoSegmentProperty_Media_StreamFieldType,        i32::from(FieldType::None).to_string()),
+            (kVideoSegmentProperty_Media_SequenceFieldType,      i32::from(FieldType::LowerFirst).to_string()),
+            (kVideoSegmentProperty_Media_ClipScaleToFramePolicy, PrNodeScalePolicy_kPrNodeScalePolicy_ScaleToFillDistort.to_string()),
+            (kVideoSegmentProperty_Media_StreamColorSpace,       sdk_str(kPrRec2100PQ)),
+            (kVideoSegmentProperty_Media_SequenceColorSpace,     sdk_str(kPrRec709)),
+            (kVideoSegmentProperty_Media_StreamLabel,            sdk_str(kPrSDK_StreamLabel_Stereoscopic_Left)),
+            (kVideoSegmentProperty_Effect_StreamLabel,           String::new()),
+        ];
+        for (key, value) in samples {
+            assert_round_trips(key, value);
+        }
+    }
+
+    /// The plain value types, whose string format isn't specific to a property.
+    #[test]
+    fn plain_values_round_trip() {
+        assert_round_trips(kVideoSegmentProperty_Effect_FilterOpaqueData,               "AAECAwQF");
+        assert_round_trips(kVideoSegmentProperty_Effect_FilterMatchName,                "AE.ADBE Gaussian Blur 2");
+        assert_round_trips(kVideoSegmentProperty_Transition_TransitionCenterPosition,  "0.5 0.25");
+        assert_round_trips(kVideoSegmentProperty_Media_ClipSpeed,                       "1.5");
+        assert_round_trips(kVideoSegmentProperty_Media_IsOffline,                       "false");
+        assert_round_trips(kVideoSegmentProperty_Clip_TrackItemStartAsTicks,            "-914457600000");
+    }
+
+    #[test]
+    fn decodes_documented_types() {
+        let PropertyData::FrameRate(rate) = decode(kVideoSegmentProperty_Media_StreamFrameRate, "10594584000") else { panic!() };
+        assert!((rate.frames_per_second() - 23.976).abs() < 0.001);
+        assert!(matches!(decode(kVideoSegmentProperty_Media_SequenceFieldType, "1"), PropertyData::FieldType(FieldType::UpperFirst)));
+        assert!(matches!(decode(kVideoSegmentProperty_Media_ClipScaleToFramePolicy, "2"), PropertyData::ScalePolicy(ScalePolicy::ScaleToFillCrop)));
+        assert!(matches!(decode(kVideoSegmentProperty_Media_StreamColorSpace, &sdk_str(kPrRec2100HLGScene)), PropertyData::ColorSpace(ColorSpace::Rec2100HlgScene)));
+        assert!(matches!(decode(kVideoSegmentProperty_Media_StreamLabel, &sdk_str(kPrSDK_StreamLabel_Stereoscopic_Right)), PropertyData::StreamLabel(StreamLabel::StereoscopicRight)));
+        let PropertyData::Binary(data) = decode(kVideoSegmentProperty_Effect_FilterOpaqueData, "AAECAwQF") else { panic!() };
+        assert_eq!(&data[..], &[0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn unknown_names_are_kept() {
+        let value = decode(kVideoSegmentProperty_Media_StreamColorSpace, "ARRI LogC3");
+        assert!(matches!(&value, PropertyData::ColorSpace(ColorSpace::Other(s)) if s == "ARRI LogC3"));
+        assert_eq!(value.to_string(), "ARRI LogC3");
+        assert!(matches!(decode(kVideoSegmentProperty_Media_ClipScaleToFramePolicy, "7"), PropertyData::ScalePolicy(ScalePolicy::Unknown(7))));
+        assert!(matches!(decode(kVideoSegmentProperty_Media_StreamFieldType, "upper"), PropertyData::Unknown(_)));
+        for raw in ["3", "-1", "7"] {
+            let value = decode(kVideoSegmentProperty_Media_StreamFieldType, raw);
+            assert!(matches!(value, PropertyData::FieldType(FieldType::Unknown(_))), "{raw}");
+            assert_eq!(value.to_string(), raw);
+        }
+    }
+}
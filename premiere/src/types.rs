@@ -32,6 +32,37 @@ define_enum! {
     }
 }
 
+/// Field order of a frame, `prFieldType` in the SDK.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum FieldType {
+    /// Progressive
+    None,
+    UpperFirst,
+    LowerFirst,
+    /// `prFieldsUnknown`, or any other value, kept so it converts back unchanged.
+    Unknown(pr_sys::prFieldType),
+}
+impl From<FieldType> for pr_sys::prFieldType {
+    fn from(v: FieldType) -> Self {
+        match v {
+            FieldType::None       => 0,
+            FieldType::UpperFirst => 1,
+            FieldType::LowerFirst => 2,
+            FieldType::Unknown(v) => v,
+        }
+    }
+}
+impl From<pr_sys::prFieldType> for FieldType {
+    fn from(v: pr_sys::prFieldType) -> Self {
+        match v {
+            0 => Self::None,
+            1 => Self::UpperFirst,
+            2 => Self::LowerFirst,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
 define_enum! {
     pr_sys::PrPlaybackQuality,
     PlaybackQuality {
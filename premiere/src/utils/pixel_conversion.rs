@@ -0,0 +1,1187 @@
+use crate::*;
+
+/// How alpha is stored in an RGBA `f32` buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alpha {
+    /// Color is independent of alpha.
+    Straight,
+    /// Color is multiplied by alpha.
+    Premultiplied,
+}
+
+/// Pixel formats supported by [`unpack()`] and [`pack()`]: the packed RGB and YUV formats.
+///
+/// Adding all of them with `PixelFormatSuite::add_supported_pixel_format()` lets one RGBA `f32` render path serve them, and Premiere
+/// converts from the other formats. The planar and biplanar YUV formats are in [`SUPPORTED_YUV420_PIXEL_FORMATS`].
+/// Not supported are the planar RGB formats, the compressed DV formats and the GPU formats.
+pub const SUPPORTED_PIXEL_FORMATS: &[PixelFormat] = &[
+    PixelFormat::Bgra4444_32f,
+    PixelFormat::Bgrp4444_32f,
+    PixelFormat::Bgrx4444_32f,
+    PixelFormat::Argb4444_32f,
+    PixelFormat::Prgb4444_32f,
+    PixelFormat::Xrgb4444_32f,
+    PixelFormat::Vuya4444_32f,
+    PixelFormat::Vuya4444_32f709,
+    PixelFormat::Vuyp4444_32f,
+    PixelFormat::Vuyp4444_32f709,
+    PixelFormat::Vuyx4444_32f,
+    PixelFormat::Vuyx4444_32f709,
+    PixelFormat::Bgra4444_32fLinear,
+    PixelFormat::Bgrp4444_32fLinear,
+    PixelFormat::Bgrx4444_32fLinear,
+    PixelFormat::Argb4444_32fLinear,
+    PixelFormat::Prgb4444_32fLinear,
+    PixelFormat::Xrgb4444_32fLinear,
+    PixelFormat::Bgra4444_16u,
+    PixelFormat::Bgrp4444_16u,
+    PixelFormat::Bgrx4444_16u,
+    PixelFormat::Argb4444_16u,
+    PixelFormat::Prgb4444_16u,
+    PixelFormat::Xrgb4444_16u,
+    PixelFormat::Vuya4444_16u,
+    PixelFormat::Bgra4444_8u,
+    PixelFormat::Bgrp4444_8u,
+    PixelFormat::Bgrx4444_8u,
+    PixelFormat::Argb4444_8u,
+    PixelFormat::Prgb4444_8u,
+    PixelFormat::Xrgb4444_8u,
+    PixelFormat::Vuya4444_8u,
+    PixelFormat::Vuya4444_8u709,
+    PixelFormat::Vuyp4444_8u,
+    PixelFormat::Vuyp4444_8u709,
+    PixelFormat::Vuyx4444_8u,
+    PixelFormat::Vuyx4444_8u709,
+    PixelFormat::Uyvy422_32f601,
+    PixelFormat::Uyvy422_32f709,
+    PixelFormat::Yuyv422_8u601,
+    PixelFormat::Yuyv422_8u709,
+    PixelFormat::Uyvy422_8u601,
+    PixelFormat::Uyvy422_8u709,
+    PixelFormat::V210422_10u601,
+    PixelFormat::V210422_10u709,
+    PixelFormat::V210422_10u709FullRange,
+    PixelFormat::Y210422_10uAs16u709,
+    PixelFormat::Y210422_10uAs16u709FullRange,
+    PixelFormat::Rgb444_10u,
+    PixelFormat::Rgb444_12uPq709,
+    PixelFormat::Rgb444_12uPqP3,
+    PixelFormat::Rgb444_12uPq2020,
+];
+
+/// Whether `format` can be converted with [`unpack()`] and [`pack()`].
+pub fn is_supported(format: PixelFormat) -> bool {
+    Format::describe(format).is_some()
+}
+
+/// The number of bytes `width` pixels of `format` take, without row padding.
+pub fn row_size(format: PixelFormat, width: usize) -> Result<usize, Error> {
+    Ok(Format::describe(format).ok_or(Error::RenderInvalidPixelFormat)?.row_size(width))
+}
+
+/// Converts one row of `format` to RGBA `f32`. The row is `dst.len()` pixels wide.
+///
+/// YUV is converted with the matrix and range of the format. The transfer function is kept, so linear formats give linear RGB,
+/// and the PQ formats give PQ encoded RGB.
+///
+/// Formats without alpha give an alpha of 1. Subsampled chroma is shared by the pixels it covers.
+pub fn unpack_row(format: PixelFormat, src: &[u8], dst: &mut [[f32; 4]], alpha: Alpha) -> Result<(), Error> {
+    let format = Format::describe(format).ok_or(Error::RenderInvalidPixelFormat)?;
+    if src.len() < format.row_size(dst.len()) {
+        return Err(Error::InvalidParms);
+    }
+    format.unpack_row(src, dst, alpha);
+    Ok(())
+}
+
+/// Converts one row of RGBA `f32` to `format`. The row is `src.len()` pixels wide.
+///
+/// Integer formats are clamped to their range, float formats are written as is. Formats without alpha drop it.
+/// Chroma of subsampled formats is averaged over the pixels it covers.
+pub fn pack_row(format: PixelFormat, src: &[[f32; 4]], dst: &mut [u8], alpha: Alpha) -> Result<(), Error> {
+    let format = Format::describe(format).ok_or(Error::RenderInvalidPixelFormat)?;
+    if dst.len() < format.row_size(src.len()) {
+        return Err(Error::InvalidParms);
+    }
+    format.pack_row(src, dst, alpha);
+    Ok(())
+}
+
+/// Converts a frame of `format` to RGBA `f32`, see [`unpack_row()`].
+/// * `src` - The pixels of the frame, with rows `row_bytes` apart
+/// * `dst` - Rows of `width` pixels without padding. The frame is as high as `dst` has rows
+///
+/// Example usage:
+/// ```ignore
+/// let mut rgba = vec![[0.0; 4]; width * height];
+/// pixel_conversion::unpack(pixel_format, input, row_bytes, &mut rgba, width, Alpha::Premultiplied)?;
+/// process(&mut rgba);
+/// pixel_conversion::pack(pixel_format, &rgba, width, output, row_bytes, Alpha::Premultiplied)?;
+/// ```
+pub fn unpack(format: PixelFormat, src: &[u8], row_bytes: usize, dst: &mut [[f32; 4]], width: usize, alpha: Alpha) -> Result<(), Error> {
+    let format = Format::describe(format).ok_or(Error::RenderInvalidPixelFormat)?;
+    if width == 0 {
+        return Ok(());
+    }
+    let size = format.row_size(width);
+    check_frame_size(src.len(), row_bytes, size, dst.len() / width)?;
+    for (y, row) in dst.chunks_exact_mut(width).enumerate() {
+        format.unpack_row(&src[y * row_bytes..][..size], row, alpha);
+    }
+    Ok(())
+}
+
+/// Converts a frame of RGBA `f32` to `format`, see [`pack_row()`].
+/// * `src` - Rows of `width` pixels without padding. The frame is as high as `src` has rows
+/// * `dst` - The pixels of the frame, with rows `row_bytes` apart. Row padding is left untouched
+pub fn pack(format: PixelFormat, src: &[[f32; 4]], width: usize, dst: &mut [u8], row_bytes: usize, alpha: Alpha) -> Result<(), Error> {
+    let format = Format::describe(format).ok_or(Error::RenderInvalidPixelFormat)?;
+    if width == 0 {
+        return Ok(());
+    }
+    let size = format.row_size(width);
+    check_frame_size(dst.len(), row_bytes, size, src.len() / width)?;
+    for (y, row) in src.chunks_exact(width).enumerate() {
+        format.pack_row(row, &mut dst[y * row_bytes..][..size], alpha);
+    }
+    Ok(())
+}
+
+/// Pixel formats supported by [`unpack_yuv420()`] and [`pack_yuv420()`]: the planar and biplanar YUV 4:2:0 formats,
+/// and the biplanar 4:2:2 `P210`.
+pub const SUPPORTED_YUV420_PIXEL_FORMATS: &[PixelFormat] = &[
+    PixelFormat::Yuv420Mpeg2FramePicturePlanar8u601,
+    PixelFormat::Yuv420Mpeg2FieldPicturePlanar8u601,
+    PixelFormat::Yuv420Mpeg2FramePicturePlanar8u601FullRange,
+    PixelFormat::Yuv420Mpeg2FieldPicturePlanar8u601FullRange,
+    PixelFormat::Yuv420Mpeg2FramePicturePlanar8u709,
+    PixelFormat::Yuv420Mpeg2FieldPicturePlanar8u709,
+    PixelFormat::Yuv420Mpeg2FramePicturePlanar8u709FullRange,
+    PixelFormat::Yuv420Mpeg2FieldPicturePlanar8u709FullRange,
+    PixelFormat::Yuv420Mpeg4FramePicturePlanar8u601,
+    PixelFormat::Yuv420Mpeg4FieldPicturePlanar8u601,
+    PixelFormat::Yuv420Mpeg4FramePicturePlanar8u601FullRange,
+    PixelFormat::Yuv420Mpeg4FieldPicturePlanar8u601FullRange,
+    PixelFormat::Yuv420Mpeg4FramePicturePlanar8u709,
+    PixelFormat::Yuv420Mpeg4FieldPicturePlanar8u709,
+    PixelFormat::Yuv420Mpeg4FramePicturePlanar8u709FullRange,
+    PixelFormat::Yuv420Mpeg4FieldPicturePlanar8u709FullRange,
+    PixelFormat::Yuv420Mpeg4FieldPictureBiplanar8u601,
+    PixelFormat::Yuv420Mpeg4FramePictureBiplanar8u601,
+    PixelFormat::Yuv420Mpeg4FieldPictureBiplanar8u601FullRange,
+    PixelFormat::Yuv420Mpeg4FramePictureBiplanar8u601FullRange,
+    PixelFormat::Yuv420Mpeg4FieldPictureBiplanar8u709,
+    PixelFormat::Yuv420Mpeg4FramePictureBiplanar8u709,
+    PixelFormat::Yuv420Mpeg4FieldPictureBiplanar8u709FullRange,
+    PixelFormat::Yuv420Mpeg4FramePictureBiplanar8u709FullRange,
+    PixelFormat::Yvu420Mpeg4FieldPictureBiplanar8u601,
+    PixelFormat::Yvu420Mpeg4FramePictureBiplanar8u601,
+    PixelFormat::Yvu420Mpeg4FieldPictureBiplanar8u601FullRange,
+    PixelFormat::Yvu420Mpeg4FramePictureBiplanar8u601FullRange,
+    PixelFormat::Yvu420Mpeg4FieldPictureBiplanar8u709,
+    PixelFormat::Yvu420Mpeg4FramePictureBiplanar8u709,
+    PixelFormat::Yvu420Mpeg4FieldPictureBiplanar8u709FullRange,
+    PixelFormat::Yvu420Mpeg4FramePictureBiplanar8u709FullRange,
+    PixelFormat::Yuv420Mpeg4FieldPictureBiplanar10uAs16u709,
+    PixelFormat::Yuv420Mpeg4FramePictureBiplanar10uAs16u709,
+    PixelFormat::Yuv420Mpeg4FieldPictureBiplanar10uAs16u709FullRange,
+    PixelFormat::Yuv420Mpeg4FramePictureBiplanar10uAs16u709FullRange,
+    PixelFormat::Yuv420Mpeg4FieldPictureBiplanar10uAs16u2020,
+    PixelFormat::Yuv420Mpeg4FramePictureBiplanar10uAs16u2020,
+    PixelFormat::Yuv420Mpeg4FieldPictureBiplanar10uAs16u2020FullRange,
+    PixelFormat::Yuv420Mpeg4FramePictureBiplanar10uAs16u2020FullRange,
+    PixelFormat::Yuv420Mpeg4FieldPictureBiplanar10uAs16u2020HDR,
+    PixelFormat::Yuv420Mpeg4FramePictureBiplanar10uAs16u2020HDR,
+    PixelFormat::Yuv420Mpeg4FieldPictureBiplanar10uAs16u2020HDRFullRange,
+    PixelFormat::Yuv420Mpeg4FramePictureBiplanar10uAs16u2020HDRFullRange,
+    PixelFormat::Yuv420Mpeg4FieldPictureBiplanar10uAs16u2020HDRHLG,
+    PixelFormat::Yuv420Mpeg4FramePictureBiplanar10uAs16u2020HDRHLG,
+    PixelFormat::Yuv420Mpeg4FieldPictureBiplanar10uAs16u2020HDRHLGFullRange,
+    PixelFormat::Yuv420Mpeg4FramePictureBiplanar10uAs16u2020HDRHLGFullRange,
+    PixelFormat::P210422_10u709,
+];
+
+/// Whether `format` can be converted with [`unpack_yuv420()`] and [`pack_yuv420()`].
+pub fn is_supported_yuv420(format: PixelFormat) -> bool {
+    PlanarFormat::describe(format).is_some()
+}
+
+/// Planes of a frame in one of the [`SUPPORTED_YUV420_PIXEL_FORMATS`].
+///
+/// `B` is `&[u8]` for [`unpack_yuv420()`] and `&mut [u8]` for [`pack_yuv420()`].
+pub struct Yuv420Planes<B> {
+    /// Luma at full resolution, with rows `y_row_bytes` apart.
+    pub y: B,
+    pub y_row_bytes: usize,
+    /// Cb at half resolution, or Cb and Cr interleaved in the biplanar formats (Cr first in the `Yvu` ones).
+    pub u: B,
+    pub u_row_bytes: usize,
+    /// Cr at half resolution. Not used by the biplanar formats.
+    pub v: B,
+    pub v_row_bytes: usize,
+}
+
+impl Yuv420Planes<&[u8]> {
+    /// The planes of a frame of `width` x `height` pixels in `format`, e.g. from [`PPixFrame::yuv420_planar_buffers()`].
+    ///
+    /// # Safety
+    /// The buffers must hold such a frame, and stay valid and unchanged while the planes are used.
+    pub unsafe fn from_buffers(buffers: &YUV420PlanarBuffers, format: PixelFormat, width: usize, height: usize) -> Result<Self, Error> {
+        let [y, u, v] = PlanarFormat::describe(format).ok_or(Error::RenderInvalidPixelFormat)?.plane_sizes(buffers, width, height)?;
+        unsafe {
+            Ok(Self {
+                y: std::slice::from_raw_parts(buffers.y_data as *const u8, y),
+                y_row_bytes: buffers.y_row_bytes as usize,
+                u: std::slice::from_raw_parts(buffers.u_data as *const u8, u),
+                u_row_bytes: buffers.u_row_bytes as usize,
+                v: if v == 0 { &[] } else { std::slice::from_raw_parts(buffers.v_data as *const u8, v) },
+                v_row_bytes: buffers.v_row_bytes as usize,
+            })
+        }
+    }
+}
+
+impl Yuv420Planes<&mut [u8]> {
+    /// The planes of a frame of `width` x `height` pixels in `format`, see [`Yuv420Planes::from_buffers()`].
+    ///
+    /// # Safety
+    /// The buffers must hold such a frame, be writable and not be accessed otherwise while the planes are used.
+    pub unsafe fn from_buffers_mut(buffers: &YUV420PlanarBuffers, format: PixelFormat, width: usize, height: usize) -> Result<Self, Error> {
+        let [y, u, v] = PlanarFormat::describe(format).ok_or(Error::RenderInvalidPixelFormat)?.plane_sizes(buffers, width, height)?;
+        unsafe {
+            Ok(Self {
+                y: std::slice::from_raw_parts_mut(buffers.y_data as *mut u8, y),
+                y_row_bytes: buffers.y_row_bytes as usize,
+                u: std::slice::from_raw_parts_mut(buffers.u_data as *mut u8, u),
+                u_row_bytes: buffers.u_row_bytes as usize,
+                v: if v == 0 { &mut [] } else { std::slice::from_raw_parts_mut(buffers.v_data as *mut u8, v) },
+                v_row_bytes: buffers.v_row_bytes as usize,
+            })
+        }
+    }
+}
+
+/// Converts a frame of a planar or biplanar YUV `format` to RGBA `f32`, like [`unpack()`].
+/// * `dst` - Rows of `width` pixels without padding. The frame is as high as `dst` has rows
+///
+/// Chroma is shared by the 2x2 pixels it covers, or by 2x1 pixels in `P210`. In the field picture formats, the chroma rows
+/// alternate between the fields, so chroma covers two rows of the same field. The transfer function is kept, so the HDR formats give
+/// PQ encoded RGB and the HLG formats HLG encoded RGB. Alpha is 1.
+///
+/// Example usage:
+/// ```ignore
+/// let buffers = frame.yuv420_planar_buffers(PPixBufferAccess::ReadOnly)?;
+/// let planes = unsafe { Yuv420Planes::from_buffers(&buffers, pixel_format, width, height)? };
+/// let mut rgba = vec![[0.0; 4]; width * height];
+/// pixel_conversion::unpack_yuv420(pixel_format, &planes, &mut rgba, width)?;
+/// ```
+pub fn unpack_yuv420(format: PixelFormat, src: &Yuv420Planes<&[u8]>, dst: &mut [[f32; 4]], width: usize) -> Result<(), Error> {
+    let format = PlanarFormat::describe(format).ok_or(Error::RenderInvalidPixelFormat)?;
+    if width == 0 {
+        return Ok(());
+    }
+    let height = dst.len() / width;
+    format.check_planes([src.y.len(), src.u.len(), src.v.len()], [src.y_row_bytes, src.u_row_bytes, src.v_row_bytes], width, height)?;
+    for (y, row) in dst.chunks_exact_mut(width).enumerate() {
+        format.unpack_row(src, y, row);
+    }
+    Ok(())
+}
+
+/// Converts a frame of RGBA `f32` to a planar or biplanar YUV `format`, see [`unpack_yuv420()`].
+/// * `src` - Rows of `width` pixels without padding. The frame is as high as `src` has rows
+///
+/// Chroma is averaged over the pixels it covers. Alpha is dropped. Row padding is left untouched.
+pub fn pack_yuv420(format: PixelFormat, src: &[[f32; 4]], width: usize, dst: &mut Yuv420Planes<&mut [u8]>) -> Result<(), Error> {
+    let format = PlanarFormat::describe(format).ok_or(Error::RenderInvalidPixelFormat)?;
+    if width == 0 {
+        return Ok(());
+    }
+    let height = src.len() / width;
+    format.check_planes([dst.y.len(), dst.u.len(), dst.v.len()], [dst.y_row_bytes, dst.u_row_bytes, dst.v_row_bytes], width, height)?;
+    format.pack(src, width, height, dst);
+    Ok(())
+}
+
+fn check_frame_size(len: usize, row_bytes: usize, row_size: usize, height: usize) -> Result<(), Error> {
+    if height > 0 && (row_bytes < row_size || len < (height - 1) * row_bytes + row_size) {
+        return Err(Error::InvalidParms);
+    }
+    Ok(())
+}
+
+/// Pixels converted at once when packing. A multiple of the 6 pixel groups of v210.
+const CHUNK: usize = 48;
+
+#[derive(Clone, Copy)]
+enum Sample {
+    U8,
+    U16,
+    /// 10 bits in the high bits of 16.
+    U16Msb10,
+    F32,
+}
+impl Sample {
+    fn size(self) -> usize {
+        match self {
+            Self::U8 => 1,
+            Self::U16 | Self::U16Msb10 => 2,
+            Self::F32 => 4,
+        }
+    }
+    fn max(self) -> Option<f32> {
+        match self {
+            Self::U8 => Some(255.0),
+            Self::U16 => Some(32768.0),
+            Self::U16Msb10 => Some(1023.0),
+            Self::F32 => None,
+        }
+    }
+    #[inline(always)]
+    fn read(self, bytes: &[u8]) -> f32 {
+        match self {
+            Self::U8 => bytes[0] as f32,
+            Self::U16 => u16::from_le_bytes([bytes[0], bytes[1]]) as f32,
+            Self::U16Msb10 => (u16::from_le_bytes([bytes[0], bytes[1]]) >> 6) as f32,
+            Self::F32 => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        }
+    }
+    /// `value` is already quantized.
+    #[inline(always)]
+    fn write(self, bytes: &mut [u8], value: f32) {
+        match self {
+            Self::U8 => bytes[0] = value as u8,
+            Self::U16 => bytes[..2].copy_from_slice(&(value as u16).to_le_bytes()),
+            Self::U16Msb10 => bytes[..2].copy_from_slice(&((value as u16) << 6).to_le_bytes()),
+            Self::F32 => bytes[..4].copy_from_slice(&value.to_le_bytes()),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Layout {
+    /// Four components per pixel. `order` is the position of R or Y, G or Cb, B or Cr, and alpha.
+    Interleaved { sample: Sample, order: [usize; 4] },
+    /// Two pixels share chroma. `order` is the position of Y0, Cb, Y1 and Cr.
+    Subsampled { sample: Sample, order: [usize; 4] },
+    /// Six pixels in four little-endian 32-bit words of three 10-bit components: Cb0 Y0 Cr0, Y1 Cb1 Y2, Cr1 Y3 Cb2, Y4 Cr2 Y5.
+    V210,
+    /// A little-endian 32-bit word per pixel, with 10-bit R, G and B from bit 20 down.
+    Rgb10,
+    /// 12-bit R, G and B in little-endian 16-bit words.
+    Rgb12,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum StoredAlpha {
+    Straight,
+    Premultiplied,
+    Opaque,
+}
+
+/// Code values of a component are `offset + normalized * scale`.
+#[derive(Clone, Copy)]
+struct Range {
+    offset: f32,
+    scale: f32,
+}
+const fn range(offset: f32, scale: f32) -> Range {
+    Range { offset, scale }
+}
+
+/// Y' in 0..1 and Cb, Cr in -0.5..0.5 from R'G'B' in 0..1.
+#[derive(Clone, Copy)]
+struct Matrix {
+    kr: f32,
+    kb: f32,
+}
+const BT601: Matrix = Matrix { kr: 0.299, kb: 0.114 };
+const BT709: Matrix = Matrix { kr: 0.2126, kb: 0.0722 };
+const BT2020: Matrix = Matrix { kr: 0.2627, kb: 0.0593 };
+
+impl Matrix {
+    #[inline(always)]
+    fn yuv_to_rgb(self, [y, cb, cr, a]: [f32; 4]) -> [f32; 4] {
+        let r = y + 2.0 * (1.0 - self.kr) * cr;
+        let b = y + 2.0 * (1.0 - self.kb) * cb;
+        let g = (y - self.kr * r - self.kb * b) / (1.0 - self.kr - self.kb);
+        [r, g, b, a]
+    }
+    #[inline(always)]
+    fn rgb_to_yuv(self, [r, g, b, a]: [f32; 4]) -> [f32; 4] {
+        let y = self.kr * r + (1.0 - self.kr - self.kb) * g + self.kb * b;
+        [y, (b - y) / (2.0 * (1.0 - self.kb)), (r - y) / (2.0 * (1.0 - self.kr)), a]
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Format {
+    layout: Layout,
+    /// Ranges of R or Y, G or Cb, B or Cr, and alpha.
+    ranges: [Range; 4],
+    /// Set for YUV formats.
+    matrix: Option<Matrix>,
+    alpha: StoredAlpha,
+    /// Largest code value of integer formats.
+    max: Option<f32>,
+}
+
+const BGRA: [usize; 4] = [2, 1, 0, 3];
+const ARGB: [usize; 4] = [1, 2, 3, 0];
+/// V U Y A puts Y, Cb and Cr where BGRA puts R, G and B.
+const VUYA: [usize; 4] = BGRA;
+const YUYV: [usize; 4] = [0, 1, 2, 3];
+const UYVY: [usize; 4] = [1, 0, 3, 2];
+
+impl Format {
+    fn rgb(order: [usize; 4], sample: Sample, alpha: StoredAlpha) -> Self {
+        let max = sample.max();
+        Self {
+            layout: Layout::Interleaved { sample, order },
+            ranges: [range(0.0, max.unwrap_or(1.0)); 4],
+            matrix: None,
+            alpha,
+            max,
+        }
+    }
+    fn yuv(order: [usize; 4], sample: Sample, matrix: Matrix, alpha: StoredAlpha) -> Self {
+        let max = sample.max();
+        let (luma, chroma) = match sample {
+            Sample::U8 => (range(16.0, 219.0), range(128.0, 224.0)),
+            Sample::U16 => (range(2048.0, 28032.0), range(16384.0, 28672.0)),
+            Sample::U16Msb10 => (range(64.0, 876.0), range(512.0, 896.0)),
+            Sample::F32 => (range(0.0, 1.0), range(0.0, 1.0)),
+        };
+        Self {
+            layout: Layout::Interleaved { sample, order },
+            ranges: [luma, chroma, chroma, range(0.0, max.unwrap_or(1.0))],
+            matrix: Some(matrix),
+            alpha,
+            max,
+        }
+    }
+    fn subsampled(order: [usize; 4], sample: Sample, matrix: Matrix, full_range: bool) -> Self {
+        let mut format = Self::yuv(order, sample, matrix, StoredAlpha::Opaque);
+        format.layout = Layout::Subsampled { sample, order };
+        if full_range {
+            format = format.full_range();
+        }
+        format
+    }
+    fn v210(matrix: Matrix, full_range: bool) -> Self {
+        let format = Self { layout: Layout::V210, ..Self::yuv(YUYV, Sample::U16Msb10, matrix, StoredAlpha::Opaque) };
+        if full_range { format.full_range() } else { format }
+    }
+    /// Full range YUV of an integer format.
+    fn full_range(mut self) -> Self {
+        let max = self.max.unwrap_or(1.0);
+        let half = ((max + 1.0) / 2.0).floor();
+        self.ranges[0] = range(0.0, max);
+        self.ranges[1] = range(half, max);
+        self.ranges[2] = range(half, max);
+        self
+    }
+    fn rgb_packed(layout: Layout, max: f32) -> Self {
+        Self {
+            layout,
+            ranges: [range(0.0, max); 4],
+            matrix: None,
+            alpha: StoredAlpha::Opaque,
+            max: Some(max),
+        }
+    }
+
+    fn describe(format: PixelFormat) -> Option<Self> {
+        use PixelFormat as P;
+        use Sample::*;
+        use StoredAlpha::*;
+        Some(match format {
+            P::Bgra4444_8u  => Self::rgb(BGRA, U8, Straight),
+            P::Bgrp4444_8u  => Self::rgb(BGRA, U8, Premultiplied),
+            P::Bgrx4444_8u  => Self::rgb(BGRA, U8, Opaque),
+            P::Argb4444_8u  => Self::rgb(ARGB, U8, Straight),
+            P::Prgb4444_8u  => Self::rgb(ARGB, U8, Premultiplied),
+            P::Xrgb4444_8u  => Self::rgb(ARGB, U8, Opaque),
+            P::Bgra4444_16u => Self::rgb(BGRA, U16, Straight),
+            P::Bgrp4444_16u => Self::rgb(BGRA, U16, Premultiplied),
+            P::Bgrx4444_16u => Self::rgb(BGRA, U16, Opaque),
+            P::Argb4444_16u => Self::rgb(ARGB, U16, Straight),
+            P::Prgb4444_16u => Self::rgb(ARGB, U16, Premultiplied),
+            P::Xrgb4444_16u => Self::rgb(ARGB, U16, Opaque),
+            P::Bgra4444_32f | P::Bgra4444_32fLinear => Self::rgb(BGRA, F32, Straight),
+            P::Bgrp4444_32f | P::Bgrp4444_32fLinear => Self::rgb(BGRA, F32, Premultiplied),
+            P::Bgrx4444_32f | P::Bgrx4444_32fLinear => Self::rgb(BGRA, F32, Opaque),
+            P::Argb4444_32f | P::Argb4444_32fLinear => Self::rgb(ARGB, F32, Straight),
+            P::Prgb4444_32f | P::Prgb4444_32fLinear => Self::rgb(ARGB, F32, Premultiplied),
+            P::Xrgb4444_32f | P::Xrgb4444_32fLinear => Self::rgb(ARGB, F32, Opaque),
+
+            P::Vuya4444_8u      => Self::yuv(VUYA, U8, BT601, Straight),
+            P::Vuya4444_8u709   => Self::yuv(VUYA, U8, BT709, Straight),
+            P::Vuyp4444_8u      => Self::yuv(VUYA, U8, BT601, Premultiplied),
+            P::Vuyp4444_8u709   => Self::yuv(VUYA, U8, BT709, Premultiplied),
+            P::Vuyx4444_8u      => Self::yuv(VUYA, U8, BT601, Opaque),
+            P::Vuyx4444_8u709   => Self::yuv(VUYA, U8, BT709, Opaque),
+            P::Vuya4444_16u     => Self::yuv(VUYA, U16, BT601, Straight),
+            P::Vuya4444_32f     => Self::yuv(VUYA, F32, BT601, Straight),
+            P::Vuya4444_32f709  => Self::yuv(VUYA, F32, BT709, Straight),
+            P::Vuyp4444_32f     => Self::yuv(VUYA, F32, BT601, Premultiplied),
+            P::Vuyp4444_32f709  => Self::yuv(VUYA, F32, BT709, Premultiplied),
+            P::Vuyx4444_32f     => Self::yuv(VUYA, F32, BT601, Opaque),
+            P::Vuyx4444_32f709  => Self::yuv(VUYA, F32, BT709, Opaque),
+
+            P::Yuyv422_8u601                => Self::subsampled(YUYV, U8, BT601, false),
+            P::Yuyv422_8u709                => Self::subsampled(YUYV, U8, BT709, false),
+            P::Uyvy422_8u601                => Self::subsampled(UYVY, U8, BT601, false),
+            P::Uyvy422_8u709                => Self::subsampled(UYVY, U8, BT709, false),
+            P::Uyvy422_32f601               => Self::subsampled(UYVY, F32, BT601, false),
+            P::Uyvy422_32f709               => Self::subsampled(UYVY, F32, BT709, false),
+            P::Y210422_10uAs16u709          => Self::subsampled(YUYV, U16Msb10, BT709, false),
+            P::Y210422_10uAs16u709FullRange => Self::subsampled(YUYV, U16Msb10, BT709, true),
+            P::V210422_10u601               => Self::v210(BT601, false),
+            P::V210422_10u709               => Self::v210(BT709, false),
+            P::V210422_10u709FullRange      => Self::v210(BT709, true),
+
+            P::Rgb444_10u => Self::rgb_packed(Layout::Rgb10, 1023.0),
+            P::Rgb444_12uPq709 | P::Rgb444_12uPqP3 | P::Rgb444_12uPq2020 => Self::rgb_packed(Layout::Rgb12, 4095.0),
+            _ => return None,
+        })
+    }
+
+    fn row_size(&self, width: usize) -> usize {
+        match self.layout {
+            Layout::Interleaved { sample, .. } => width * 4 * sample.size(),
+            Layout::Subsampled { sample, .. } => width.div_ceil(2) * 4 * sample.size(),
+            Layout::V210 => width.div_ceil(6) * 16,
+            Layout::Rgb10 => width * 4,
+            Layout::Rgb12 => width * 6,
+        }
+    }
+
+    fn unpack_row(&self, src: &[u8], dst: &mut [[f32; 4]], alpha: Alpha) {
+        self.read(src, dst);
+
+        let ranges = self.ranges.map(|r| (r.offset, 1.0 / r.scale));
+        for px in dst.iter_mut() {
+            for (value, (offset, inv_scale)) in px.iter_mut().zip(ranges) {
+                *value = (*value - offset) * inv_scale;
+            }
+        }
+        if let Some(matrix) = self.matrix {
+            for px in dst.iter_mut() {
+                *px = matrix.yuv_to_rgb(*px);
+            }
+        }
+        match (self.alpha, alpha) {
+            (StoredAlpha::Opaque, _) => dst.iter_mut().for_each(|px| px[3] = 1.0),
+            (StoredAlpha::Straight, Alpha::Premultiplied) => premultiply(dst),
+            (StoredAlpha::Premultiplied, Alpha::Straight) => unpremultiply(dst),
+            _ => {}
+        }
+    }
+
+    fn pack_row(&self, src: &[[f32; 4]], dst: &mut [u8], alpha: Alpha) {
+        let mut chunk = [[0.0f32; 4]; CHUNK];
+        for (i, src) in src.chunks(CHUNK).enumerate() {
+            let chunk = &mut chunk[..src.len()];
+            chunk.copy_from_slice(src);
+            match (self.alpha, alpha) {
+                (StoredAlpha::Opaque, _) => chunk.iter_mut().for_each(|px| px[3] = 1.0),
+                (StoredAlpha::Premultiplied, Alpha::Straight) => premultiply(chunk),
+                (StoredAlpha::Straight, Alpha::Premultiplied) => unpremultiply(chunk),
+                _ => {}
+            }
+            if let Some(matrix) = self.matrix {
+                for px in chunk.iter_mut() {
+                    *px = matrix.rgb_to_yuv(*px);
+                }
+            }
+            for px in chunk.iter_mut() {
+                for (value, range) in px.iter_mut().zip(&self.ranges) {
+                    *value = *value * range.scale + range.offset;
+                }
+            }
+            let start = self.row_size(i * CHUNK);
+            self.write(chunk, &mut dst[start..start + self.row_size(chunk.len())]);
+        }
+    }
+
+    /// Reads code values of R or Y, G or Cb, B or Cr, and alpha.
+    fn read(&self, src: &[u8], dst: &mut [[f32; 4]]) {
+        match self.layout {
+            Layout::Interleaved { sample, order } => {
+                let size = sample.size();
+                for (px, bytes) in dst.iter_mut().zip(src.chunks_exact(4 * size)) {
+                    *px = order.map(|pos| sample.read(&bytes[pos * size..]));
+                }
+            }
+            Layout::Subsampled { sample, order } => {
+                let size = sample.size();
+                for (pair, bytes) in dst.chunks_mut(2).zip(src.chunks_exact(4 * size)) {
+                    let [y0, cb, y1, cr] = order.map(|pos| sample.read(&bytes[pos * size..]));
+                    pair[0] = [y0, cb, cr, 0.0];
+                    if let Some(px) = pair.get_mut(1) {
+                        *px = [y1, cb, cr, 0.0];
+                    }
+                }
+            }
+            Layout::V210 => {
+                for (group, bytes) in dst.chunks_mut(6).zip(src.chunks_exact(16)) {
+                    let mut values = [0.0f32; 12];
+                    for (i, word) in bytes.chunks_exact(4).enumerate() {
+                        let word = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+                        values[i * 3]     = (word & 0x3ff) as f32;
+                        values[i * 3 + 1] = ((word >> 10) & 0x3ff) as f32;
+                        values[i * 3 + 2] = ((word >> 20) & 0x3ff) as f32;
+                    }
+                    // Y of pixel i is at 2i + 1, Cb and Cr of pixel pair k at 4k and 4k + 2.
+                    for (i, px) in group.iter_mut().enumerate() {
+                        let k = i / 2;
+                        *px = [values[i * 2 + 1], values[k * 4], values[k * 4 + 2], 0.0];
+                    }
+                }
+            }
+            Layout::Rgb10 => {
+                for (px, bytes) in dst.iter_mut().zip(src.chunks_exact(4)) {
+                    let word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                    *px = [((word >> 20) & 0x3ff) as f32, ((word >> 10) & 0x3ff) as f32, (word & 0x3ff) as f32, 0.0];
+                }
+            }
+            Layout::Rgb12 => {
+                for (px, bytes) in dst.iter_mut().zip(src.chunks_exact(6)) {
+                    let c = |i: usize| (u16::from_le_bytes([bytes[i * 2], bytes[i * 2 + 1]]) & 0xfff) as f32;
+                    *px = [c(0), c(1), c(2), 0.0];
+                }
+            }
+        }
+    }
+
+    /// Writes unquantized code values of R or Y, G or Cb, B or Cr, and alpha.
+    fn write(&self, src: &[[f32; 4]], dst: &mut [u8]) {
+        let q = |value: f32| match self.max {
+            Some(max) => (value.clamp(0.0, max) + 0.5).floor(),
+            None => value,
+        };
+        match self.layout {
+            Layout::Interleaved { sample, order } => {
+                let size = sample.size();
+                for (px, bytes) in src.iter().zip(dst.chunks_exact_mut(4 * size)) {
+                    for c in 0..4 {
+                        sample.write(&mut bytes[order[c] * size..], q(px[c]));
+                    }
+                }
+            }
+            Layout::Subsampled { sample, order } => {
+                let size = sample.size();
+                for (pair, bytes) in src.chunks(2).zip(dst.chunks_exact_mut(4 * size)) {
+                    let (p0, p1) = (pair[0], *pair.last().unwrap());
+                    let values = [p0[0], (p0[1] + p1[1]) * 0.5, p1[0], (p0[2] + p1[2]) * 0.5];
+                    for c in 0..4 {
+                        sample.write(&mut bytes[order[c] * size..], q(values[c]));
+                    }
+                }
+            }
+            Layout::V210 => {
+                for (group, bytes) in src.chunks(6).zip(dst.chunks_exact_mut(16)) {
+                    let px = |i: usize| group[i.min(group.len() - 1)];
+                    let mut values = [0.0f32; 12];
+                    for i in 0..6 {
+                        values[i * 2 + 1] = px(i)[0];
+                    }
+                    for k in 0..3 {
+                        let (p0, p1) = (px(k * 2), px(k * 2 + 1));
+                        values[k * 4] = (p0[1] + p1[1]) * 0.5;
+                        values[k * 4 + 2] = (p0[2] + p1[2]) * 0.5;
+                    }
+                    for (i, word) in bytes.chunks_exact_mut(4).enumerate() {
+                        let c = |j: usize| q(values[i * 3 + j]) as u32;
+                        word.copy_from_slice(&(c(0) | (c(1) << 10) | (c(2) << 20)).to_le_bytes());
+                    }
+                }
+            }
+            Layout::Rgb10 => {
+                for (px, bytes) in src.iter().zip(dst.chunks_exact_mut(4)) {
+                    let word = ((q(px[0]) as u32) << 20) | ((q(px[1]) as u32) << 10) | q(px[2]) as u32;
+                    bytes.copy_from_slice(&word.to_le_bytes());
+                }
+            }
+            Layout::Rgb12 => {
+                for (px, bytes) in src.iter().zip(dst.chunks_exact_mut(6)) {
+                    for c in 0..3 {
+                        bytes[c * 2..c * 2 + 2].copy_from_slice(&(q(px[c]) as u16).to_le_bytes());
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Chroma {
+    /// Cb and Cr in separate planes.
+    Planar,
+    /// Cb and Cr interleaved in one plane, Cr first if `swapped`.
+    Biplanar { swapped: bool },
+}
+
+/// A planar or biplanar YUV format.
+#[derive(Clone, Copy)]
+struct PlanarFormat {
+    /// Sample, ranges and matrix, in an interleaved layout which isn't used.
+    yuv: Format,
+    sample: Sample,
+    chroma: Chroma,
+    /// Chroma rows alternate between the fields.
+    field_picture: bool,
+    /// 4:2:2, chroma isn't subsampled vertically.
+    full_height_chroma: bool,
+}
+
+impl PlanarFormat {
+    fn new(sample: Sample, chroma: Chroma, matrix: Matrix, field_picture: bool, full_range: bool) -> Self {
+        let yuv = Format::yuv(YUYV, sample, matrix, StoredAlpha::Opaque);
+        Self {
+            yuv: if full_range { yuv.full_range() } else { yuv },
+            sample,
+            chroma,
+            field_picture,
+            full_height_chroma: false,
+        }
+    }
+
+    fn describe(format: PixelFormat) -> Option<Self> {
+        use PixelFormat as P;
+        use Sample::*;
+        const PLANAR: Chroma = Chroma::Planar;
+        const UV: Chroma = Chroma::Biplanar { swapped: false };
+        const VU: Chroma = Chroma::Biplanar { swapped: true };
+        let (sample, chroma, matrix, field_picture, full_range) = match format {
+            P::Yuv420Mpeg2FramePicturePlanar8u601          | P::Yuv420Mpeg4FramePicturePlanar8u601          => (U8, PLANAR, BT601, false, false),
+            P::Yuv420Mpeg2FieldPicturePlanar8u601          | P::Yuv420Mpeg4FieldPicturePlanar8u601          => (U8, PLANAR, BT601, true,  false),
+            P::Yuv420Mpeg2FramePicturePlanar8u601FullRange | P::Yuv420Mpeg4FramePicturePlanar8u601FullRange => (U8, PLANAR, BT601, false, true),
+            P::Yuv420Mpeg2FieldPicturePlanar8u601FullRange | P::Yuv420Mpeg4FieldPicturePlanar8u601FullRange => (U8, PLANAR, BT601, true,  true),
+            P::Yuv420Mpeg2FramePicturePlanar8u709          | P::Yuv420Mpeg4FramePicturePlanar8u709          => (U8, PLANAR, BT709, false, false),
+            P::Yuv420Mpeg2FieldPicturePlanar8u709          | P::Yuv420Mpeg4FieldPicturePlanar8u709          => (U8, PLANAR, BT709, true,  false),
+            P::Yuv420Mpeg2FramePicturePlanar8u709FullRange | P::Yuv420Mpeg4FramePicturePlanar8u709FullRange => (U8, PLANAR, BT709, false, true),
+            P::Yuv420Mpeg2FieldPicturePlanar8u709FullRange | P::Yuv420Mpeg4FieldPicturePlanar8u709FullRange => (U8, PLANAR, BT709, true,  true),
+
+            P::Yuv420Mpeg4FramePictureBiplanar8u601          => (U8, UV, BT601, false, false),
+            P::Yuv420Mpeg4FieldPictureBiplanar8u601          => (U8, UV, BT601, true,  false),
+            P::Yuv420Mpeg4FramePictureBiplanar8u601FullRange => (U8, UV, BT601, false, true),
+            P::Yuv420Mpeg4FieldPictureBiplanar8u601FullRange => (U8, UV, BT601, true,  true),
+            P::Yuv420Mpeg4FramePictureBiplanar8u709          => (U8, UV, BT709, false, false),
+            P::Yuv420Mpeg4FieldPictureBiplanar8u709          => (U8, UV, BT709, true,  false),
+            P::Yuv420Mpeg4FramePictureBiplanar8u709FullRange => (U8, UV, BT709, false, true),
+            P::Yuv420Mpeg4FieldPictureBiplanar8u709FullRange => (U8, UV, BT709, true,  true),
+            P::Yvu420Mpeg4FramePictureBiplanar8u601          => (U8, VU, BT601, false, false),
+            P::Yvu420Mpeg4FieldPictureBiplanar8u601          => (U8, VU, BT601, true,  false),
+            P::Yvu420Mpeg4FramePictureBiplanar8u601FullRange => (U8, VU, BT601, false, true),
+            P::Yvu420Mpeg4FieldPictureBiplanar8u601FullRange => (U8, VU, BT601, true,  true),
+            P::Yvu420Mpeg4FramePictureBiplanar8u709          => (U8, VU, BT709, false, false),
+            P::Yvu420Mpeg4FieldPictureBiplanar8u709          => (U8, VU, BT709, true,  false),
+            P::Yvu420Mpeg4FramePictureBiplanar8u709FullRange => (U8, VU, BT709, false, true),
+            P::Yvu420Mpeg4FieldPictureBiplanar8u709FullRange => (U8, VU, BT709, true,  true),
+
+            P::Yuv420Mpeg4FramePictureBiplanar10uAs16u709          => (U16Msb10, UV, BT709, false, false),
+            P::Yuv420Mpeg4FieldPictureBiplanar10uAs16u709          => (U16Msb10, UV, BT709, true,  false),
+            P::Yuv420Mpeg4FramePictureBiplanar10uAs16u709FullRange => (U16Msb10, UV, BT709, false, true),
+            P::Yuv420Mpeg4FieldPictureBiplanar10uAs16u709FullRange => (U16Msb10, UV, BT709, true,  true),
+            // The HDR formats differ in their transfer function only, which is kept.
+            P::Yuv420Mpeg4FramePictureBiplanar10uAs16u2020 | P::Yuv420Mpeg4FramePictureBiplanar10uAs16u2020HDR | P::Yuv420Mpeg4FramePictureBiplanar10uAs16u2020HDRHLG => {
+                (U16Msb10, UV, BT2020, false, false)
+            }
+            P::Yuv420Mpeg4FieldPictureBiplanar10uAs16u2020 | P::Yuv420Mpeg4FieldPictureBiplanar10uAs16u2020HDR | P::Yuv420Mpeg4FieldPictureBiplanar10uAs16u2020HDRHLG => {
+                (U16Msb10, UV, BT2020, true, false)
+            }
+            P::Yuv420Mpeg4FramePictureBiplanar10uAs16u2020FullRange | P::Yuv420Mpeg4FramePictureBiplanar10uAs16u2020HDRFullRange | P::Yuv420Mpeg4FramePictureBiplanar10uAs16u2020HDRHLGFullRange => {
+                (U16Msb10, UV, BT2020, false, true)
+            }
+            P::Yuv420Mpeg4FieldPictureBiplanar10uAs16u2020FullRange | P::Yuv420Mpeg4FieldPictureBiplanar10uAs16u2020HDRFullRange | P::Yuv420Mpeg4FieldPictureBiplanar10uAs16u2020HDRHLGFullRange => {
+                (U16Msb10, UV, BT2020, true, true)
+            }
+
+            P::P210422_10u709 => {
+                return Some(Self { full_height_chroma: true, ..Self::new(U16Msb10, UV, BT709, false, false) });
+            }
+            _ => return None,
+        };
+        Some(Self::new(sample, chroma, matrix, field_picture, full_range))
+    }
+
+    /// The chroma row of luma row `y`.
+    fn chroma_row(&self, y: usize) -> usize {
+        if self.full_height_chroma {
+            y
+        } else if self.field_picture {
+            // Row `y` is row `y / 2` of field `y % 2`, and each field has its own chroma rows.
+            y / 4 * 2 + y % 2
+        } else {
+            y / 2
+        }
+    }
+
+    fn chroma_rows(&self, height: usize) -> usize {
+        (0..height).map(|y| self.chroma_row(y) + 1).max().unwrap_or(0)
+    }
+
+    /// Bytes of a row of the Y, U and V planes.
+    fn row_sizes(&self, width: usize) -> [usize; 3] {
+        let size = self.sample.size();
+        let chroma = width.div_ceil(2) * size;
+        match self.chroma {
+            Chroma::Planar => [width * size, chroma, chroma],
+            Chroma::Biplanar { .. } => [width * size, chroma * 2, 0],
+        }
+    }
+
+    fn check_planes(&self, lens: [usize; 3], row_bytes: [usize; 3], width: usize, height: usize) -> Result<(), Error> {
+        let rows = [height, self.chroma_rows(height), self.chroma_rows(height)];
+        for (((len, row_bytes), row_size), rows) in lens.into_iter().zip(row_bytes).zip(self.row_sizes(width)).zip(rows) {
+            if row_size > 0 {
+                check_frame_size(len, row_bytes, row_size, rows)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn plane_sizes(&self, buffers: &YUV420PlanarBuffers, width: usize, height: usize) -> Result<[usize; 3], Error> {
+        let pointers = [buffers.y_data, buffers.u_data, buffers.v_data];
+        let row_bytes = [buffers.y_row_bytes, buffers.u_row_bytes, buffers.v_row_bytes].map(|r| r as usize);
+        let rows = [height, self.chroma_rows(height), self.chroma_rows(height)];
+        let mut sizes = [0; 3];
+        for i in 0..3 {
+            let row_size = self.row_sizes(width)[i];
+            if row_size == 0 || rows[i] == 0 {
+                continue;
+            }
+            if pointers[i].is_null() || row_bytes[i] < row_size {
+                return Err(Error::InvalidParms);
+            }
+            sizes[i] = (rows[i] - 1) * row_bytes[i] + row_size;
+        }
+        Ok(sizes)
+    }
+
+    /// Offsets of Cb and Cr of chroma sample `x` in their rows.
+    fn chroma_offsets(&self, x: usize) -> [usize; 2] {
+        let size = self.sample.size();
+        match self.chroma {
+            Chroma::Planar => [x * size, x * size],
+            Chroma::Biplanar { swapped: false } => [x * 2 * size, (x * 2 + 1) * size],
+            Chroma::Biplanar { swapped: true } => [(x * 2 + 1) * size, x * 2 * size],
+        }
+    }
+
+    fn unpack_row(&self, src: &Yuv420Planes<&[u8]>, y: usize, dst: &mut [[f32; 4]]) {
+        let c = self.chroma_row(y);
+        let luma = &src.y[y * src.y_row_bytes..];
+        let cb = &src.u[c * src.u_row_bytes..];
+        let cr = match self.chroma {
+            Chroma::Planar => &src.v[c * src.v_row_bytes..],
+            Chroma::Biplanar { .. } => cb,
+        };
+        let size = self.sample.size();
+        let ranges = self.yuv.ranges.map(|r| (r.offset, 1.0 / r.scale));
+        let matrix = self.yuv.matrix.unwrap_or(BT709);
+        for (x, px) in dst.iter_mut().enumerate() {
+            let [cb_offset, cr_offset] = self.chroma_offsets(x / 2);
+            let code = [self.sample.read(&luma[x * size..]), self.sample.read(&cb[cb_offset..]), self.sample.read(&cr[cr_offset..]), 1.0];
+            let mut yuv = [0.0; 4];
+            for (value, (code, (offset, inv_scale))) in yuv.iter_mut().zip(code.into_iter().zip(ranges)) {
+                *value = (code - offset) * inv_scale;
+            }
+            yuv[3] = 1.0;
+            *px = matrix.yuv_to_rgb(yuv);
+        }
+    }
+
+    fn pack(&self, src: &[[f32; 4]], width: usize, height: usize, dst: &mut Yuv420Planes<&mut [u8]>) {
+        let size = self.sample.size();
+        let max = self.yuv.max.unwrap_or(1.0);
+        let q = |value: f32| (value.clamp(0.0, max) + 0.5).floor();
+        let code = |value: f32, range: Range| q(value * range.scale + range.offset);
+        let matrix = self.yuv.matrix.unwrap_or(BT709);
+
+        // Sums of Cb and Cr, and the number of pixels, of every chroma sample.
+        let chroma_width = width.div_ceil(2);
+        let mut chroma = vec![[0.0f32; 3]; chroma_width * self.chroma_rows(height)];
+        for (y, row) in src.chunks_exact(width).enumerate() {
+            let luma = &mut dst.y[y * dst.y_row_bytes..];
+            let sums = &mut chroma[self.chroma_row(y) * chroma_width..][..chroma_width];
+            for (x, px) in row.iter().enumerate() {
+                let [luma_value, cb, cr, _] = matrix.rgb_to_yuv(*px);
+                self.sample.write(&mut luma[x * size..], code(luma_value, self.yuv.ranges[0]));
+                let sum = &mut sums[x / 2];
+                sum[0] += cb;
+                sum[1] += cr;
+                sum[2] += 1.0;
+            }
+        }
+        for (c, sums) in chroma.chunks_exact(chroma_width).enumerate() {
+            for (x, &[cb, cr, count]) in sums.iter().enumerate() {
+                let count = count.max(1.0);
+                let [cb_offset, cr_offset] = self.chroma_offsets(x);
+                let (cb, cr) = (code(cb / count, self.yuv.ranges[1]), code(cr / count, self.yuv.ranges[2]));
+                self.sample.write(&mut dst.u[c * dst.u_row_bytes + cb_offset..], cb);
+                match self.chroma {
+                    Chroma::Planar => self.sample.write(&mut dst.v[c * dst.v_row_bytes + cr_offset..], cr),
+                    Chroma::Biplanar { .. } => self.sample.write(&mut dst.u[c * dst.u_row_bytes + cr_offset..], cr),
+                }
+            }
+        }
+    }
+}
+
+fn premultiply(pixels: &mut [[f32; 4]]) {
+    for px in pixels {
+        let a = px[3];
+        px[0] *= a;
+        px[1] *= a;
+        px[2] *= a;
+    }
+}
+
+fn unpremultiply(pixels: &mut [[f32; 4]]) {
+    for px in pixels {
+        let a = px[3];
+        let inv = if a > 0.0 { 1.0 / a } else { 0.0 };
+        px[0] *= inv;
+        px[1] *= inv;
+        px[2] *= inv;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pack_px(format: PixelFormat, src: &[[f32; 4]]) -> Vec<u8> {
+        let mut bytes = vec![0; row_size(format, src.len()).unwrap()];
+        pack_row(format, src, &mut bytes, Alpha::Straight).unwrap();
+        bytes
+    }
+
+    fn unpack_px(format: PixelFormat, src: &[u8], width: usize) -> Vec<[f32; 4]> {
+        let mut pixels = vec![[0.0; 4]; width];
+        unpack_row(format, src, &mut pixels, Alpha::Straight).unwrap();
+        pixels
+    }
+
+    fn assert_close(a: [f32; 4], b: [f32; 4], tolerance: f32, context: &str) {
+        assert!(a.iter().zip(b).all(|(a, b)| (a - b).abs() <= tolerance), "{context}: {a:?} != {b:?}");
+    }
+
+    #[test]
+    fn white_and_black_are_studio_range_in_8_bit_601() {
+        assert_eq!(pack_px(PixelFormat::Vuya4444_8u, &[[1.0; 4]]), [128, 128, 235, 255]);
+        assert_eq!(pack_px(PixelFormat::Vuya4444_8u, &[[0.0, 0.0, 0.0, 1.0]]), [128, 128, 16, 255]);
+        assert_eq!(pack_px(PixelFormat::Uyvy422_8u601, &[[1.0; 4]; 2]), [128, 235, 128, 235]);
+        assert_close(unpack_px(PixelFormat::Vuya4444_8u, &[128, 128, 235, 255], 1)[0], [1.0; 4], 1e-6, "white");
+    }
+
+    #[test]
+    fn yuv_components_are_ordered_by_format() {
+        // Red in BT.601 is Y 81, Cb 90, Cr 240.
+        let red = [[1.0, 0.0, 0.0, 1.0]; 2];
+        assert_eq!(pack_px(PixelFormat::Vuya4444_8u,   &red[..1]), [240, 90, 81, 255]);
+        assert_eq!(pack_px(PixelFormat::Uyvy422_8u601, &red), [90, 81, 240, 81]);
+        assert_eq!(pack_px(PixelFormat::Yuyv422_8u601, &red), [81, 90, 81, 240]);
+        assert_eq!(pack_px(PixelFormat::Bgra4444_8u,   &red[..1]), [0, 0, 255, 255]);
+        assert_eq!(pack_px(PixelFormat::Argb4444_8u,   &red[..1]), [255, 255, 0, 0]);
+    }
+
+    #[test]
+    fn v210_group_is_unpacked_from_hand_packed_words() {
+        let y = [64, 240, 415, 590, 765, 940];
+        // Only the first pixel pair has chroma, Cr 960 makes it red.
+        let (cb, cr) = ([512, 512, 512], [960, 512, 512]);
+        let words = [
+            cb[0] | y[0] << 10 | cr[0] << 20,
+            y[1] | cb[1] << 10 | y[2] << 20,
+            cr[1] | y[3] << 10 | cb[2] << 20,
+            y[4] | cr[2] << 10 | y[5] << 20,
+        ];
+        let bytes = words.iter().flat_map(|w: &u32| w.to_le_bytes()).collect::<Vec<_>>();
+        let pixels = unpack_px(PixelFormat::V210422_10u601, &bytes, 6);
+        for (i, px) in pixels.iter().enumerate() {
+            let luma = (y[i] as f32 - 64.0) / 876.0;
+            if i < 2 {
+                assert!(px[0] > luma + 0.5 && px[1] < luma, "pixel {i}: {px:?}");
+            } else {
+                assert_close(*px, [luma, luma, luma, 1.0], 1e-5, &format!("pixel {i}"));
+            }
+        }
+        assert_eq!(pack_px(PixelFormat::V210422_10u601, &pixels), bytes);
+    }
+
+    #[test]
+    fn packed_rgb_layouts() {
+        let px = [[1.0, 0.5, 0.0, 1.0]];
+        // R in bits 20-29, G in 10-19 and B in 0-9 of a little-endian word.
+        let rgb10 = (1023u32 << 20 | 512 << 10).to_le_bytes();
+        assert_eq!(pack_px(PixelFormat::Rgb444_10u, &px), rgb10);
+        assert_close(unpack_px(PixelFormat::Rgb444_10u, &rgb10, 1)[0], [1.0, 512.0 / 1023.0, 0.0, 1.0], 1e-6, "rgb10");
+
+        // R, G and B in the low 12 bits of little-endian 16-bit words.
+        let rgb12 = [0xff, 0x0f, 0x00, 0x08, 0x00, 0x00];
+        assert_eq!(pack_px(PixelFormat::Rgb444_12uPq2020, &px), rgb12);
+        let high_bits_set = [0xff, 0xff, 0x00, 0xf8, 0x00, 0xf0];
+        assert_close(unpack_px(PixelFormat::Rgb444_12uPq2020, &high_bits_set, 1)[0], [1.0, 2048.0 / 4095.0, 0.0, 1.0], 1e-6, "rgb12");
+    }
+
+    #[test]
+    fn all_supported_formats_round_trip() {
+        // Pairs of pixels share chroma in subsampled formats, so every color is repeated.
+        let colors = [
+            [1.0, 1.0, 1.0, 1.0],
+            [0.0, 0.0, 0.0, 1.0],
+            [0.8, 0.2, 0.1, 0.5],
+            [0.1, 0.6, 0.3, 0.8],
+            [0.25, 0.5, 0.75, 1.0],
+            [0.9, 0.9, 0.2, 0.6],
+        ];
+        let src = colors.iter().flat_map(|&c| [c, c]).collect::<Vec<_>>();
+        for &format in SUPPORTED_PIXEL_FORMATS {
+            assert!(is_supported(format), "{format:?}");
+            let description = Format::describe(format).unwrap();
+            let tolerance = match description.max {
+                Some(max) if max < 256.0 => 0.02,
+                Some(max) if max < 1024.0 => 0.005,
+                Some(_) => 0.002,
+                None => 1e-5,
+            };
+            let packed = pack_px(format, &src);
+            let unpacked = unpack_px(format, &packed, src.len());
+            for (i, (&expected, &actual)) in src.iter().zip(&unpacked).enumerate() {
+                let expected = if description.alpha == StoredAlpha::Opaque { [expected[0], expected[1], expected[2], 1.0] } else { expected };
+                assert_close(actual, expected, tolerance, &format!("{format:?} pixel {i}"));
+            }
+            if description.max.is_some() {
+                assert_eq!(pack_px(format, &unpacked), packed, "{format:?}");
+            }
+        }
+    }
+
+    /// Packs `src` into tightly packed planes.
+    fn pack_planes(format: PixelFormat, src: &[[f32; 4]], width: usize) -> [Vec<u8>; 3] {
+        let description = PlanarFormat::describe(format).unwrap();
+        let chroma_rows = description.chroma_rows(src.len() / width);
+        let [y_row, u_row, v_row] = description.row_sizes(width);
+        let (mut y, mut u, mut v) = (vec![0; y_row * src.len() / width], vec![0; u_row * chroma_rows], vec![0; v_row * chroma_rows]);
+        let mut planes = Yuv420Planes { y: &mut y[..], y_row_bytes: y_row, u: &mut u[..], u_row_bytes: u_row, v: &mut v[..], v_row_bytes: v_row };
+        pack_yuv420(format, src, width, &mut planes).unwrap();
+        [y, u, v]
+    }
+
+    fn unpack_planes(format: PixelFormat, [y, u, v]: &[Vec<u8>; 3], width: usize, height: usize) -> Vec<[f32; 4]> {
+        let [y_row, u_row, v_row] = PlanarFormat::describe(format).unwrap().row_sizes(width);
+        let planes = Yuv420Planes { y: &y[..], y_row_bytes: y_row, u: &u[..], u_row_bytes: u_row, v: &v[..], v_row_bytes: v_row };
+        let mut dst = vec![[0.0; 4]; width * height];
+        unpack_yuv420(format, &planes, &mut dst, width).unwrap();
+        dst
+    }
+
+    #[test]
+    fn planar_601_is_studio_range() {
+        let format = PixelFormat::Yuv420Mpeg2FramePicturePlanar8u601;
+        assert_eq!(pack_planes(format, &[[1.0; 4]; 4], 2), [vec![235; 4], vec![128], vec![128]]);
+        assert_eq!(pack_planes(format, &[[0.0, 0.0, 0.0, 1.0]; 4], 2), [vec![16; 4], vec![128], vec![128]]);
+        assert_eq!(pack_planes(format, &[[1.0, 0.0, 0.0, 1.0]; 4], 2), [vec![81; 4], vec![90], vec![240]]);
+
+        let full_range = PixelFormat::Yuv420Mpeg2FramePicturePlanar8u601FullRange;
+        assert_eq!(pack_planes(full_range, &[[1.0; 4]; 4], 2), [vec![255; 4], vec![128], vec![128]]);
+    }
+
+    #[test]
+    fn chroma_is_averaged_over_the_pixels_it_covers() {
+        let format = PixelFormat::Yuv420Mpeg2FramePicturePlanar8u601FullRange;
+        let src = [[1.0, 0.0, 0.0, 1.0], [0.0, 0.0, 1.0, 1.0], [1.0, 0.0, 0.0, 1.0], [0.0, 0.0, 1.0, 1.0]];
+        let averaged = pack_planes(format, &[[0.5, 0.0, 0.5, 1.0]; 4], 2);
+        let [_, u, v] = pack_planes(format, &src, 2);
+        assert_eq!([u, v], [averaged[1].clone(), averaged[2].clone()]);
+    }
+
+    #[test]
+    fn yvu_stores_cr_first() {
+        let red = [[1.0, 0.0, 0.0, 1.0]; 4];
+        let [_, uv, _] = pack_planes(PixelFormat::Yuv420Mpeg4FramePictureBiplanar8u601, &red, 2);
+        let [_, vu, _] = pack_planes(PixelFormat::Yvu420Mpeg4FramePictureBiplanar8u601, &red, 2);
+        assert_eq!(uv, [90, 240]);
+        assert_eq!(vu, [240, 90]);
+    }
+
+    #[test]
+    fn field_pictures_have_chroma_per_field() {
+        // Rows 0 and 2 are the first field and share chroma row 0, rows 1 and 3 share chroma row 1.
+        let format = PixelFormat::Yuv420Mpeg2FieldPicturePlanar8u601FullRange;
+        let (red, blue) = ([1.0, 0.0, 0.0, 1.0], [0.0, 0.0, 1.0, 1.0]);
+        let src = [red, red, blue, blue, red, red, blue, blue];
+        let [_, u, v] = pack_planes(format, &src, 2);
+        let [_, red_u, red_v] = pack_planes(format, &[red; 4], 2);
+        let [_, blue_u, blue_v] = pack_planes(format, &[blue; 4], 2);
+        assert_eq!(u, [red_u[0], blue_u[0]]);
+        assert_eq!(v, [red_v[0], blue_v[0]]);
+    }
+
+    #[test]
+    fn p210_has_chroma_per_row() {
+        let format = PixelFormat::P210422_10u709;
+        let src = [[1.0, 0.0, 0.0, 1.0], [1.0, 0.0, 0.0, 1.0], [0.0, 0.0, 1.0, 1.0], [0.0, 0.0, 1.0, 1.0]];
+        let [y, uv, _] = pack_planes(format, &src, 2);
+        assert_eq!((y.len(), uv.len()), (8, 8));
+        assert_close(unpack_planes(format, &[y, uv, vec![]], 2, 2)[2], src[2], 0.005, "P210");
+    }
+
+    #[test]
+    fn bt2020_uses_its_own_luma_weights() {
+        let [y, _, _] = pack_planes(PixelFormat::Yuv420Mpeg4FramePictureBiplanar10uAs16u2020, &[[1.0, 0.0, 0.0, 1.0]; 4], 2);
+        // 64 + 876 * 0.2627, with 10 bit in the most significant bits.
+        assert_eq!(u16::from_ne_bytes([y[0], y[1]]), 294 << 6);
+    }
+
+    #[test]
+    fn planes_are_taken_from_buffers() {
+        let format = PixelFormat::Yuv420Mpeg4FramePictureBiplanar8u709;
+        let (mut y, mut uv) = (vec![0u8; 6 * 4], vec![0u8; 6 * 2]);
+        let buffers = YUV420PlanarBuffers {
+            y_data: y.as_mut_ptr() as _,
+            y_row_bytes: 6,
+            u_data: uv.as_mut_ptr() as _,
+            u_row_bytes: 6,
+            v_data: std::ptr::null_mut(),
+            v_row_bytes: 0,
+        };
+        let mut planes = unsafe { Yuv420Planes::from_buffers_mut(&buffers, format, 4, 4) }.unwrap();
+        assert_eq!((planes.y.len(), planes.u.len(), planes.v.len()), (22, 10, 0));
+        pack_yuv420(format, &[[1.0; 4]; 16], 4, &mut planes).unwrap();
+        assert_eq!(y[..4], [235; 4]);
+        assert_eq!(y[4..6], [0; 2]);
+
+        let planes = unsafe { Yuv420Planes::from_buffers(&buffers, format, 4, 4) }.unwrap();
+        let mut dst = vec![[0.0; 4]; 16];
+        unpack_yuv420(format, &planes, &mut dst, 4).unwrap();
+        assert_close(dst[15], [1.0; 4], 0.01, "white");
+
+        let null = YUV420PlanarBuffers { u_data: std::ptr::null_mut(), ..buffers };
+        assert!(unsafe { Yuv420Planes::from_buffers(&null, format, 4, 4) }.is_err());
+        assert!(unsafe { Yuv420Planes::from_buffers(&buffers, PixelFormat::Bgra4444_8u, 4, 4) }.is_err());
+    }
+
+    #[test]
+    fn all_supported_yuv420_formats_round_trip() {
+        // Chroma is shared by 2x2 pixels, and by rows of both fields in field pictures, so colors change every two columns only.
+        let colors = [[1.0, 1.0, 1.0, 1.0], [0.0, 0.0, 0.0, 1.0], [0.8, 0.2, 0.1, 1.0], [0.25, 0.5, 0.75, 1.0]];
+        let (width, height) = (colors.len() * 2, 4);
+        let src = (0..height).flat_map(|_| colors.iter().flat_map(|&c| [c, c])).collect::<Vec<_>>();
+        for &format in SUPPORTED_YUV420_PIXEL_FORMATS {
+            assert!(is_supported_yuv420(format), "{format:?}");
+            assert!(!is_supported(format), "{format:?}");
+            let tolerance = if matches!(PlanarFormat::describe(format).unwrap().sample, Sample::U8) { 0.02 } else { 0.005 };
+            let packed = pack_planes(format, &src, width);
+            let unpacked = unpack_planes(format, &packed, width, height);
+            for (i, (&expected, &actual)) in src.iter().zip(&unpacked).enumerate() {
+                assert_close(actual, expected, tolerance, &format!("{format:?} pixel {i}"));
+            }
+            assert_eq!(pack_planes(format, &unpacked, width), packed, "{format:?}");
+        }
+    }
+}
@@ -1,6 +1,8 @@
 use crate::*;
 use pr_sys::*;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 
 #[derive(Debug, Clone)]
 pub struct ClipOperator {
@@ -21,6 +23,116 @@ pub struct EffectDetails {
 
 pub type ClipOperatorsMap = HashMap<i32, ClipOperator>;
 
+/// An owned copy of the video segments of a sequence, see [`VideoSequenceParser::parse_timeline()`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineGraph {
+    pub hash: String,
+    pub segments: Vec<Segment>,
+}
+
+/// A time range of the sequence in which the same nodes are rendered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Segment {
+    pub start_time: PrTime,
+    pub end_time: PrTime,
+    /// The offset of node time from sequence time in this segment
+    pub segment_offset: PrTime,
+    pub hash: String,
+    pub node: Node,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum NodeType {
+    Compositor,
+    Clip,
+    Media,
+    Effect,
+    AdjustmentEffect,
+    Adjustment,
+    Transition,
+    SolidColor,
+    Multicam,
+    Preview,
+    Disabled,
+    Unknown(String),
+}
+impl From<&str> for NodeType {
+    fn from(s: &str) -> Self {
+        // The node type is read from a fixed size buffer, so drop the terminator and everything after it.
+        let s = s.split('\0').next().unwrap_or_default();
+        let is = |id: &[u8]| s.as_bytes() == &id[..id.len() - 1];
+        if      is(kVideoSegment_NodeType_Compositor)       { Self::Compositor }
+        else if is(kVideoSegment_NodeType_Clip)             { Self::Clip }
+        else if is(kVideoSegment_NodeType_Media)            { Self::Media }
+        else if is(kVideoSegment_NodeType_Effect)           { Self::Effect }
+        else if is(kVideoSegment_NodeType_AdjustmentEffect) { Self::AdjustmentEffect }
+        else if is(kVideoSegment_NodeType_Adjustment)       { Self::Adjustment }
+        else if is(kVideoSegment_NodeType_Transition)       { Self::Transition }
+        else if is(kVideoSegment_NodeType_SolidColor)       { Self::SolidColor }
+        else if is(kVideoSegment_NodeType_Multicam)         { Self::Multicam }
+        else if is(kVideoSegment_NodeType_Preview)          { Self::Preview }
+        else if is(kVideoSegment_NodeType_Disabled)         { Self::Disabled }
+        else { Self::Unknown(s.to_owned()) }
+    }
+}
+
+/// A node of the segment tree: a compositor with the clips of the tracks as inputs, a clip with its media as input and its effects as operators, and so on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Node {
+    pub node_type: NodeType,
+    pub hash: String,
+    /// See `PrNodeInfoFlag`
+    pub flags: i32,
+    pub properties: Vec<(Property, PropertyData)>,
+    pub inputs: Vec<NodeInput>,
+    pub operators: Vec<Node>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeInput {
+    /// The time offset relative to the parent node
+    pub offset: PrTime,
+    pub node: Node,
+}
+
+impl Node {
+    pub fn property(&self, key: &Property) -> Option<&PropertyData> {
+        self.properties.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+    /// The start and end of the track item of a clip node in sequence time, in ticks.
+    pub fn track_item_range(&self) -> Option<(i64, i64)> {
+        match (self.property(&Property::Clip_TrackItemStartAsTicks)?, self.property(&Property::Clip_TrackItemEndAsTicks)?) {
+            (PropertyData::Int64(start), PropertyData::Int64(end)) => Some((*start, *end)),
+            _ => None,
+        }
+    }
+    /// Visits this node, its inputs and its operators, depth first.
+    pub fn visit(&self, f: &mut impl FnMut(&Node)) {
+        f(self);
+        for input in &self.inputs {
+            input.node.visit(f);
+        }
+        for operator in &self.operators {
+            operator.visit(f);
+        }
+    }
+}
+
+fn guid_string(id: &prPluginID) -> String {
+    let bytes = id.mGUID.iter().map(|&c| c as u8).take_while(|&c| c != 0).collect::<Vec<u8>>();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Releases a node ID on drop, so it's released on errors too.
+struct AcquiredNode<'a>(&'a suites::VideoSegment, i32);
+impl Drop for AcquiredNode<'_> {
+    fn drop(&mut self) {
+        if let Err(e) = self.0.release_video_node_id(self.1) {
+            log::error!("Failed to release video node {}: {e:?}", self.1);
+        }
+    }
+}
+
 pub struct VideoSequenceParser {
     segment_suite: suites::VideoSegment,
 }
@@ -89,4 +201,73 @@ impl VideoSequenceParser {
 
         Ok(operators_map)
     }
+
+    /// Parses the whole segment tree of a sequence.
+    /// * `timeline_data` - The plugin timeline ID for the sequence
+    pub fn parse_timeline(&self, timeline_data: PrTimelineID) -> Result<TimelineGraph, Error> {
+        let video_segments_id = self.segment_suite.acquire_video_segments_id(timeline_data)?;
+        let graph = self.parse_video_segments(video_segments_id);
+        if let Err(e) = self.segment_suite.release_video_segments_id(video_segments_id) {
+            log::error!("Failed to release video segments {video_segments_id}: {e:?}");
+        }
+        graph
+    }
+
+    /// Parses the segment tree of a Video Segments ID, like one with previews. The ID is not released.
+    pub fn parse_video_segments(&self, video_segments_id: i32) -> Result<TimelineGraph, Error> {
+        let hash = self.segment_suite.hash(video_segments_id)?;
+        let count = self.segment_suite.segment_count(video_segments_id)?;
+        let mut segments = Vec::with_capacity(count.max(0) as usize);
+        for index in 0..count {
+            let (start_time, end_time, segment_offset, mut segment_hash) = self.segment_suite.segment_info(video_segments_id, index)?;
+            let node = AcquiredNode(&self.segment_suite, self.segment_suite.acquire_node_id(video_segments_id, &mut segment_hash)?);
+            segments.push(Segment {
+                start_time,
+                end_time,
+                segment_offset,
+                hash: guid_string(&segment_hash),
+                node: self.parse_node(node.1)?,
+            });
+        }
+        Ok(TimelineGraph {
+            hash: guid_string(&hash),
+            segments,
+        })
+    }
+
+    /// Parses a node with all its properties, inputs and operators, recursively.
+    /// The ID is not released, but all the IDs acquired for the inputs and operators are.
+    pub fn parse_node(&self, video_node_id: i32) -> Result<Node, Error> {
+        let (node_type, hash, flags) = self.segment_suite.node_info(video_node_id)?;
+
+        let properties = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let collected = properties.clone();
+        self.segment_suite.iterate_node_properties(video_node_id, move |key, value| collected.lock().push((key, value)))?;
+        let properties = std::mem::take(&mut *properties.lock());
+
+        let mut inputs = Vec::new();
+        for index in 0..self.segment_suite.node_input_count(video_node_id)? {
+            let (offset, input_id) = self.segment_suite.acquire_input_node_id(video_node_id, index)?;
+            let input = AcquiredNode(&self.segment_suite, input_id);
+            inputs.push(NodeInput {
+                offset,
+                node: self.parse_node(input.1)?,
+            });
+        }
+
+        let mut operators = Vec::new();
+        for index in 0..self.segment_suite.node_operator_count(video_node_id)? {
+            let operator = AcquiredNode(&self.segment_suite, self.segment_suite.acquire_operator_node_id(video_node_id, index)?);
+            operators.push(self.parse_node(operator.1)?);
+        }
+
+        Ok(Node {
+            node_type: NodeType::from(node_type.as_str()),
+            hash: guid_string(&hash),
+            flags,
+            properties,
+            inputs,
+            operators,
+        })
+    }
 }